@@ -1,40 +1,59 @@
+use metamath_rs::unicode::UnicodeRenderer;
 use metamath_rs::{as_str, Database, StatementRef, StatementType};
 
 pub fn list_statements(
     db: &Database,
     label_test: impl Fn(&[u8]) -> bool,
+    renderer: Option<&UnicodeRenderer>,
     out: &mut impl std::io::Write,
 ) -> Result<(), std::io::Error> {
     let separator = "-".repeat(79);
-    let scope = db.scope_result();
-    let name = db.name_result();
     for stmt in db.statements() {
         if let StatementType::Axiom | StatementType::Provable = stmt.statement_type() {
-            let label = stmt.label();
-            if label_test(label) {
-                if let Some(frame) = scope.get(label) {
-                    for (ix1, ix2) in &*frame.mandatory_dv {
-                        writeln!(
-                            out,
-                            "$d {} {} $.",
-                            as_str(name.atom_name(frame.var_list[*ix1])),
-                            as_str(name.atom_name(frame.var_list[*ix2]))
-                        )?;
-                    }
-                    for hyp in frame.hypotheses.iter().skip(frame.mandatory_count) {
-                        write_statement(db.statement_by_address(hyp.address()), out)?;
-                    }
-                    write_statement(stmt, out)?;
-                    writeln!(out, "{}", separator)?;
-                }
+            if label_test(stmt.label()) && write_frame(db, stmt, renderer, out)? {
+                writeln!(out, "{}", separator)?;
             }
         }
     }
     Ok(())
 }
 
+/// Writes an assertion together with its distinct variable conditions and
+/// essential hypotheses. Returns `false` if the assertion has no frame.
+pub fn write_frame(
+    db: &Database,
+    stmt: StatementRef,
+    renderer: Option<&UnicodeRenderer>,
+    out: &mut impl std::io::Write,
+) -> Result<bool, std::io::Error> {
+    let name = db.name_result();
+    let Some(frame) = db.scope_result().get(stmt.label()) else {
+        return Ok(false);
+    };
+    for (ix1, ix2) in &*frame.mandatory_dv {
+        let v1 = name.atom_name(frame.var_list[*ix1]);
+        let v2 = name.atom_name(frame.var_list[*ix2]);
+        if let Some(renderer) = renderer {
+            writeln!(
+                out,
+                "$d {} {} $.",
+                renderer.render([v1]),
+                renderer.render([v2])
+            )?;
+        } else {
+            writeln!(out, "$d {} {} $.", as_str(v1), as_str(v2))?;
+        }
+    }
+    for hyp in frame.hypotheses.iter().skip(frame.mandatory_count) {
+        write_statement(db.statement_by_address(hyp.address()), renderer, out)?;
+    }
+    write_statement(stmt, renderer, out)?;
+    Ok(true)
+}
+
 pub fn write_statement(
     stmt: StatementRef,
+    renderer: Option<&UnicodeRenderer>,
     out: &mut impl std::io::Write,
 ) -> Result<(), std::io::Error> {
     write!(
@@ -48,8 +67,16 @@ pub fn write_statement(
             _ => "x",
         }
     )?;
-    for token in stmt.math_iter() {
-        write!(out, " {}", as_str(&token))?;
+    if let Some(renderer) = renderer {
+        write!(
+            out,
+            " {}",
+            renderer.render(stmt.math_iter().map(|tk| tk.slice))
+        )?;
+    } else {
+        for token in stmt.math_iter() {
+            write!(out, " {}", as_str(&token))?;
+        }
     }
     writeln!(out)?;
     Ok(())
@@ -3,18 +3,40 @@
 //! module, as is a discussion of the data representation.
 
 mod list_stmt;
+mod repl;
+mod util;
+mod watch;
 
-use annotate_snippets::Renderer;
+#[cfg(test)]
+mod util_tests;
+#[cfg(test)]
+mod watch_tests;
+
+use annotate_snippets::{Level, Renderer};
 use clap::error::ErrorKind;
 use clap::{CommandFactory, Parser};
 use list_stmt::list_statements;
+use metamath_rs::changelog::write_changelog;
+use metamath_rs::comment_parser::Date;
 use metamath_rs::database::{Database, DbOptions};
+use metamath_rs::diag::{self, Diagnostic, LocatedDiagnostic};
+use metamath_rs::diff;
+use metamath_rs::export::ExportError;
+use metamath_rs::latex::LatexStyle;
+use metamath_rs::lint::{LintLevel, LintPolicy};
 use metamath_rs::parser::is_valid_label;
+use metamath_rs::recent::RECENT_COUNT;
 use metamath_rs::statement::StatementAddress;
+use metamath_rs::table::TableFormat;
+use metamath_rs::tptp::ConnectiveTable;
+use repl::Repl;
 use simple_logger::SimpleLogger;
 use std::fs::File;
-use std::io::{self, stdout, BufWriter};
+use std::io::{self, stdout, BufWriter, IsTerminal, Write};
 use std::mem;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use watch::{DiagTracker, FileWatcher};
 
 /// A Metamath database verifier and processing tool
 #[derive(Debug, clap::Parser)]
@@ -35,6 +57,15 @@ struct Cli {
     /// Checks proof validity
     #[arg(short, long)]
     verify: bool,
+    /// Continues verifying a proof after a failing step, reporting all its errors
+    /// with their step numbers
+    #[arg(long, requires("verify"))]
+    all_proof_errors: bool,
+    /// Caches the verification results in the given directory, so that the unchanged
+    /// parts of the database are not verified again by the next runs (the database
+    /// is still parsed and scope-checked on each run)
+    #[arg(long, value_name("DIR"))]
+    cache_dir: Option<String>,
     /// Regenerates `discouraged` file
     #[arg(short = 'D', long, value_name("FILE"))]
     discouraged: Option<String>,
@@ -44,12 +75,82 @@ struct Cli {
     /// Outputs statements directly or indirectly using the given list of statements
     #[arg(long, value_names(&["FILE", "LABELS"]))]
     stmt_use: Vec<String>,
+    /// Generates a static HTML site for the database into the given directory
+    #[arg(long, value_name("DIR"))]
+    html: Option<String>,
+    /// Outputs the most recently contributed or revised theorems as JSON
+    #[arg(long, value_name("FILE"))]
+    recent_json: Option<String>,
+    /// Number of theorems listed by --recent-json and in the recent contributions page of --html
+    #[arg(long, value_name("N"), default_value_t = RECENT_COUNT)]
+    recent_count: usize,
+    /// Outputs a table of metadata about each assertion, as TSV if the file
+    /// name ends with `.tsv`, and as CSV otherwise
+    #[arg(long, value_name("FILE"))]
+    export_table: Option<String>,
+    /// Exports all the statements of the database as JSON, with their parsed formulas,
+    /// frames, comments, outline positions and proofs
+    #[arg(long, value_name("FILE"))]
+    export_json: Option<String>,
+    /// Typesets the given statements and their proofs to a LaTeX file
+    #[arg(long, value_name("LABEL"), num_args(1..))]
+    latex: Vec<String>,
+    /// Output file for --latex
+    #[arg(long, value_name("FILE"), default_value("mmtex.tex"))]
+    latex_file: String,
+    /// Writes a self-contained database with only the given theorems and their dependencies
+    #[arg(long, value_name("LABEL"), num_args(1..))]
+    extract: Vec<String>,
+    /// Output file for --extract, instead of the standard output
+    #[arg(short = 'o', long = "output", value_name("FILE"), requires("extract"))]
+    extract_file: Option<String>,
+    /// Exports the database to Metamath Zero, writing the specification to the given
+    /// `.mm0` file, and the proofs to the `.mmu` file next to it
+    #[arg(long, value_name("FILE"))]
+    export_mm0: Option<String>,
+    /// Exports only the given theorems and their dependencies with --export-mm0
+    #[arg(long, value_name("LABEL"), num_args(1..), requires("export_mm0"))]
+    mm0_extract: Vec<String>,
+    /// Exports each first-order theorem as a TPTP problem `LABEL.p` in the given
+    /// directory, with the instances of the assertions used in its proof as premises
+    #[arg(long, value_name("DIR"))]
+    export_tptp: Option<String>,
+    /// Adds the translations of syntax axioms listed in the given file to the ones
+    /// of set.mm for --export-tptp, with one `LABEL CONNECTIVE [NAME]` entry per line
+    #[arg(long, value_name("FILE"), requires("export_tptp"))]
+    tptp_table: Option<String>,
+    /// Compares the database to an older version, and outputs the added, removed,
+    /// renamed and modified theorems
+    #[arg(long, value_name("OLD_DATABASE"))]
+    diff: Option<String>,
+    /// Output format for --diff
+    #[arg(long, value_name("FORMAT"), value_parser(["text", "json"]), default_value("text"), requires("diff"))]
+    diff_format: String,
+    /// Compares the database to an older version, and outputs change log entries
+    /// for the renamed and deleted theorems, with suggested replacements
+    #[arg(long, value_name("OLD_DATABASE"))]
+    changelog: Option<String>,
+    /// Date of the change log entries, like `1-Jan-2024`, instead of today
+    #[arg(
+        long,
+        value_name("DATE"),
+        value_parser(parse_date),
+        requires("changelog")
+    )]
+    changelog_date: Option<Date>,
+    /// Uses the plain LaTeX style of `show statement /old_tex` for --latex
+    #[arg(long)]
+    old_tex: bool,
     /// Checks axiom usage
     #[arg(short = 'u', long)]
     verify_usage: bool,
     /// Shows database outline
     #[arg(short = 'O', long)]
     outline: bool,
+    /// Output format for --outline: the json and markdown formats number the
+    /// chapters and count their statements
+    #[arg(long, value_name("FORMAT"), value_parser(["text", "json", "markdown"]), default_value("text"), requires("outline"))]
+    outline_format: String,
     /// Dumps typesetting information
     #[arg(short = 'T', long)]
     dump_typesetting: bool,
@@ -74,6 +175,15 @@ struct Cli {
     /// List all statements of this database
     #[arg(short = 'S', long)]
     list_statements: bool,
+    /// Outputs statistics about the statements, typecodes, chapters, mathboxes and
+    /// proofs of the database, and the time it took to parse and verify each segment
+    #[arg(long)]
+    stats: bool,
+    /// Renders formulas as Unicode text derived from the `althtmldef` definitions,
+    /// with --list-statements, --dump-formula and in the REPL. Proof files written
+    /// by --export stay in ASCII, so that other tools can read them back
+    #[arg(long)]
+    unicode: bool,
     /// Activates debug logs, including for the grammar building and statement parsing
     #[arg(long)]
     debug: bool,
@@ -83,9 +193,40 @@ struct Cli {
     /// Explicitly deallocates working memory before exit
     #[arg(long)]
     free: bool,
+    /// Does not report the diagnostics with the given codes
+    #[arg(long, value_name("CODE"), value_delimiter(','))]
+    allow: Vec<String>,
+    /// Reports the diagnostics with the given codes as warnings
+    #[arg(long, value_name("CODE"), value_delimiter(','))]
+    warn: Vec<String>,
+    /// Reports the diagnostics with the given codes as errors
+    #[arg(long, value_name("CODE"), value_delimiter(','))]
+    deny: Vec<String>,
+    /// Exits with code 1 only if errors are reported, rather than any diagnostic
+    #[arg(long)]
+    fail_on_errors_only: bool,
+    /// Emits the diagnostics in a machine-readable format instead of rendering them.
+    /// Progress messages and timings are then written to stderr
+    #[arg(long, value_name("FORMAT"), value_parser(["json", "sarif"]))]
+    diagnostics_format: Option<String>,
+    /// Applies the automatic fixes of the reported diagnostics to the source files,
+    /// and checks the database again
+    #[arg(long, conflicts_with_all(["text", "diagnostics_format"]))]
+    fix: bool,
+    /// Author name replacing the `?who?` placeholders with --fix
+    #[arg(long, value_name("NAME"), requires("fix"))]
+    fix_author: Option<String>,
     /// Demonstrates incremental verifier
     #[arg(long)]
     repeat: bool,
+    /// Watches the database files, and reruns incrementally each time they change,
+    /// reporting only new and resolved diagnostics
+    #[arg(long, conflicts_with("repeat"))]
+    watch: bool,
+    /// Loads the database once, then reads and runs query commands like
+    /// `show statement LABEL` from the standard input; type `help` for the list of commands
+    #[arg(long, conflicts_with_all(["repeat", "watch", "fix"]))]
+    repl: bool,
     /// Number of threads to use for verification
     #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
     jobs: Option<u32>,
@@ -96,10 +237,26 @@ struct Cli {
     /// Can be used one or two times; the second is for exthtml processing
     #[arg(long, value_name("FILE"))]
     biblio: Vec<String>,
+    /// Rewrites the cross-reference table of a bibliography file
+    /// Can be used one or two times; the second is for exthtml processing
+    #[arg(long, value_name("FILE"))]
+    write_bibliography: Vec<String>,
     #[cfg(feature = "dot")]
     /// Export the database's grammar in Graphviz DOT format for visualization
     #[arg(short = 'E', long)]
     export_grammar_dot: bool,
+    #[cfg(feature = "dot")]
+    /// Export the proof of the given theorem in Graphviz DOT format to `LABEL.dot`
+    #[arg(long, value_name("LABEL"))]
+    export_proof_dot: Option<String>,
+    #[cfg(feature = "dot")]
+    /// Shows the expression of each step with --export-proof-dot
+    #[arg(long, requires("export_proof_dot"))]
+    proof_dot_exprs: bool,
+    #[cfg(feature = "dot")]
+    /// Shows the syntax steps with --export-proof-dot
+    #[arg(long, requires("export_proof_dot"))]
+    proof_dot_syntax: bool,
     #[cfg(feature = "xml")]
     /// Exports all theorem dependencies in the GraphML file format
     #[arg(long, value_name("FILE"))]
@@ -110,24 +267,65 @@ struct Cli {
     verify_markup: bool,
 }
 
+/// Parses a date in the `1-Jan-2024` format.
+fn parse_date(text: &str) -> Result<Date, String> {
+    Date::try_from(text.as_bytes())
+        .ok()
+        .filter(Date::is_valid)
+        .ok_or_else(|| format!("invalid date {text}, expected a date like 1-Jan-2024"))
+}
+
+/// Returns today's date, in UTC.
+fn today() -> Date {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    // Converts the number of days since 1970-01-01 to a civil date,
+    // counting eras of 400 years starting on March 1st.
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    Date {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
     let mut cmd = Cli::command();
 
     let incremental = cli.repeat
+        || cli.watch
+        || cli.repl
         || cli.grammar
         || cli.parse_stmt
         || cli.verify_parse_stmt
         || cli.dump_grammar
-        || cli.dump_formula;
+        || cli.dump_formula
+        || !cli.extract.is_empty()
+        || cli.export_json.is_some()
+        || cli.export_mm0.is_some()
+        || cli.export_tptp.is_some()
+        || cli.changelog.is_some();
     #[cfg(feature = "dot")]
-    let incremental = incremental || cli.export_grammar_dot;
+    let incremental = incremental || cli.export_grammar_dot || cli.export_proof_dot.is_some();
     let options = DbOptions {
         autosplit: cli.split,
         timing: cli.timing,
         trace_recalc: cli.trace_recalc,
         incremental,
         jobs: cli.jobs.unwrap_or(1) as usize,
+        verify_recovery: cli.all_proof_errors,
+        log_to_stderr: cli.diagnostics_format.is_some(),
     };
 
     if cli.debug {
@@ -135,6 +333,27 @@ fn main() {
     }
 
     let mut db = Database::new(options);
+    if let Some(dir) = &cli.cache_dir {
+        db.set_cache_dir(dir);
+    }
+
+    let mut policy = LintPolicy::default();
+    for (codes, level) in [
+        (&cli.allow, LintLevel::Allow),
+        (&cli.warn, LintLevel::Warn),
+        (&cli.deny, LintLevel::Deny),
+    ] {
+        for code in codes {
+            if !policy.set(code, level) {
+                cmd.error(
+                    ErrorKind::InvalidValue,
+                    format!("Unknown diagnostic code {code}"),
+                )
+                .exit()
+            }
+        }
+    }
+    db.set_lint_policy(policy);
 
     let mut data = Vec::new();
     for kv in cli.text.chunks(2) {
@@ -142,8 +361,26 @@ fn main() {
     }
     let start = cli.db.unwrap_or_else(|| data[0].0.clone());
 
+    if cli.repl {
+        Repl::new(&mut db, start, data, cli.unicode)
+            .run()
+            .unwrap_or_else(|err| cmd.error(ErrorKind::Io, err).exit());
+        return;
+    }
+
+    let mut watcher = cli.watch.then(|| {
+        FileWatcher::new().unwrap_or_else(|err| {
+            cmd.error(ErrorKind::Io, format!("Cannot watch files: {err}"))
+                .exit()
+        })
+    });
+    let mut tracker = cli.watch.then(DiagTracker::default);
+    let mut fix = cli.fix;
+    let mut rechecking = false;
+
     loop {
         db.parse(start.clone(), data.clone());
+        db.lint_pass();
 
         if cli.verify {
             db.verify_pass();
@@ -167,65 +404,327 @@ fn main() {
 
         let mut diags = db.diag_notations();
 
-        if let Some(discouraged) = &cli.discouraged {
-            File::create(discouraged)
-                .and_then(|file| db.write_discouraged(&mut BufWriter::new(file)))
+        if cli.cache_dir.is_some() {
+            db.save_cache()
                 .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
         }
 
-        #[cfg(feature = "xml")]
-        if let Some(file) = &cli.export_graphml_deps {
-            File::create(file)
-                .map_err(|err| err.into())
-                .and_then(|file| db.export_graphml_deps(&mut BufWriter::new(file)))
-                .unwrap_or_else(|diag| diags.push((StatementAddress::default(), diag)));
-        }
+        let mut located = cli.diagnostics_format.is_some().then(Vec::new);
+        let (mut count, mut errors) = (0, 0);
 
-        if let Some(file) = &cli.axiom_use {
-            File::create(file)
-                .and_then(|file| {
-                    db.write_stmt_use(|label| label.starts_with(b"ax-"), &mut BufWriter::new(file))
-                })
-                .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
-        }
+        // After fixes are applied, only the diagnostics are checked again
+        if !rechecking {
+            if let Some(discouraged) = &cli.discouraged {
+                File::create(discouraged)
+                    .and_then(|file| db.write_discouraged(&mut BufWriter::new(file)))
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
 
-        if !cli.stmt_use.is_empty() {
-            let output_file_path = &cli.stmt_use[0];
-            let stmt_list: Vec<_> = cli.stmt_use[1].split(',').map(str::as_bytes).collect();
-            if !stmt_list.iter().copied().all(is_valid_label) {
-                cmd.error(
-                    ErrorKind::InvalidValue,
-                    "Expected list of labels as second argument to --stmt-use",
-                )
-                .exit();
+            #[cfg(feature = "xml")]
+            if let Some(file) = &cli.export_graphml_deps {
+                File::create(file)
+                    .map_err(|err| err.into())
+                    .and_then(|file| db.export_graphml_deps(&mut BufWriter::new(file)))
+                    .unwrap_or_else(|diag| diags.push((StatementAddress::default(), diag)));
             }
-            File::create(output_file_path)
-                .and_then(|file| {
-                    db.write_stmt_use(
-                        |label| stmt_list.contains(&label),
-                        &mut BufWriter::new(file),
+
+            if let Some(file) = &cli.axiom_use {
+                File::create(file)
+                    .and_then(|file| {
+                        db.write_stmt_use(
+                            |label| label.starts_with(b"ax-"),
+                            &mut BufWriter::new(file),
+                        )
+                    })
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if !cli.stmt_use.is_empty() {
+                let output_file_path = &cli.stmt_use[0];
+                let stmt_list: Vec<_> = cli.stmt_use[1].split(',').map(str::as_bytes).collect();
+                if !stmt_list.iter().copied().all(is_valid_label) {
+                    cmd.error(
+                        ErrorKind::InvalidValue,
+                        "Expected list of labels as second argument to --stmt-use",
                     )
-                })
+                    .exit();
+                }
+                File::create(output_file_path)
+                    .and_then(|file| {
+                        db.write_stmt_use(
+                            |label| stmt_list.contains(&label),
+                            &mut BufWriter::new(file),
+                        )
+                    })
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(dir) = &cli.html {
+                db.scope_pass();
+                db.typesetting_pass();
+                db.outline_pass();
+                db.write_html_site(dir, cli.recent_count)
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(file) = &cli.recent_json {
+                File::create(file)
+                    .and_then(|file| {
+                        db.write_recent_json(cli.recent_count, &mut BufWriter::new(file))
+                    })
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(file) = &cli.export_table {
+                db.name_pass();
+                db.outline_pass();
+                let format = if file.ends_with(".tsv") {
+                    TableFormat::Tsv
+                } else {
+                    TableFormat::Csv
+                };
+                File::create(file)
+                    .and_then(|file| db.write_statement_table(format, &mut BufWriter::new(file)))
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(file) = &cli.export_json {
+                db.scope_pass();
+                db.stmt_parse_pass();
+                db.outline_pass();
+                File::create(file)
+                    .and_then(|file| db.write_database_json(&mut BufWriter::new(file)))
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            #[cfg(feature = "dot")]
+            if let Some(label) = &cli.export_proof_dot {
+                db.scope_pass();
+                let stmt = db.statement(label.as_bytes()).unwrap_or_else(|| {
+                    cmd.error(
+                        ErrorKind::InvalidValue,
+                        format!("Unknown label {label} for --export-proof-dot"),
+                    )
+                    .exit()
+                });
+                db.export_proof_dot(stmt, cli.proof_dot_exprs, cli.proof_dot_syntax)
+                    .unwrap_or_else(|err| diags.push((stmt.address(), err.into())));
+            }
+
+            if !cli.latex.is_empty() {
+                db.scope_pass();
+                db.typesetting_pass();
+                let stmts: Vec<_> = cli
+                    .latex
+                    .iter()
+                    .map(|label| {
+                        db.statement(label.as_bytes()).unwrap_or_else(|| {
+                            cmd.error(
+                                ErrorKind::InvalidValue,
+                                format!("Unknown label {label} for --latex"),
+                            )
+                            .exit()
+                        })
+                    })
+                    .collect();
+                let style = if cli.old_tex {
+                    LatexStyle::Old
+                } else {
+                    LatexStyle::Modern
+                };
+                match File::create(&cli.latex_file)
+                    .and_then(|file| db.write_latex(&stmts, style, &mut BufWriter::new(file)))
+                {
+                    Ok(latex_diags) => diags.extend(latex_diags),
+                    Err(err) => diags.push((StatementAddress::default(), err.into())),
+                }
+            }
+
+            if !cli.extract.is_empty() {
+                db.scope_pass();
+                db.stmt_parse_pass();
+                let stmts: Vec<_> = cli
+                    .extract
+                    .iter()
+                    .map(|label| {
+                        db.statement(label.as_bytes()).unwrap_or_else(|| {
+                            cmd.error(
+                                ErrorKind::InvalidValue,
+                                format!("Unknown label {label} for --extract"),
+                            )
+                            .exit()
+                        })
+                    })
+                    .collect();
+                match &cli.extract_file {
+                    Some(file) => File::create(file)
+                        .and_then(|file| db.write_extract(&stmts, &mut BufWriter::new(file))),
+                    None => db.write_extract(&stmts, &mut stdout().lock()),
+                }
                 .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
-        }
+            }
 
-        if cli.list_statements {
-            db.scope_pass();
-            _ = list_statements(&db, |_label| true, &mut stdout());
+            if let Some(file) = &cli.export_mm0 {
+                db.scope_pass();
+                db.stmt_parse_pass();
+                let stmts: Vec<_> = cli
+                    .mm0_extract
+                    .iter()
+                    .map(|label| {
+                        db.statement(label.as_bytes()).unwrap_or_else(|| {
+                            cmd.error(
+                                ErrorKind::InvalidValue,
+                                format!("Unknown label {label} for --mm0-extract"),
+                            )
+                            .exit()
+                        })
+                    })
+                    .collect();
+                let mmu_file = Path::new(file).with_extension("mmu");
+                if mmu_file == Path::new(file) {
+                    cmd.error(
+                        ErrorKind::InvalidValue,
+                        format!("The MM0 file {file} shall not have the .mmu extension of the proof file"),
+                    )
+                    .exit()
+                }
+                File::create(file)
+                    .and_then(|mm0| Ok((mm0, File::create(&mmu_file)?)))
+                    .map_err(ExportError::from)
+                    .and_then(|(mm0, mmu)| {
+                        db.write_mm0(&stmts, &mut BufWriter::new(mm0), &mut BufWriter::new(mmu))
+                    })
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(dir) = &cli.export_tptp {
+                db.scope_pass();
+                db.stmt_parse_pass();
+                let mut table = ConnectiveTable::default();
+                if let Some(file) = &cli.tptp_table {
+                    let text = std::fs::read_to_string(file).unwrap_or_else(|err| {
+                        cmd.error(ErrorKind::Io, format!("Could not read {file}: {err}"))
+                            .exit()
+                    });
+                    if let Err(line) = table.load(&text) {
+                        cmd.error(
+                            ErrorKind::InvalidValue,
+                            format!("Invalid translation at line {line} of {file}"),
+                        )
+                        .exit()
+                    }
+                }
+                std::fs::create_dir_all(dir).unwrap_or_else(|err| {
+                    cmd.error(ErrorKind::Io, format!("Cannot create {dir}: {err}"))
+                        .exit()
+                });
+                for stmt in db.statements() {
+                    let mut problem = vec![];
+                    match db.write_tptp_problem(stmt, &table, &mut problem) {
+                        Ok(true) => {
+                            let label = String::from_utf8_lossy(stmt.label());
+                            std::fs::write(Path::new(dir).join(format!("{label}.p")), problem)
+                                .unwrap_or_else(|err| diags.push((stmt.address(), err.into())));
+                        }
+                        Ok(false) => {}
+                        Err(err) => diags.push((stmt.address(), err.into())),
+                    }
+                }
+            }
+
+            if let Some(old_file) = &cli.diff {
+                db.scope_pass();
+                let old_db = load_old_database(&mut cmd, options, old_file);
+                let (old_count, old_errors) =
+                    report_diags(&old_db, old_db.diag_notations(), located.as_mut());
+                count += old_count;
+                errors += old_errors;
+                let changes = old_db.diff(&db);
+                let mut out = stdout().lock();
+                if cli.diff_format == "json" {
+                    diff::write_json(&changes, &mut out)
+                } else {
+                    changes
+                        .iter()
+                        .try_for_each(|change| writeln!(out, "{change}"))
+                }
+                .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if let Some(old_file) = &cli.changelog {
+                db.scope_pass();
+                db.stmt_parse_pass();
+                let old_db = load_old_database(&mut cmd, options, old_file);
+                let (old_count, old_errors) =
+                    report_diags(&old_db, old_db.diag_notations(), located.as_mut());
+                count += old_count;
+                errors += old_errors;
+                let changes = old_db.label_changes(&db);
+                let date = cli.changelog_date.unwrap_or_else(today);
+                write_changelog(&changes, date, &mut stdout().lock())
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
+
+            if !cli.write_bibliography.is_empty() {
+                if cli.write_bibliography.len() > 2 {
+                    cmd.error(
+                        ErrorKind::TooManyValues,
+                        "expected at most 2 bibliography files",
+                    )
+                    .exit()
+                }
+                db.typesetting_pass();
+                for (file, ext) in cli.write_bibliography.iter().zip([false, true]) {
+                    std::fs::read(file)
+                        .and_then(|template| {
+                            let mut out = vec![];
+                            db.write_bibliography(&template, ext, &mut out)?;
+                            std::fs::write(file, out)
+                        })
+                        .unwrap_or_else(|err| {
+                            diags.push((StatementAddress::default(), err.into()))
+                        });
+                }
+            }
+
+            if cli.list_statements {
+                db.scope_pass();
+                let renderer = cli.unicode.then(|| {
+                    db.typesetting_pass();
+                    db.unicode_renderer(stdout().is_terminal())
+                });
+                _ = list_statements(&db, |_label| true, renderer.as_ref(), &mut stdout());
+            }
+
+            if cli.stats {
+                db.scope_pass();
+                db.verify_pass();
+                db.outline_pass();
+                db.write_stats(&mut stdout().lock())
+                    .unwrap_or_else(|err| diags.push((StatementAddress::default(), err.into())));
+            }
         }
 
-        let r = Renderer::styled();
-        #[allow(unused_mut)]
-        let mut count = db
-            .render_diags(diags, |msg| println!("{}", r.render(msg)))
-            .len();
+        let mut fixes = vec![];
+        if fix {
+            fixes.extend(db.diag_fixes(&diags, cli.fix_author.as_deref()));
+        }
+        if let Some(tracker) = &mut tracker {
+            diags = tracker.retain_new(&db, diags);
+        }
+        let (new_count, new_errors) = report_diags(&db, diags, located.as_mut());
+        count += new_count;
+        errors += new_errors;
 
         if cli.verify_parse_stmt {
             db.stmt_parse_pass();
-            let diags = db.verify_parse_stmt();
-            count += db
-                .render_diags(diags, |msg| println!("{}", r.render(msg)))
-                .len();
+            let mut diags = db.verify_parse_stmt();
+            if let Some(tracker) = &mut tracker {
+                diags = tracker.retain_new(&db, diags);
+            }
+            let (new_count, new_errors) = report_diags(&db, diags, located.as_mut());
+            count += new_count;
+            errors += new_errors;
         }
 
         #[cfg(feature = "verify_markup")]
@@ -259,16 +758,88 @@ fn main() {
                 base,
                 ext: bibs.next(),
             });
+            if let Some(tracker) = &mut tracker {
+                bib_diags = tracker.retain_new_bib(bib_diags);
+            }
+
+            count += bib_diags.len();
+            errors += (bib_diags.iter())
+                .filter(|(_, diag)| diag.level() == Level::Error)
+                .count();
+            if let Some(located) = &mut located {
+                located.extend(BibError::locate_list(&bib_diags));
+            } else {
+                let r = Renderer::styled();
+                BibError::render_list(&bib_diags, |msg| println!("{}", r.render(msg)));
+            }
 
-            count += BibError::render_list(&bib_diags, |msg| println!("{}", r.render(msg))).len();
+            let mut diags = db.verify_markup(bib.as_ref());
+            if fix {
+                fixes.extend(db.diag_fixes(&diags, cli.fix_author.as_deref()));
+            }
+            if let Some(tracker) = &mut tracker {
+                diags = tracker.retain_new(&db, diags);
+            }
+            let (new_count, new_errors) = report_diags(&db, diags, located.as_mut());
+            count += new_count;
+            errors += new_errors;
+        }
 
-            let diags = db.verify_markup(bib.as_ref());
-            count += db
-                .render_diags(diags, |msg| println!("{}", r.render(msg)))
-                .len();
+        if let Some(tracker) = &mut tracker {
+            let (total, resolved) = tracker.finish_run();
+            // Keep stdout for the machine-readable diagnostics
+            let status = |line: String| {
+                if located.is_some() {
+                    eprintln!("{line}");
+                } else {
+                    println!("{line}");
+                }
+            };
+            for summary in &resolved {
+                status(format!("resolved: {summary}"));
+            }
+            status(format!(
+                "{count} new and {} resolved diagnostics, {total} diagnostics remaining.",
+                resolved.len()
+            ));
+        } else if located.is_none() {
+            println!("{count} diagnostics issued.");
+        }
+        if let Some(located) = &located {
+            let mut out = stdout().lock();
+            _ = if cli.diagnostics_format.as_deref() == Some("sarif") {
+                diag::write_sarif(
+                    located,
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION"),
+                    &mut out,
+                )
+            } else {
+                diag::write_json(located, &mut out)
+            };
         }
 
-        println!("{count} diagnostics issued.");
+        if fix {
+            // Fixes are only applied once, and the database is then checked again
+            fix = false;
+            let files = db.apply_fixes(&fixes);
+            for (file, text) in &files {
+                std::fs::write(file, text).unwrap_or_else(|err| {
+                    cmd.error(ErrorKind::Io, format!("Cannot write {file}: {err}"))
+                        .exit()
+                });
+            }
+            let message = format!("Applied {} fixes to {} files.", fixes.len(), files.len());
+            if located.is_some() {
+                eprintln!("{message}");
+            } else {
+                println!("{message}");
+            }
+            if !files.is_empty() {
+                rechecking = true;
+                continue;
+            }
+        }
 
         if cli.dump_grammar {
             db.grammar_pass();
@@ -283,12 +854,29 @@ fn main() {
 
         if cli.dump_formula {
             db.stmt_parse_pass();
-            db.dump_formula();
+            if cli.unicode {
+                db.typesetting_pass();
+                db.dump_formula_unicode(&db.unicode_renderer(stdout().is_terminal()));
+            } else {
+                db.dump_formula();
+            }
         }
 
         if cli.outline {
             db.outline_pass();
-            db.print_outline();
+            let mut out = stdout().lock();
+            match cli.outline_format.as_str() {
+                "json" => db.write_outline_json(&mut out),
+                "markdown" => db.write_outline_markdown(&mut out),
+                _ => {
+                    db.print_outline();
+                    Ok(())
+                }
+            }
+            .unwrap_or_else(|err| {
+                cmd.error(ErrorKind::Io, format!("Cannot write outline: {err}"))
+                    .exit()
+            });
         }
 
         if cli.dump_typesetting {
@@ -303,7 +891,15 @@ fn main() {
             }
         }
 
-        if cli.repeat {
+        if let Some(watcher) = &mut watcher {
+            watcher.update(&db).unwrap_or_else(|err| {
+                cmd.error(ErrorKind::Io, format!("Cannot watch files: {err}"))
+                    .exit()
+            });
+            if !watcher.wait() {
+                break;
+            }
+        } else if cli.repeat {
             let mut input = String::new();
             if io::stdin().read_line(&mut input).unwrap() == 0 {
                 break;
@@ -313,9 +909,51 @@ fn main() {
                 mem::forget(db);
             }
 
-            // Exit with code 1 if any warning or error were encountered
-            let code = if count > 0 { 1 } else { 0 };
+            // Exit with code 1 if any diagnostic (or only any error) was reported
+            let failed = if cli.fail_on_errors_only {
+                errors
+            } else {
+                count
+            };
+            let code = if failed > 0 { 1 } else { 0 };
             std::process::exit(code);
         }
     }
 }
+
+/// Loads an older version of the database, to compare it with the current one.
+/// Exits if the file cannot be read.
+fn load_old_database(cmd: &mut clap::Command, options: DbOptions, file: &str) -> Database {
+    if let Err(err) = std::fs::metadata(file) {
+        cmd.error(ErrorKind::Io, format!("Cannot read {file}: {err}"))
+            .exit()
+    }
+    let mut old_db = Database::new(options);
+    old_db.parse(file.to_owned(), vec![]);
+    old_db.scope_pass();
+    old_db
+}
+
+/// Renders the given diagnostics to stdout, or collects them into `located`
+/// for a machine-readable output, according to the lint policy.
+/// Returns the number of diagnostics reported, and the number of errors among them.
+fn report_diags(
+    db: &Database,
+    diags: Vec<(StatementAddress, Diagnostic)>,
+    located: Option<&mut Vec<LocatedDiagnostic>>,
+) -> (usize, usize) {
+    let errors = (diags.iter())
+        .filter(|(addr, diag)| db.diag_level(*addr, diag) == Some(Level::Error))
+        .count();
+    let count = if let Some(located) = located {
+        let diags = db.locate_diags(diags);
+        let count = diags.len();
+        located.extend(diags);
+        count
+    } else {
+        let r = Renderer::styled();
+        db.render_diags(diags, |msg| println!("{}", r.render(msg)))
+            .len()
+    };
+    (count, errors)
+}
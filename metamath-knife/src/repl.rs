@@ -0,0 +1,293 @@
+//! An interactive command loop, querying a database loaded once.
+
+use crate::list_stmt::{write_frame, write_statement};
+use crate::report_diags;
+use crate::util::{glob_match, split_args, symbols_match, textwrap};
+use metamath_rs::database::Database;
+use metamath_rs::proof::{ProofStyle, ProofTreePrinter};
+use metamath_rs::statement::StatementAddress;
+use metamath_rs::unicode::UnicodeRenderer;
+use metamath_rs::{as_str, StatementRef, StatementType};
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, stdin, stdout, BufRead, IsTerminal, Write};
+
+const HELP: &str = "\
+Commands:
+  show statement LABEL                Shows a statement with its hypotheses and comment
+  show proof LABEL                    Shows the logical steps of a proof
+  show proof LABEL /normal            Shows a proof in the given format, one of /normal,
+                                      /compressed, /packed, /explicit
+  show usage LABEL [/recursive]       Lists the theorems using a statement
+  show trace_back LABEL [/axioms]     Lists the statements a proof depends on
+  search LABELS [\"MATH\"]              Lists the statements matching a label pattern,
+                                      with `*` and `?` wildcards, and containing the
+                                      given symbols, with `$*` and `$?` wildcards
+  outline                             Shows the database outline
+  reload                              Reloads the database files
+  help                                Shows this help
+  quit                                Exits";
+
+/// The typecode of logical statements, as opposed to syntax statements.
+const PROVABLE_TC: &[u8] = b"|-";
+
+/// The state of the command loop.
+pub struct Repl<'a> {
+    db: &'a mut Database,
+    start: String,
+    data: Vec<(String, Vec<u8>)>,
+    unicode: bool,
+}
+
+impl<'a> Repl<'a> {
+    pub const fn new(
+        db: &'a mut Database,
+        start: String,
+        data: Vec<(String, Vec<u8>)>,
+        unicode: bool,
+    ) -> Self {
+        Self {
+            db,
+            start,
+            data,
+            unicode,
+        }
+    }
+
+    /// Loads the database, then reads and executes commands from the standard input
+    /// until it is closed or the `quit` command is given.
+    pub fn run(&mut self) -> io::Result<()> {
+        self.reload();
+        let prompt = stdin().is_terminal();
+        let mut lines = stdin().lock().lines();
+        loop {
+            if prompt {
+                print!("MM> ");
+                stdout().flush()?;
+            }
+            let Some(line) = lines.next().transpose()? else {
+                break;
+            };
+            let args = split_args(&line);
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            let mut out = stdout().lock();
+            match &*args {
+                [] => {}
+                ["quit" | "exit"] => break,
+                ["help"] => writeln!(out, "{HELP}")?,
+                ["reload"] => self.reload(),
+                ["outline"] => {
+                    self.db.outline_pass();
+                    self.db.print_outline();
+                }
+                ["show", "statement", label] => self.show_statement(label, &mut out)?,
+                ["show", "proof", label] => self.show_proof_steps(label, &mut out)?,
+                ["show", "proof", label, style] => self.show_proof(label, style, &mut out)?,
+                ["show", "usage", label] => self.show_usage(label, false, &mut out)?,
+                ["show", "usage", label, "/recursive"] => self.show_usage(label, true, &mut out)?,
+                ["show", "trace_back", label] => self.show_trace_back(label, false, &mut out)?,
+                ["show", "trace_back", label, "/axioms"] => {
+                    self.show_trace_back(label, true, &mut out)?
+                }
+                ["search", labels] => self.search(labels, None, &mut out)?,
+                ["search", labels, math] => self.search(labels, Some(math), &mut out)?,
+                _ => writeln!(
+                    out,
+                    "?Unknown command, type `help` for the list of commands"
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the database files again, incrementally, runs the passes used by the
+    /// commands, and reports the diagnostics.
+    fn reload(&mut self) {
+        self.db.parse(self.start.clone(), self.data.clone());
+        self.db.scope_pass();
+        self.db.verify_pass();
+        self.db.grammar_pass();
+        self.db.typesetting_pass();
+        self.db.outline_pass();
+        let (count, _) = report_diags(self.db, self.db.diag_notations(), None);
+        println!("{count} diagnostics issued.");
+    }
+
+    fn renderer(&mut self) -> Option<UnicodeRenderer> {
+        self.unicode.then(|| {
+            self.db.typesetting_pass();
+            self.db.unicode_renderer(stdout().is_terminal())
+        })
+    }
+
+    /// Finds a statement by its label, reporting an error if it does not exist.
+    fn lookup(&self, label: &str, out: &mut impl Write) -> io::Result<Option<StatementRef<'_>>> {
+        let stmt = self.db.statement(label.as_bytes());
+        if stmt.is_none() {
+            writeln!(out, "?Unknown label {label}")?;
+        }
+        Ok(stmt)
+    }
+
+    fn show_statement(&mut self, label: &str, out: &mut impl Write) -> io::Result<()> {
+        let renderer = self.renderer();
+        let Some(stmt) = self.lookup(label, out)? else {
+            return Ok(());
+        };
+        if let Some(comment) = stmt.associated_comment() {
+            let segment = comment.segment();
+            let text = as_str(comment.comment_contents().as_ref(&segment.buffer))
+                .split_whitespace()
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", textwrap(&text, 79))?;
+        }
+        if !write_frame(self.db, stmt, renderer.as_ref(), out)? {
+            write_statement(stmt, renderer.as_ref(), out)?;
+        }
+        Ok(())
+    }
+
+    /// Shows the logical steps of a proof, with their hypotheses and expressions.
+    fn show_proof_steps(&mut self, label: &str, out: &mut impl Write) -> io::Result<()> {
+        let renderer = self.renderer();
+        let Some(stmt) = self.lookup(label, out)? else {
+            return Ok(());
+        };
+        let Some(arr) = self.db.get_proof_tree(stmt) else {
+            return writeln!(out, "?The proof of {label} is incomplete or invalid");
+        };
+        let exprs = arr.exprs().expect("proof trees are built with expressions");
+        let indent = arr.indent();
+        let steps = arr.with_logical_steps(self.db, |cur, ix, step, hyps| {
+            let hyps = hyps.iter().map(usize::to_string).collect::<Vec<_>>();
+            let tokens = exprs[cur].split(|&c| c == b' ').filter(|tk| !tk.is_empty());
+            let math = std::iter::once(step.math_at(0).slice).chain(tokens);
+            let math = match &renderer {
+                Some(renderer) => renderer.render(math),
+                None => math.map(as_str).collect::<Vec<_>>().join(" "),
+            };
+            format!(
+                "{ix:>4} {:<8} {:<12} {}{} {math}",
+                hyps.join(","),
+                as_str(step.label()),
+                ". ".repeat(indent[cur].into()),
+                indent[cur] + 1
+            )
+        });
+        for step in steps {
+            writeln!(out, "{step}")?;
+        }
+        Ok(())
+    }
+
+    /// Shows a proof in one of the formats used in database files.
+    fn show_proof(&mut self, label: &str, style: &str, out: &mut impl Write) -> io::Result<()> {
+        let style = match style {
+            "/normal" => ProofStyle::Normal,
+            "/compressed" => ProofStyle::Compressed,
+            "/packed" => ProofStyle::Packed,
+            "/explicit" => ProofStyle::Explicit,
+            _ => return writeln!(out, "?Unknown proof format {style}"),
+        };
+        let Some(stmt) = self.lookup(label, out)? else {
+            return Ok(());
+        };
+        let Some(arr) = self.db.get_proof_tree(stmt) else {
+            return writeln!(out, "?The proof of {label} is incomplete or invalid");
+        };
+        let printer = ProofTreePrinter::new(self.db, stmt.label(), style, &arr);
+        writeln!(out, "{printer}")
+    }
+
+    /// Lists the theorems whose proofs reference the given statement,
+    /// directly or, with `recursive`, indirectly.
+    fn show_usage(&mut self, label: &str, recursive: bool, out: &mut impl Write) -> io::Result<()> {
+        let Some(stmt) = self.lookup(label, out)? else {
+            return Ok(());
+        };
+        let mut used = HashSet::from([stmt.address()]);
+        let mut users = vec![];
+        for user in self.db.statements_range_address(stmt.address()..) {
+            if user.statement_type() != StatementType::Provable || user.address() == stmt.address()
+            {
+                continue;
+            }
+            let uses = user.use_iter().any(|(_, label)| {
+                (self.db.statement(label))
+                    .is_some_and(|used_stmt| used.contains(&used_stmt.address()))
+            });
+            if uses {
+                users.push(as_str(user.label()));
+                if recursive {
+                    used.insert(user.address());
+                }
+            }
+        }
+        if users.is_empty() {
+            writeln!(out, "{label} is not referenced by any theorem.")
+        } else {
+            writeln!(out, "{label} is referenced by: {}", users.join(" "))
+        }
+    }
+
+    /// Lists the assertions the proof of the given theorem depends on, in database
+    /// order, or only the logical axioms and definitions with `axioms_only`.
+    fn show_trace_back(
+        &mut self,
+        label: &str,
+        axioms_only: bool,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let Some(stmt) = self.lookup(label, out)? else {
+            return Ok(());
+        };
+        let mut seen = HashSet::<StatementAddress>::new();
+        let mut queue = VecDeque::from([stmt]);
+        while let Some(stmt) = queue.pop_front() {
+            for (_, used) in stmt.use_iter() {
+                let Some(used) = self.db.statement(used) else {
+                    continue;
+                };
+                if used.is_assertion() && seen.insert(used.address()) {
+                    queue.push_back(used);
+                }
+            }
+        }
+        let mut deps = (seen.into_iter())
+            .map(|addr| self.db.statement_by_address(addr))
+            .filter(|dep| dep.math_len() > 0 && dep.math_at(0).slice == PROVABLE_TC)
+            .filter(|dep| !axioms_only || dep.statement_type() == StatementType::Axiom)
+            .collect::<Vec<_>>();
+        deps.sort_by(|a, b| self.db.cmp_address(&a.address(), &b.address()));
+        let labels = deps
+            .iter()
+            .map(|dep| as_str(dep.label()))
+            .collect::<Vec<_>>();
+        let what = if axioms_only { "axioms" } else { "statements" };
+        writeln!(
+            out,
+            "{label} depends on {} {what}: {}",
+            labels.len(),
+            labels.join(" ")
+        )
+    }
+
+    /// Lists the assertions whose label matches the pattern, and whose formula contains
+    /// the given sequence of symbols.
+    fn search(&mut self, labels: &str, math: Option<&str>, out: &mut impl Write) -> io::Result<()> {
+        let renderer = self.renderer();
+        let math = math.map_or_else(Vec::new, |math| math.split_whitespace().collect());
+        let mut count = 0;
+        for stmt in self.db.statements() {
+            if !stmt.is_assertion() || !glob_match(labels.as_bytes(), stmt.label()) {
+                continue;
+            }
+            let tokens = stmt.math_iter().map(|tk| tk.slice).collect::<Vec<_>>();
+            if !math.is_empty() && !(0..tokens.len()).any(|i| symbols_match(&math, &tokens[i..])) {
+                continue;
+            }
+            write_statement(stmt, renderer.as_ref(), out)?;
+            count += 1;
+        }
+        writeln!(out, "{count} statements found.")
+    }
+}
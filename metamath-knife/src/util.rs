@@ -0,0 +1,59 @@
+//! Text utilities of the interactive command loop.
+
+/// Splits a command line into arguments, keeping quoted strings together.
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (arg, tail) = if let Some(quoted) = rest.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else {
+            rest.split_once(char::is_whitespace).unwrap_or((rest, ""))
+        };
+        args.push(arg.to_owned());
+        rest = tail.trim_start();
+    }
+    args
+}
+
+/// Matches a label against a pattern, where `*` matches any sequence of characters
+/// and `?` matches any single character.
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some((&c, rest)) => text
+            .split_first()
+            .is_some_and(|(&t, text)| (c == b'?' || c == t) && glob_match(rest, text)),
+    }
+}
+
+/// Matches the start of a list of symbols against a pattern, where `$*` matches
+/// any sequence of symbols and `$?` matches any single symbol.
+pub fn symbols_match(pattern: &[&str], tokens: &[&[u8]]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((&"$*", rest)) => (0..=tokens.len()).any(|i| symbols_match(rest, &tokens[i..])),
+        Some((&symbol, rest)) => tokens.split_first().is_some_and(|(&token, tokens)| {
+            (symbol == "$?" || symbol.as_bytes() == token) && symbols_match(rest, tokens)
+        }),
+    }
+}
+
+/// Joins words into lines of at most the given width.
+pub fn textwrap(words: &[&str], width: usize) -> String {
+    let mut text = String::new();
+    let mut line_len = 0;
+    for word in words {
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            text.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            text.push(' ');
+            line_len += 1;
+        }
+        text.push_str(word);
+        line_len += word.len();
+    }
+    text
+}
@@ -0,0 +1,44 @@
+use crate::util::{glob_match, split_args, symbols_match, textwrap};
+
+#[test]
+fn test_split_args() {
+    assert_eq!(split_args("  show  proof ax-1 "), ["show", "proof", "ax-1"]);
+    assert_eq!(
+        split_args("search * \"|- ( ph -> $* )\" x"),
+        ["search", "*", "|- ( ph -> $* )", "x"]
+    );
+    assert_eq!(
+        split_args("search \"unclosed quote"),
+        ["search", "unclosed quote"]
+    );
+    assert!(split_args("   ").is_empty());
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match(b"ax-*", b"ax-mp"));
+    assert!(glob_match(b"*", b""));
+    assert!(glob_match(b"a?1", b"ax1"));
+    assert!(glob_match(b"*mp*", b"ax-mp"));
+    assert!(!glob_match(b"ax-?", b"ax-mp"));
+    assert!(!glob_match(b"ax", b"ax-mp"));
+}
+
+#[test]
+fn test_symbols_match() {
+    let tokens: &[&[u8]] = &[b"(", b"ph", b"->", b"ps", b")"];
+    assert!(symbols_match(&["(", "ph"], tokens));
+    assert!(symbols_match(&["(", "$*", ")"], tokens));
+    assert!(symbols_match(&["(", "$?", "->", "$?", ")"], tokens));
+    assert!(symbols_match(&[], tokens));
+    assert!(!symbols_match(&["ph"], tokens));
+    assert!(!symbols_match(&["(", "$?", ")"], tokens));
+}
+
+#[test]
+fn test_textwrap() {
+    assert_eq!(textwrap(&["a", "bb", "ccc"], 6), "a bb\nccc");
+    assert_eq!(textwrap(&["a", "bb", "ccc"], 8), "a bb ccc");
+    assert_eq!(textwrap(&["toolongword", "x"], 4), "toolongword\nx");
+    assert_eq!(textwrap(&[], 10), "");
+}
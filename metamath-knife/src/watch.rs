@@ -0,0 +1,184 @@
+use metamath_rs::diag::{BibError, Diagnostic, LocatedDiagnostic};
+use metamath_rs::statement::StatementAddress;
+use metamath_rs::{Database, SourceInfo};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+/// How long to wait for the files to settle after a change, before reloading.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the source files of a database for changes.
+///
+/// The directories containing the files are watched rather than the files
+/// themselves, so that files replaced by editors (by writing a new file and
+/// renaming it) are still tracked.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// The files of the database, as absolute paths.
+    files: HashSet<PathBuf>,
+    /// The directories currently watched.
+    dirs: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        Ok(Self {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            files: HashSet::new(),
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Updates the set of watched files to the files loaded in the database,
+    /// including the ones added by new file inclusions.
+    pub fn update(&mut self, db: &Database) -> notify::Result<()> {
+        self.files = db
+            .source_names()
+            .map(Path::new)
+            .filter(|path| path.is_file())
+            .filter_map(|path| std::path::absolute(path).ok())
+            .collect();
+        let dirs = (self.files.iter())
+            .filter_map(|path| Some(path.parent()?.to_owned()))
+            .collect::<HashSet<_>>();
+        for dir in self.dirs.difference(&dirs) {
+            self.watcher.unwatch(dir)?;
+        }
+        for dir in dirs.difference(&self.dirs) {
+            self.watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        self.dirs = dirs;
+        Ok(())
+    }
+
+    /// Returns whether the event concerns one of the database files.
+    fn is_relevant(&self, event: &notify::Result<notify::Event>) -> bool {
+        event.as_ref().map_or(true, |event| {
+            !event.kind.is_access() && event.paths.iter().any(|path| self.files.contains(path))
+        })
+    }
+
+    /// Blocks until one of the database files changes, and then until no more
+    /// changes happen for a short while.
+    /// Returns `false` if the watcher has stopped.
+    pub fn wait(&self) -> bool {
+        loop {
+            match self.events.recv() {
+                Ok(event) if self.is_relevant(&event) => break,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+        }
+        while self.events.recv_timeout(DEBOUNCE).is_ok() {}
+        true
+    }
+}
+
+/// Identifies a diagnostic across reloads of the database, independently of
+/// its position: by its title, and the messages and text of its annotations.
+/// Several diagnostics may have the same key, for example the same trailing
+/// whitespace on different lines.
+#[derive(PartialEq, Eq, Hash)]
+struct DiagKey {
+    title: String,
+    annotations: Vec<(String, String, Vec<u8>)>,
+}
+
+/// Keeps track of the diagnostics reported in the previous run,
+/// so that only the new and resolved ones are reported.
+#[derive(Default)]
+pub struct DiagTracker {
+    /// The diagnostics of the previous run, with a one-line summary for each
+    /// occurrence of a diagnostic.
+    previous: HashMap<DiagKey, Vec<String>>,
+    current: HashMap<DiagKey, Vec<String>>,
+}
+
+impl DiagTracker {
+    /// Records a located diagnostic as reported in the current run, and returns
+    /// whether it is new, i.e. whether it is reported more times in the current
+    /// run than in the previous run.
+    /// The text of its annotations is looked up in the given files.
+    pub fn track<'a>(
+        &mut self,
+        located: LocatedDiagnostic,
+        source_text: impl Fn(&str) -> Option<&'a [u8]>,
+    ) -> bool {
+        let summary = located.annotations.first().map_or_else(
+            || located.title.clone(),
+            |ann| {
+                let (line, col) = ann.start;
+                format!("{} ({}:{line}:{col})", located.title, ann.file)
+            },
+        );
+        let annotations = (located.annotations.into_iter())
+            .map(|ann| {
+                let text = source_text(&ann.file).unwrap_or_default();
+                let text = text.get(ann.range).unwrap_or_default().to_vec();
+                (ann.message, ann.file, text)
+            })
+            .collect();
+        let key = DiagKey {
+            title: located.title,
+            annotations,
+        };
+        let previous = self.previous.get(&key).map_or(0, Vec::len);
+        let current = self.current.entry(key).or_default();
+        current.push(summary);
+        current.len() > previous
+    }
+    /// Records the given diagnostics as reported in the current run,
+    /// and returns only the ones which were not reported in the previous run.
+    pub fn retain_new(
+        &mut self,
+        db: &Database,
+        diags: Vec<(StatementAddress, Diagnostic)>,
+    ) -> Vec<(StatementAddress, Diagnostic)> {
+        let located = db.locate_each_diag(&diags);
+        (diags.into_iter().zip(located))
+            .filter_map(|(diag, located)| {
+                let new =
+                    located.is_none_or(|located| self.track(located, |file| db.source_text(file)));
+                new.then_some(diag)
+            })
+            .collect()
+    }
+
+    /// Records the given bibliography diagnostics as reported in the current run,
+    /// and returns only the ones which were not reported in the previous run.
+    pub fn retain_new_bib<'a>(
+        &mut self,
+        diags: Vec<(&'a SourceInfo, BibError)>,
+    ) -> Vec<(&'a SourceInfo, BibError)> {
+        let located = BibError::locate_list(&diags);
+        (diags.into_iter().zip(located))
+            .filter_map(|(diag, located)| {
+                let source = diag.0;
+                self.track(located, |_| Some(source.text())).then_some(diag)
+            })
+            .collect()
+    }
+
+    /// Ends the current run, and returns the number of diagnostics still present,
+    /// and the summaries of the diagnostics which have been resolved.
+    pub fn finish_run(&mut self) -> (usize, Vec<String>) {
+        let previous = std::mem::replace(&mut self.previous, std::mem::take(&mut self.current));
+        let mut resolved = vec![];
+        for (key, summaries) in previous {
+            let current = self.previous.get(&key).map_or(&[][..], Vec::as_slice);
+            // Report the occurrences whose position changed as the resolved ones
+            let count = summaries.len().saturating_sub(current.len());
+            let (moved, unmoved): (Vec<_>, Vec<_>) = summaries
+                .into_iter()
+                .partition(|summary| !current.contains(summary));
+            resolved.extend(moved.into_iter().chain(unmoved).take(count));
+        }
+        (self.previous.values().map(Vec::len).sum(), resolved)
+    }
+}
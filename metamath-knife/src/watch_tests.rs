@@ -0,0 +1,72 @@
+use crate::watch::DiagTracker;
+use annotate_snippets::Level;
+use metamath_rs::diag::{LocatedAnnotation, LocatedDiagnostic};
+
+const TEXT: &[u8] = b"ax-1 $a |- ph $.  \nax-2 $a |- ps $.  \nax-3 $a |- ch $.\t\n";
+
+/// Builds a diagnostic with the given title, at column 17 of the given line,
+/// covering the given byte range of `TEXT`.
+fn diag(title: &str, line: u32, start: usize, end: usize) -> LocatedDiagnostic {
+    LocatedDiagnostic {
+        code: "Test",
+        level: Level::Warning,
+        title: title.to_owned(),
+        annotations: vec![LocatedAnnotation {
+            level: Level::Warning,
+            message: "Here".to_owned(),
+            file: "test.mm".to_owned(),
+            range: start..end,
+            start: (line, 17),
+            end: (line, 19),
+        }],
+        notes: vec![],
+        fixes: vec![],
+    }
+}
+
+/// Tracks the given diagnostics in a run, and returns which ones are new.
+fn run(tracker: &mut DiagTracker, diags: Vec<LocatedDiagnostic>) -> Vec<bool> {
+    (diags.into_iter())
+        .map(|diag| tracker.track(diag, |_| Some(TEXT)))
+        .collect()
+}
+
+#[test]
+fn test_diag_tracker() {
+    let mut tracker = DiagTracker::default();
+    // The same trailing whitespace on two lines
+    let new = run(
+        &mut tracker,
+        vec![
+            diag("Trailing whitespace", 1, 16, 18),
+            diag("Trailing whitespace", 2, 35, 37),
+        ],
+    );
+    assert_eq!(new, [true, true]);
+    assert_eq!(tracker.finish_run(), (2, vec![]));
+
+    // One of them is fixed, and a tab is introduced
+    let new = run(
+        &mut tracker,
+        vec![
+            diag("Trailing whitespace", 1, 16, 18),
+            diag("Tab used", 3, 54, 55),
+        ],
+    );
+    assert_eq!(new, [false, true]);
+    let (count, resolved) = tracker.finish_run();
+    assert_eq!(count, 2);
+    assert_eq!(resolved, ["Trailing whitespace (test.mm:2:17)"]);
+
+    // The trailing whitespace is introduced again
+    let new = run(
+        &mut tracker,
+        vec![
+            diag("Trailing whitespace", 1, 16, 18),
+            diag("Trailing whitespace", 2, 35, 37),
+            diag("Tab used", 3, 54, 55),
+        ],
+    );
+    assert_eq!(new, [false, true, false]);
+    assert_eq!(tracker.finish_run(), (3, vec![]));
+}
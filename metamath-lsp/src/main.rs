@@ -0,0 +1,228 @@
+//! A Language Server Protocol server for Metamath databases.
+//!
+//! The server communicates with the editor using JSON-RPC over stdio, and keeps
+//! a [`Database`] alive so that edits are processed incrementally. Unsaved
+//! editor buffers are fed to the database in place of the files on disk.
+//! The database is reloaded once the editor has been idle for [`RELOAD_DELAY`],
+//! or before answering a request.
+
+mod server;
+
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{DocumentSymbolRequest, GotoDefinition, HoverRequest};
+use lsp_types::{
+    DocumentSymbolResponse, HoverProviderCapability, InitializeParams, OneOf, PositionEncodingKind,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+};
+use metamath_rs::database::{Database, DbOptions};
+use server::Server;
+use std::error::Error;
+use std::time::Duration;
+
+/// How long to wait after an edit for more edits before reloading the database.
+const RELOAD_DELAY: Duration = Duration::from_millis(300);
+
+/// A Language Server Protocol server for Metamath databases
+#[derive(Debug, clap::Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Database file to load; by default, the first document opened by the editor
+    #[arg(id("DATABASE"))]
+    db: Option<String>,
+    /// Processes files > 1 MiB in multiple segments
+    #[arg(long)]
+    split: bool,
+    /// Number of threads to use for verification
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    jobs: Option<u32>,
+}
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Sync + Send>>;
+
+fn main() -> Result<()> {
+    let cli = <Cli as clap::Parser>::parse();
+    let options = DbOptions {
+        autosplit: cli.split,
+        incremental: true,
+        jobs: cli.jobs.unwrap_or(1) as usize,
+        ..Default::default()
+    };
+
+    let (connection, io_threads) = Connection::stdio();
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    // Byte offsets are used if the client supports them, UTF-16 offsets otherwise
+    let utf8 = (params.capabilities.general)
+        .and_then(|general| general.position_encodings)
+        .is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF8));
+    let position_encoding = if utf8 {
+        PositionEncodingKind::UTF8
+    } else {
+        PositionEncodingKind::UTF16
+    };
+    let capabilities = ServerCapabilities {
+        position_encoding: Some(position_encoding),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize_finish(id, serde_json::json!({ "capabilities": capabilities }))?;
+
+    let mut server = Server::new(Database::new(options), cli.db, !utf8);
+    if server.has_database() {
+        server.reload();
+        publish_diagnostics(&connection, &mut server)?;
+    }
+    main_loop(&connection, &mut server)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn main_loop(connection: &Connection, server: &mut Server) -> Result<()> {
+    // Whether documents have changed since the database was last reloaded
+    let mut changed = false;
+    loop {
+        let msg = if changed {
+            match connection.receiver.recv_timeout(RELOAD_DELAY) {
+                Ok(msg) => msg,
+                Err(err) if err.is_timeout() => {
+                    changed = false;
+                    server.reload();
+                    publish_diagnostics(connection, server)?;
+                    continue;
+                }
+                Err(_) => return Ok(()),
+            }
+        } else {
+            match connection.receiver.recv() {
+                Ok(msg) => msg,
+                Err(_) => return Ok(()),
+            }
+        };
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    return Ok(());
+                }
+                if changed {
+                    changed = false;
+                    server.reload();
+                    publish_diagnostics(connection, server)?;
+                }
+                let response = handle_request(server, req);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(not) => changed |= handle_notification(server, not),
+            Message::Response(_) => {}
+        }
+    }
+}
+
+/// Extracts the parameters of a request if it has the given method,
+/// or gives the request back otherwise.
+/// Malformed parameters are answered with an `InvalidParams` error response.
+fn cast<R: lsp_types::request::Request>(
+    req: Request,
+) -> std::result::Result<std::result::Result<(RequestId, R::Params), Response>, Request> {
+    let id = req.id.clone();
+    match req.extract(R::METHOD) {
+        Ok(params) => Ok(Ok(params)),
+        Err(ExtractError::MethodMismatch(req)) => Err(req),
+        Err(ExtractError::JsonError { method, error }) => Ok(Err(Response::new_err(
+            id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            format!("invalid parameters for {method}: {error}"),
+        ))),
+    }
+}
+
+fn handle_request(server: &Server, req: Request) -> Response {
+    let req = match cast::<GotoDefinition>(req) {
+        Ok(Err(response)) => return response,
+        Ok(Ok((id, params))) => {
+            let params = params.text_document_position_params;
+            let result = server.definition(&params.text_document.uri, params.position);
+            return Response::new_ok(id, result);
+        }
+        Err(req) => req,
+    };
+    let req = match cast::<HoverRequest>(req) {
+        Ok(Err(response)) => return response,
+        Ok(Ok((id, params))) => {
+            let params = params.text_document_position_params;
+            let result = server.hover(&params.text_document.uri, params.position);
+            return Response::new_ok(id, result);
+        }
+        Err(req) => req,
+    };
+    let req = match cast::<DocumentSymbolRequest>(req) {
+        Ok(Err(response)) => return response,
+        Ok(Ok((id, params))) => {
+            let result = server
+                .document_symbols(&params.text_document.uri)
+                .map(DocumentSymbolResponse::Nested);
+            return Response::new_ok(id, result);
+        }
+        Err(req) => req,
+    };
+    Response::new_err(
+        req.id,
+        lsp_server::ErrorCode::MethodNotFound as i32,
+        format!("unsupported request {}", req.method),
+    )
+}
+
+/// Handles a notification, and returns whether the contents of a document have changed.
+fn handle_notification(server: &mut Server, not: Notification) -> bool {
+    match not.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let Ok(params) =
+                not.extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+            else {
+                return false;
+            };
+            let doc = params.text_document;
+            server.update(&doc.uri, Some(doc.text));
+            true
+        }
+        DidChangeTextDocument::METHOD => {
+            let Ok(mut params) = not
+                .extract::<lsp_types::DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+            else {
+                return false;
+            };
+            // With full document synchronization, the last change is the whole document
+            let Some(change) = params.content_changes.pop() else {
+                return false;
+            };
+            server.update(&params.text_document.uri, Some(change.text));
+            true
+        }
+        DidCloseTextDocument::METHOD => {
+            let Ok(params) =
+                not.extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+            else {
+                return false;
+            };
+            server.update(&params.text_document.uri, None);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn publish_diagnostics(connection: &Connection, server: &mut Server) -> Result<()> {
+    for (uri, diagnostics) in server.diagnostics() {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        connection.sender.send(Message::Notification(not))?;
+    }
+    Ok(())
+}
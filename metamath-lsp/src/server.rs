@@ -0,0 +1,438 @@
+use lsp_types::{
+    DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, GotoDefinitionResponse,
+    Hover, HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, Position, Range,
+    SymbolKind, Uri,
+};
+use metamath_rs::diag::{LocatedAnnotation, LocatedDiagnostic};
+use metamath_rs::line_cache::LineCache;
+use metamath_rs::outline::OutlineNodeRef;
+use metamath_rs::scopeck::Hyp;
+use metamath_rs::statement::{StatementAddress, SymbolType};
+use metamath_rs::{as_str, Database, StatementRef, StatementType};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Converts a file path to a `file://` URI.
+fn path_to_uri(path: &Path) -> Option<Uri> {
+    let mut uri = String::from("file://");
+    for &c in path.to_str()?.as_bytes() {
+        if c.is_ascii_alphanumeric() || b"/-._~".contains(&c) {
+            uri.push(c as char);
+        } else {
+            uri.push_str(&format!("%{c:02X}"));
+        }
+    }
+    Uri::from_str(&uri).ok()
+}
+
+/// Converts a `file://` URI to a file path.
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    let path = uri.as_str().strip_prefix("file://")?.as_bytes();
+    let mut out = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.filter(|_| path[i] == b'%') {
+            Some(hex) => {
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            None => {
+                out.push(path[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(out).ok()?))
+}
+
+/// Returns the number of UTF-16 code units in a piece of text.
+fn utf16_len(text: &[u8]) -> usize {
+    String::from_utf8_lossy(text).encode_utf16().count()
+}
+
+/// Returns the LSP position of a byte offset in a buffer, given its line and byte column
+/// (both starting from 1). With `utf16`, the column is counted in UTF-16 code units
+/// from the start of the line, as expected by LSP clients by default.
+fn lsp_position(
+    text: Option<&[u8]>,
+    offset: usize,
+    (line, col): (u32, u32),
+    utf16: bool,
+) -> Position {
+    let line_start = offset + 1 - col as usize;
+    match text.and_then(|text| text.get(line_start..offset)) {
+        Some(prefix) if utf16 => Position::new(line - 1, utf16_len(prefix) as u32),
+        _ => Position::new(line - 1, col - 1),
+    }
+}
+
+/// Converts a byte offset in a buffer to an LSP position.
+fn position(lc: &mut LineCache, text: &[u8], offset: usize, utf16: bool) -> Position {
+    let offset = offset.min(text.len());
+    lsp_position(Some(text), offset, lc.from_offset(text, offset), utf16)
+}
+
+/// Converts an LSP position to a byte offset in a buffer.
+fn offset(text: &[u8], position: Position, utf16: bool) -> usize {
+    let line_start = (text.split_inclusive(|&c| c == b'\n'))
+        .take(position.line as usize)
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    let line = text[line_start.min(text.len())..]
+        .split(|&c| c == b'\n')
+        .next()
+        .unwrap_or_default();
+    let col = if utf16 {
+        let mut units = 0;
+        let line_text = String::from_utf8_lossy(line);
+        (line_text.char_indices())
+            .find(|&(_, c)| {
+                units += c.len_utf16();
+                units > position.character as usize
+            })
+            .map_or(line.len(), |(i, _)| i)
+    } else {
+        position.character as usize
+    };
+    (line_start + col).min(text.len())
+}
+
+/// Returns the whitespace-delimited token around the given offset.
+fn token_at(text: &[u8], offset: usize) -> Option<&[u8]> {
+    let start = text[..offset]
+        .iter()
+        .rposition(u8::is_ascii_whitespace)
+        .map_or(0, |i| i + 1);
+    let end = text[offset..]
+        .iter()
+        .position(u8::is_ascii_whitespace)
+        .map_or(text.len(), |i| offset + i);
+    (start < end).then(|| &text[start..end])
+}
+
+/// Returns the LSP range of an annotation, in the file with the given text.
+fn range(ann: &LocatedAnnotation, text: Option<&[u8]>, utf16: bool) -> Range {
+    Range::new(
+        lsp_position(text, ann.range.start, ann.start, utf16),
+        lsp_position(text, ann.range.end, ann.end, utf16),
+    )
+}
+
+/// Converts a diagnostic level to an LSP severity.
+fn severity(level: annotate_snippets::Level) -> DiagnosticSeverity {
+    use annotate_snippets::Level;
+    match level {
+        Level::Error => DiagnosticSeverity::ERROR,
+        Level::Warning => DiagnosticSeverity::WARNING,
+        Level::Info | Level::Note => DiagnosticSeverity::INFORMATION,
+        Level::Help => DiagnosticSeverity::HINT,
+    }
+}
+
+/// The state of the language server: the database, and the unsaved editor buffers.
+pub struct Server {
+    db: Database,
+    /// Name of the main database file, if known yet.
+    start: Option<String>,
+    /// Contents of the open documents, by file name as known to the database.
+    overlays: HashMap<String, Vec<u8>>,
+    /// Files for which non-empty diagnostics have been published.
+    published: HashSet<String>,
+    /// Whether positions are counted in UTF-16 code units, rather than in bytes.
+    utf16: bool,
+}
+
+impl Server {
+    /// Creates a server for the given database, which is loaded on the first
+    /// document opened if not provided. Positions exchanged with the client are
+    /// counted in UTF-16 code units if `utf16` is set, and in bytes otherwise.
+    pub fn new(db: Database, start: Option<String>, utf16: bool) -> Self {
+        Self {
+            db,
+            start,
+            overlays: HashMap::new(),
+            published: HashSet::new(),
+            utf16,
+        }
+    }
+
+    /// Returns the file name, as known to the database, for the given document.
+    /// Files which are not (yet) part of the database are named after their path.
+    fn file_name(&self, uri: &Uri) -> Option<String> {
+        let path = uri_to_path(uri)?;
+        let mut names = self.db.source_names().chain(self.start.as_deref());
+        let known = names.find(|name| std::path::absolute(name).ok().as_ref() == Some(&path));
+        Some(known.map_or_else(|| path.to_string_lossy().into_owned(), str::to_owned))
+    }
+
+    /// Returns the current contents of a file, from the open documents or from disk.
+    fn file_text(&self, name: &str) -> Option<Vec<u8>> {
+        (self.overlays.get(name).cloned()).or_else(|| std::fs::read(name).ok())
+    }
+
+    /// Returns the URI of a file known to the database.
+    fn file_uri(name: &str) -> Option<Uri> {
+        path_to_uri(&std::path::absolute(name).ok()?)
+    }
+
+    /// Records the new contents of a document.
+    /// The database is only parsed again by [`Server::reload`].
+    pub fn update(&mut self, uri: &Uri, text: Option<String>) {
+        let Some(name) = self.file_name(uri) else {
+            return;
+        };
+        match text {
+            Some(text) => {
+                self.start.get_or_insert_with(|| name.clone());
+                self.overlays.insert(name, text.into_bytes());
+            }
+            None => {
+                self.overlays.remove(&name);
+            }
+        }
+    }
+
+    /// Returns whether the main database file is known yet.
+    pub const fn has_database(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// Parses the database again and runs all passes.
+    pub fn reload(&mut self) {
+        let Some(start) = self.start.clone() else {
+            return;
+        };
+        let text = (self.overlays.iter())
+            .map(|(name, text)| (name.clone(), text.clone()))
+            .collect();
+        self.db.parse(start, text);
+        self.db.scope_pass();
+        self.db.verify_pass();
+        self.db.verify_usage_pass();
+        self.db.grammar_pass();
+        self.db.stmt_parse_pass();
+        self.db.typesetting_pass();
+        self.db.outline_pass();
+        self.db.lint_pass();
+    }
+
+    /// Returns the diagnostics to publish for each document, including
+    /// empty lists for documents whose diagnostics have been fixed.
+    pub fn diagnostics(&mut self) -> Vec<(Uri, Vec<lsp_types::Diagnostic>)> {
+        #[allow(unused_mut)]
+        let mut diags = self.db.diag_notations();
+        #[cfg(feature = "verify_markup")]
+        diags.extend(self.db.verify_markup(None));
+        let mut by_file: HashMap<String, Vec<lsp_types::Diagnostic>> = HashMap::new();
+        let mut texts: HashMap<String, Option<Vec<u8>>> = HashMap::new();
+        let mut locate = |ann: &LocatedAnnotation| {
+            let text = (texts.entry(ann.file.clone()))
+                .or_insert_with(|| self.file_text(&ann.file).filter(|_| self.utf16));
+            range(ann, text.as_deref(), self.utf16)
+        };
+        for diag in self.db.locate_diags(diags) {
+            let LocatedDiagnostic {
+                code,
+                level,
+                title,
+                annotations,
+                notes,
+                ..
+            } = diag;
+            let Some((primary, others)) = annotations.split_first() else {
+                continue;
+            };
+            let related = (others.iter())
+                .filter_map(|ann| {
+                    Some(DiagnosticRelatedInformation {
+                        location: Location::new(Self::file_uri(&ann.file)?, locate(ann)),
+                        message: ann.message.clone(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut message = format!("{title}: {}", primary.message);
+            for note in notes {
+                message.push_str("\n\n");
+                message.push_str(&note);
+            }
+            let file = by_file.entry(primary.file.clone()).or_default();
+            file.push(lsp_types::Diagnostic {
+                range: locate(primary),
+                severity: Some(severity(level)),
+                code: Some(NumberOrString::String(code.to_owned())),
+                source: Some("metamath".to_owned()),
+                message,
+                related_information: (!related.is_empty()).then_some(related),
+                ..Default::default()
+            });
+        }
+        let published = by_file.keys().cloned().collect::<HashSet<_>>();
+        for name in self.published.difference(&published) {
+            by_file.insert(name.clone(), vec![]);
+        }
+        self.published = published;
+        (by_file.into_iter())
+            .filter_map(|(name, diags)| Some((Self::file_uri(&name)?, diags)))
+            .collect()
+    }
+
+    /// Returns the location of the given span of a statement.
+    fn location(&self, addr: StatementAddress, span: metamath_rs::Span) -> Option<Location> {
+        let name = self.db.statement_source_name(addr);
+        let text = self.db.statement_source_text(addr);
+        let range = self.db.statement_source_range(addr, span);
+        let mut lc = LineCache::default();
+        let range = Range::new(
+            position(&mut lc, text, range.start, self.utf16),
+            position(&mut lc, text, range.end, self.utf16),
+        );
+        Some(Location::new(Self::file_uri(name)?, range))
+    }
+
+    /// Returns the token under the cursor.
+    fn token_at(&self, uri: &Uri, pos: Position) -> Option<Vec<u8>> {
+        let text = self.file_text(&self.file_name(uri)?)?;
+        token_at(&text, offset(&text, pos, self.utf16)).map(<[u8]>::to_vec)
+    }
+
+    /// Finds the definition of the label or math symbol under the cursor.
+    pub fn definition(&self, uri: &Uri, pos: Position) -> Option<GotoDefinitionResponse> {
+        let token = self.token_at(uri, pos)?;
+        let location = if let Some(stmt) = self.db.statement(&token) {
+            self.location(stmt.address(), stmt.label_span())
+        } else {
+            let symbol = self.db.name_result().lookup_symbol(&token)?;
+            let stmt = self.db.statement_by_address(symbol.address.statement);
+            self.location(stmt.address(), stmt.math_span(symbol.address.token_index))
+        };
+        location.map(GotoDefinitionResponse::Scalar)
+    }
+
+    /// Describes the statement or math symbol under the cursor.
+    pub fn hover(&self, uri: &Uri, pos: Position) -> Option<Hover> {
+        let token = self.token_at(uri, pos)?;
+        let value = if let Some(stmt) = self.db.statement(&token) {
+            self.describe_statement(stmt)
+        } else {
+            let symbol = self.db.name_result().lookup_symbol(&token)?;
+            let kind = match symbol.stype {
+                SymbolType::Constant => "Constant",
+                SymbolType::Variable => "Variable",
+            };
+            let rendered = self.db.unicode_renderer(false).render([&*token]);
+            format!("{kind} `{}` &nbsp; {}", as_str(&token), rendered.trim())
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        })
+    }
+
+    /// Formats a statement with its frame and its comment, in Markdown.
+    fn describe_statement(&self, stmt: StatementRef<'_>) -> String {
+        fn write_stmt(out: &mut String, stmt: StatementRef<'_>, keyword: &str) {
+            out.push_str(&format!("{} {keyword}", as_str(stmt.label())));
+            for token in stmt.math_iter() {
+                out.push(' ');
+                out.push_str(as_str(&token));
+            }
+            out.push_str(" $.\n");
+        }
+        let keyword = match stmt.statement_type() {
+            StatementType::Axiom => "$a",
+            StatementType::Provable => "$p",
+            StatementType::Essential => "$e",
+            StatementType::Floating => "$f",
+            _ => "",
+        };
+        let mut out = String::from("```metamath\n");
+        if let Some(frame) = self.db.scope_result().get(stmt.label()) {
+            let nset = self.db.name_result();
+            for &(v1, v2) in &*frame.mandatory_dv {
+                out.push_str(&format!(
+                    "$d {} {} $.\n",
+                    as_str(nset.atom_name(frame.var_list[v1])),
+                    as_str(nset.atom_name(frame.var_list[v2]))
+                ));
+            }
+            for hyp in &*frame.hypotheses {
+                if let Hyp::Essential(addr, _) = hyp {
+                    write_stmt(&mut out, self.db.statement_by_address(*addr), "$e");
+                }
+            }
+        }
+        write_stmt(&mut out, stmt, keyword);
+        out.push_str("```\n");
+        if let Some(comment) = stmt.associated_comment() {
+            let text = self.db.statement_source_text(comment.address());
+            let range =
+                (self.db).statement_source_range(comment.address(), comment.comment_contents());
+            out.push('\n');
+            out.push_str(as_str(&text[range]).trim());
+        }
+        out
+    }
+
+    /// Lists the chapters and assertions of the given document, from the database outline.
+    pub fn document_symbols(&self, uri: &Uri) -> Option<Vec<DocumentSymbol>> {
+        let name = self.file_name(uri)?;
+        self.start.as_ref()?;
+        Some(self.outline_symbols(OutlineNodeRef::root_node(&self.db), &name))
+    }
+
+    /// Builds the symbols for the children of an outline node which are in the given file.
+    /// Chapters whose header is in another file are replaced by their children.
+    fn outline_symbols(&self, node: OutlineNodeRef<'_>, file: &str) -> Vec<DocumentSymbol> {
+        let mut symbols = vec![];
+        for child in node.children_iter() {
+            let stmt = child.get_statement();
+            let in_file = self.db.statement_source_name(stmt.address()) == file;
+            let (kind, children) = match child {
+                OutlineNodeRef::Chapter { .. } => {
+                    let children = self.outline_symbols(child, file);
+                    if !in_file {
+                        symbols.extend(children);
+                        continue;
+                    }
+                    (SymbolKind::NAMESPACE, Some(children))
+                }
+                OutlineNodeRef::Statement { .. } if in_file => match stmt.statement_type() {
+                    StatementType::Provable => (SymbolKind::FUNCTION, None),
+                    StatementType::Axiom => (SymbolKind::CONSTANT, None),
+                    _ => continue,
+                },
+                OutlineNodeRef::Statement { .. } => continue,
+            };
+            let (Some(range), Some(selection)) = (
+                self.location(stmt.address(), child.get_span()),
+                self.location(stmt.address(), stmt.label_span()),
+            ) else {
+                continue;
+            };
+            let selection_range = if stmt.label().is_empty() {
+                range.range
+            } else {
+                selection.range
+            };
+            #[allow(deprecated)]
+            symbols.push(DocumentSymbol {
+                name: child.get_name().to_owned(),
+                detail: None,
+                kind,
+                tags: None,
+                deprecated: None,
+                range: range.range,
+                selection_range,
+                children,
+            });
+        }
+        symbols
+    }
+}
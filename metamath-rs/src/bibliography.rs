@@ -0,0 +1,166 @@
+//! Generation of the bibliography cross-reference index.
+//!
+//! Comments refer to bibliographic entries using tags like `[Monk]`, generally
+//! surrounded by some context, as in `Theorem 3.2 of [Monk] p. 23`.
+//! This module collects these citations, and rewrites the cross-reference table
+//! of a bibliography file like `mmbiblio.html`, which is found between the
+//! `<!-- #START# -->` and `<!-- #END# -->` markers, the way the
+//! `WRITE BIBLIOGRAPHY` command of
+//! [`metamath.exe`](https://github.com/metamath/metamath-exe) does.
+
+use crate::comment_parser::CommentItem;
+use crate::database::time;
+use crate::html::write_escaped;
+use crate::statement::TokenPtr;
+use crate::{as_str, Database, StatementRef};
+use regex::bytes::Regex;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+/// The marker starting the cross-reference table in a bibliography file.
+const START_MARKER: &[u8] = b"<!-- #START# -->";
+/// The marker ending the cross-reference table in a bibliography file.
+const END_MARKER: &[u8] = b"<!-- #END# -->";
+
+/// A reference to a bibliography entry, made in a statement's comment.
+#[derive(Debug, Clone)]
+pub struct BibCitation<'a> {
+    /// The statement whose comment contains the citation.
+    pub stmt: StatementRef<'a>,
+    /// The bibliographic tag, without the brackets.
+    pub tag: TokenPtr<'a>,
+    /// The page and item referenced, like `p. 23 Theorem 3.2`,
+    /// which may be empty if the citation has no recognizable context.
+    pub context: String,
+}
+
+/// Normalizes the whitespace of a piece of comment text.
+fn normalize(text: &[u8]) -> String {
+    let text = String::from_utf8_lossy(text);
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Extracts the item referenced in the text preceding a tag,
+/// like `Theorem 3.2` in `... by Theorem 3.2 of [Monk]`.
+pub(crate) fn context_before(text: &[u8]) -> Option<String> {
+    static BEFORE: OnceLock<Regex> = OnceLock::new();
+    let before = BEFORE.get_or_init(|| {
+        Regex::new(r"([A-Z][A-Za-z]*\.?[ \r\n]+[0-9IVX][^ \r\n,;:]*)[ \r\n]+(?:of|in)[ \r\n]*$")
+            .unwrap()
+    });
+    Some(normalize(before.captures(text)?.get(1)?.as_bytes()))
+}
+
+/// Extracts the pages referenced in the text following a tag,
+/// like `p. 23` in `[Monk] p. 23.`
+pub(crate) fn context_after(text: &[u8]) -> Option<String> {
+    static AFTER: OnceLock<Regex> = OnceLock::new();
+    let after = AFTER.get_or_init(|| {
+        Regex::new(r"^[ \r\n]*,?[ \r\n]*(pp?\.[ \r\n]*[0-9A-Za-z]+(?:-[0-9A-Za-z]+)?)").unwrap()
+    });
+    Some(normalize(after.captures(text)?.get(1)?.as_bytes()))
+}
+
+impl Database {
+    /// Collects all the bibliographic references made in the comments of
+    /// assertions, sorted by tag (case insensitively), then by context,
+    /// then in database order.
+    #[must_use]
+    pub fn bib_citations(&self) -> Vec<BibCitation<'_>> {
+        let mut citations = vec![];
+        for stmt in self.statements().filter(|stmt| stmt.is_assertion()) {
+            let Some(comment) = stmt.associated_comment() else {
+                continue;
+            };
+            let buf = &**comment.segment().segment.buffer;
+            let parser = comment.comment_parser();
+            let items: Vec<_> = parser.clone().collect();
+            let text = |item: Option<&CommentItem>| {
+                let mut out = vec![];
+                if let Some(&CommentItem::Text(span)) = item {
+                    parser.unescape_text(span, &mut out);
+                }
+                out
+            };
+            for (i, item) in items.iter().enumerate() {
+                let CommentItem::BibTag(span) = *item else {
+                    continue;
+                };
+                let before = i.checked_sub(1).and_then(|j| items.get(j));
+                let context = [
+                    context_after(&text(items.get(i + 1))),
+                    context_before(&text(before)),
+                ];
+                citations.push(BibCitation {
+                    stmt,
+                    tag: span.as_ref(buf),
+                    context: context.into_iter().flatten().collect::<Vec<_>>().join(" "),
+                });
+            }
+        }
+        citations.sort_by(|a, b| {
+            (a.tag.to_ascii_lowercase().cmp(&b.tag.to_ascii_lowercase()))
+                .then_with(|| a.context.cmp(&b.context))
+        });
+        citations
+    }
+
+    /// Writes the given bibliography file `template` to `out`, replacing its
+    /// cross-reference table with the citations made in the database.
+    ///
+    /// If the database has an `exthtmllabel`, citations in statements before it
+    /// are written when `ext` is `false`, and citations in statements after it
+    /// are written when `ext` is `true`.
+    pub fn write_bibliography(
+        &self,
+        template: &[u8],
+        ext: bool,
+        out: &mut impl Write,
+    ) -> Result<(), io::Error> {
+        time(&self.options.clone(), "bibliography", || {
+            let find = |marker: &[u8]| {
+                template
+                    .windows(marker.len())
+                    .position(|window| window == marker)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("missing {} marker in bibliography", as_str(marker)),
+                        )
+                    })
+            };
+            let start = find(START_MARKER)? + START_MARKER.len();
+            let end = find(END_MARKER)?;
+            if end < start {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "bibliography markers are out of order",
+                ));
+            }
+            let ext_start = (self.typesetting_result().ext_html_label.as_ref())
+                .and_then(|(_, label)| self.statement(label))
+                .map(StatementRef::address);
+            out.write_all(&template[..start])?;
+            writeln!(out)?;
+            for citation in self.bib_citations() {
+                let is_ext = ext_start.is_some_and(|ext_start| {
+                    self.cmp_address(&citation.stmt.address(), &ext_start)
+                        .is_ge()
+                });
+                if is_ext != ext {
+                    continue;
+                }
+                let label = as_str(citation.stmt.label());
+                write!(out, "[")?;
+                write_escaped(out, citation.tag)?;
+                write!(out, "]")?;
+                if !citation.context.is_empty() {
+                    write!(out, " ")?;
+                    write_escaped(out, citation.context.as_bytes())?;
+                }
+                writeln!(out, ": <A HREF=\"{label}.html\">{label}</A><BR>")?;
+            }
+            out.write_all(&template[end..])
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use crate::bibliography::{context_after, context_before};
+use crate::grammar_tests::mkdb;
+
+#[test]
+fn test_context_before() {
+    assert_eq!(
+        context_before(b"This is Theorem 3.2 of "),
+        Some("Theorem 3.2".into())
+    );
+    assert_eq!(
+        context_before(b"see Lemma\n  IV in"),
+        Some("Lemma IV".into())
+    );
+    assert_eq!(context_before(b"Definition 2.1, in "), None);
+    assert_eq!(context_before(b"This is Theorem 3.2 "), None);
+    assert_eq!(context_before(b"This is theorem 3.2 of "), None);
+    assert_eq!(context_before(b""), None);
+}
+
+#[test]
+fn test_context_after() {
+    assert_eq!(context_after(b" p. 23."), Some("p. 23".into()));
+    assert_eq!(context_after(b", pp. 1-2, and"), Some("pp. 1-2".into()));
+    assert_eq!(context_after(b"\n  p.\n  xiv"), Some("p. xiv".into()));
+    assert_eq!(context_after(b" page 23"), None);
+    assert_eq!(context_after(b" and p. 23"), None);
+    assert_eq!(context_after(b""), None);
+}
+
+#[test]
+fn test_write_bibliography() {
+    let mut db = mkdb(
+        b"$c |- ph $.
+$( This is Theorem 3.2 of [Monk] p. 23. $)
+ax-1 $a |- ph $.
+$( See Theorem 1<2 in [Monk], pp. 1-2, and [Tarski&Givant]. $)
+ax-2 $a |- ph $.
+$( No citation here. $)
+ax-3 $a |- ph $.
+",
+    );
+    db.scope_pass();
+    db.typesetting_pass();
+    let mut out = vec![];
+    db.write_bibliography(
+        b"<HTML>\n<!-- #START# -->\nold\n<!-- #END# -->\n</HTML>\n",
+        false,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "<HTML>\n<!-- #START# -->\n\
+         [Monk] p. 23 Theorem 3.2: <A HREF=\"ax-1.html\">ax-1</A><BR>\n\
+         [Monk] pp. 1-2 Theorem 1&lt;2: <A HREF=\"ax-2.html\">ax-2</A><BR>\n\
+         [Tarski&amp;Givant]: <A HREF=\"ax-2.html\">ax-2</A><BR>\n\
+         <!-- #END# -->\n</HTML>\n"
+    );
+}
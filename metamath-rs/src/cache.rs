@@ -0,0 +1,193 @@
+//! Persistent cache of verification results across runs.
+//!
+//! The in-memory incremental machinery only helps within a single process. To
+//! also avoid re-verifying an unchanged database on each invocation, the
+//! segments which verified without any error can be recorded in a cache
+//! directory set with [`Database::set_cache_dir`], and skipped by the
+//! verification passes of later runs.
+//!
+//! Only the verification results are cached: the database is still parsed, and
+//! its names and scopes still computed on each run, since the hashes below are
+//! computed from the parsed statements and from the frames of the scope pass.
+//!
+//! Whether a segment verifies only depends on its own statements, and on the
+//! frames of the assertions and hypotheses its proofs refer to. Each segment is
+//! therefore identified by a hash of the text of its statements, and of the
+//! text, hypotheses, variables and disjoint variable conditions of the frames
+//! referenced by its proofs, together with whether they are defined before the
+//! referencing proof. Editing a segment thus only invalidates it and the
+//! segments whose proofs refer to the edited statements.
+//! Segments with errors are never cached, so their diagnostics are always
+//! recomputed.
+//!
+//! The hashes are not cryptographic: a cache directory shall not be shared with
+//! untrusted parties.
+
+use crate::nameck::Nameset;
+use crate::scopeck::{Frame, ScopeResult};
+use crate::segment::Comparer;
+use crate::segment_set::SegmentSet;
+use crate::statement::SegmentId;
+use crate::util::{HashMap, HashSet};
+use crate::{Database, StatementRef, StatementType};
+use fnv::FnvHasher;
+use std::cmp::Ordering;
+use std::hash::Hasher;
+use std::path::PathBuf;
+use std::{fs, io, iter};
+
+/// Name of the cache file, within the cache directory.
+const CACHE_FILE: &str = "verify.cache";
+
+/// The first line of the cache file. Caches written by another version are
+/// ignored, since the verifier may have changed.
+const CACHE_HEADER: &str = concat!("metamath-rs verify cache ", env!("CARGO_PKG_VERSION"));
+
+/// A pair of FNV hashers with different keys, for a 128-bit hash.
+struct Hasher128([FnvHasher; 2]);
+
+impl Hasher128 {
+    fn new() -> Self {
+        Self([
+            FnvHasher::default(),
+            FnvHasher::with_key(0x6d6d_6b6e_6966_6521),
+        ])
+    }
+
+    /// Hashes a byte string, followed by a separator.
+    fn write(&mut self, bytes: &[u8]) {
+        for hasher in &mut self.0 {
+            hasher.write(bytes);
+            hasher.write_u8(0);
+        }
+    }
+
+    /// Hashes the text of a statement.
+    fn write_statement(&mut self, stmt: StatementRef<'_>) {
+        self.write(stmt.span().as_ref(&stmt.segment().segment.buffer));
+    }
+
+    /// Hashes the parts of a frame which do not depend on the interning of
+    /// the names in the current run: the text of its hypotheses, the names of
+    /// its variables and its disjoint variable conditions.
+    fn write_frame(&mut self, sset: &SegmentSet, nset: &Nameset, frame: &Frame) {
+        for hyp in &*frame.hypotheses {
+            self.write_statement(sset.statement(hyp.address()));
+        }
+        for &var in &*frame.var_list {
+            self.write(nset.atom_name(var));
+        }
+        self.write(&frame.mandatory_count.to_le_bytes());
+        for &(var1, var2) in &*frame.mandatory_dv {
+            self.write(&[var1.to_le_bytes(), var2.to_le_bytes()].concat());
+        }
+        for (var1, vars) in frame.optional_dv.iter().enumerate() {
+            for var2 in vars {
+                self.write(&[var1.to_le_bytes(), var2.to_le_bytes()].concat());
+            }
+        }
+    }
+
+    fn finish(&self) -> u128 {
+        u128::from(self.0[0].finish()) << 64 | u128::from(self.0[1].finish())
+    }
+}
+
+/// Returns the hash of each segment of the database, covering its statements
+/// and the frames its proofs refer to.
+pub(crate) fn segment_hashes(
+    sset: &SegmentSet,
+    nset: &Nameset,
+    scope: &ScopeResult,
+) -> HashMap<SegmentId, u128> {
+    let mut out = HashMap::default();
+    for sref in sset.segments(..) {
+        let mut hasher = Hasher128::new();
+        for stmt in sref.range(..) {
+            hasher.write_statement(stmt);
+        }
+        for stmt in sref.range(..) {
+            if stmt.statement_type() != StatementType::Provable {
+                continue;
+            }
+            let labels = iter::once(stmt.label()).chain(stmt.use_iter().map(|(_, label)| label));
+            for label in labels {
+                hasher.write(label);
+                let Some(lookup) = nset.lookup_label(label) else {
+                    continue;
+                };
+                let before = sset.order.cmp(&lookup.address, &stmt.address()) == Ordering::Less;
+                hasher.write(&[u8::from(before)]);
+                hasher.write_statement(sset.statement(lookup.address));
+                if let Some(frame) = scope.get(label) {
+                    hasher.write_frame(sset, nset, frame);
+                }
+            }
+        }
+        out.insert(sref.id, hasher.finish());
+    }
+    out
+}
+
+/// The hashes of the segments known to verify without errors.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct VerifyCache {
+    dir: PathBuf,
+    pub(crate) clean: HashSet<u128>,
+}
+
+impl VerifyCache {
+    /// Loads the cache from the given directory.
+    /// A missing or unreadable cache file gives an empty cache.
+    pub(crate) fn load(dir: PathBuf) -> Self {
+        let clean = fs::read_to_string(dir.join(CACHE_FILE))
+            .ok()
+            .filter(|text| text.lines().next() == Some(CACHE_HEADER))
+            .map(|text| {
+                (text.lines().skip(1))
+                    .filter_map(|line| u128::from_str_radix(line, 16).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { dir, clean }
+    }
+
+    /// Writes the cache file, creating the cache directory if needed.
+    fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut hashes = self.clean.iter().collect::<Vec<_>>();
+        hashes.sort_unstable();
+        let mut lines = vec![format!("{CACHE_HEADER}\n")];
+        lines.extend(hashes.iter().map(|hash| format!("{hash:032x}\n")));
+        fs::write(self.dir.join(CACHE_FILE), lines.concat())
+    }
+}
+
+impl Database {
+    /// Sets a directory where the verification results are cached across runs,
+    /// see [`crate::cache`], and loads the results cached by previous runs.
+    /// The other passes are not cached.
+    /// This shall be called before [`Database::verify_pass`].
+    pub fn set_cache_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.verify_cache = Some(VerifyCache::load(dir.into()));
+    }
+
+    /// Records the segments which verified without errors in the cache
+    /// directory, replacing the previous cache.
+    /// Does nothing if no cache directory was set, or if
+    /// [`Database::verify_pass`] was not previously called.
+    pub fn save_cache(&mut self) -> io::Result<()> {
+        let Some(verify) = self.try_verify_result().cloned() else {
+            return Ok(());
+        };
+        let hashes = segment_hashes(self.parse_result(), self.name_result(), self.scope_result());
+        let Some(cache) = &mut self.verify_cache else {
+            return Ok(());
+        };
+        cache.clean = (hashes.into_iter())
+            .filter(|(id, _)| verify.is_clean(*id))
+            .map(|(_, hash)| hash)
+            .collect();
+        cache.save()
+    }
+}
@@ -0,0 +1,133 @@
+use crate::cache::segment_hashes;
+use crate::grammar_tests::mkdb;
+use crate::statement::SegmentId;
+use crate::util::HashMap;
+use crate::Database;
+use std::fs;
+
+const GOOD_DB: &[u8] = b"$c |- wff ( ) -> $.
+  $v ph ps $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  th1 $p |- ( ph -> ( ph -> ph ) ) $= wph wph ax-1 $.
+";
+
+const BAD_DB: &[u8] = b"$c |- wff ( ) -> $.
+  $v ph ps $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  th1 $p |- ( ph -> ( ph -> ph ) ) $= wph wps ax-1 $.
+";
+
+/// Returns the hashes of the segments of the database.
+fn segment_hashes_of(db: &mut Database) -> HashMap<SegmentId, u128> {
+    db.scope_pass();
+    segment_hashes(db.parse_result(), db.name_result(), db.scope_result())
+}
+
+fn cache_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("metamath-rs-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_cache_round_trip() {
+    let dir = cache_dir("cache-round-trip");
+    let mut db = mkdb(GOOD_DB);
+    db.set_cache_dir(&dir);
+    assert!(db.verify_pass().diagnostics().is_empty());
+    db.save_cache().unwrap();
+    let hashes = segment_hashes_of(&mut db);
+    let text = fs::read_to_string(dir.join("verify.cache")).unwrap();
+    let lines = text.lines().skip(1).collect::<Vec<_>>();
+    assert_eq!(lines.len(), hashes.len());
+    for hash in hashes.values() {
+        assert!(lines.contains(&&*format!("{hash:032x}")));
+    }
+
+    // Segments with errors are not cached
+    let mut db = mkdb(BAD_DB);
+    db.set_cache_dir(&dir);
+    assert!(!db.verify_pass().diagnostics().is_empty());
+    db.save_cache().unwrap();
+    let bad_hashes = segment_hashes_of(&mut db);
+    let text = fs::read_to_string(dir.join("verify.cache")).unwrap();
+    assert!(text.lines().count() - 1 < bad_hashes.len());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cache_skips_verification() {
+    let dir = cache_dir("cache-skip");
+    let good = segment_hashes_of(&mut mkdb(GOOD_DB));
+    let bad = segment_hashes_of(&mut mkdb(BAD_DB));
+    assert!(good
+        .values()
+        .all(|hash| !bad.values().any(|other| other == hash)));
+
+    // Pretend that the faulty database was verified: its proof is not checked again
+    let mut lines = vec![format!(
+        "metamath-rs verify cache {}",
+        env!("CARGO_PKG_VERSION")
+    )];
+    lines.extend(bad.values().map(|hash| format!("{hash:032x}")));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("verify.cache"), lines.join("\n")).unwrap();
+    let mut db = mkdb(BAD_DB);
+    db.set_cache_dir(&dir);
+    assert!(db.verify_pass().diagnostics().is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Loads a database made of a main file including two other files.
+fn mkdb_files(first: &[u8], second: &[u8]) -> Database {
+    let mut db = mkdb(b"");
+    db.parse(
+        "main.mm".to_owned(),
+        vec![
+            ("main.mm".to_owned(), b"$[ a.mm $]\n$[ b.mm $]\n".to_vec()),
+            ("a.mm".to_owned(), first.to_owned()),
+            ("b.mm".to_owned(), second.to_owned()),
+        ],
+    );
+    db
+}
+
+#[test]
+fn test_cache_dependencies() {
+    const FIRST: &str = "$c |- wff ( ) -> $.
+  $v ph ps $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ax-2 $a |- ( ph -> ph ) $.
+";
+    const SECOND: &[u8] = b"th1 $p |- ( ph -> ( ph -> ph ) ) $= wph wph ax-1 $.\n";
+    let segment = |db: &mut Database, name: &str| {
+        let hashes = segment_hashes_of(db);
+        let sset = db.parse_result();
+        let id = (sset.segments(..))
+            .find(|sref| sset.source_info(sref.id).name == name && sref.range(..).next().is_some())
+            .unwrap()
+            .id;
+        hashes[&id]
+    };
+    let hash = segment(&mut mkdb_files(FIRST.as_bytes(), SECOND), "b.mm");
+
+    // Editing an unrelated statement keeps the hash of the segment
+    let first = FIRST.replace("ph -> ph", "ps -> ps");
+    assert_eq!(
+        segment(&mut mkdb_files(first.as_bytes(), SECOND), "b.mm"),
+        hash
+    );
+
+    // Editing a statement referenced by its proof changes it
+    let first = FIRST.replace("ps -> ph ) )", "ps -> ps ) )");
+    assert_ne!(
+        segment(&mut mkdb_files(first.as_bytes(), SECOND), "b.mm"),
+        hash
+    );
+}
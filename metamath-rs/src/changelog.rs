@@ -0,0 +1,230 @@
+//! Change log entries for the labels renamed and deleted between two versions
+//! of a database.
+//!
+//! set.mm keeps a list of such changes in its "Recent label changes" comment,
+//! with one line per change: the date, the old label, the new label (or
+//! `[deleted]`), and free notes. This module detects the changes with
+//! [`Database::diff`], and suggests replacements for the deleted theorems.
+
+use crate::comment_parser::Date;
+use crate::diff::StatementChange;
+use crate::formula::{Formula, Substitutions};
+use crate::scopeck::{Frame, Hyp};
+use crate::util::HashSet;
+use crate::{as_str, Database, StatementRef};
+use std::io;
+
+/// A change of label, to be recorded in the change log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelChange {
+    /// The assertion was renamed, keeping the same statement, hypotheses and proof.
+    Renamed {
+        /// The label in the old version.
+        from: String,
+        /// The label in the new version.
+        to: String,
+    },
+    /// The assertion was deleted.
+    Deleted {
+        /// The label of the deleted assertion.
+        label: String,
+        /// An assertion of the new version with the same statement,
+        /// or a more general one, which can be used instead.
+        replacement: Option<String>,
+    },
+}
+
+/// Returns the math tokens of the given statement.
+fn math_tokens(stmt: StatementRef<'_>) -> Vec<&[u8]> {
+    stmt.math_iter().map(|token| token.slice).collect()
+}
+
+/// Unifies each of the hypotheses of a candidate replacement with one of the
+/// given hypotheses, completing the given substitutions.
+fn unify_hyps(
+    hyps: &[Formula],
+    models: &[&Formula],
+    substitutions: &Substitutions,
+) -> Option<Substitutions> {
+    let Some((model, models)) = models.split_first() else {
+        return Some(substitutions.clone());
+    };
+    hyps.iter().find_map(|hyp| {
+        let mut substitutions = substitutions.clone();
+        hyp.unify(model, &mut substitutions).ok()?;
+        unify_hyps(hyps, models, &substitutions)
+    })
+}
+
+impl Database {
+    /// Returns the names of the variables in the given formula.
+    fn formula_variables(&self, formula: &Formula) -> Vec<&[u8]> {
+        (formula.labels_iter())
+            .filter(|&(_, is_variable)| is_variable)
+            .filter_map(|(label, _)| self.statement_by_label(label))
+            .filter_map(|stmt| Some(stmt.math_iter().nth(1)?.slice))
+            .collect()
+    }
+
+    /// Returns whether the distinct variable conditions of the candidate frame,
+    /// once substituted, are among the given ones.
+    fn disjoints_hold(
+        &self,
+        frame: &Frame,
+        substitutions: &Substitutions,
+        disjoints: &HashSet<(&[u8], &[u8])>,
+    ) -> bool {
+        let nset = self.name_result();
+        let variables = |ix: usize| {
+            (frame.hypotheses.iter())
+                .find_map(|hyp| match *hyp {
+                    Hyp::Floating(addr, var, _) if var == ix => Some(addr),
+                    _ => None,
+                })
+                .and_then(|addr| nset.lookup_label(self.statement_by_address(addr).label()))
+                .and_then(|lookup| substitutions.get(lookup.atom))
+                .map(|formula| self.formula_variables(formula))
+                .unwrap_or_default()
+        };
+        frame.mandatory_dv.iter().all(|&(ix1, ix2)| {
+            let vars2 = variables(ix2);
+            variables(ix1).into_iter().all(|var1| {
+                vars2.iter().all(|&var2| {
+                    var1 != var2 && disjoints.contains(&(var1.min(var2), var1.max(var2)))
+                })
+            })
+        })
+    }
+
+    /// Searches the new version for an assertion which can replace the given
+    /// deleted assertion: one with the same statement, or else the first one
+    /// which is more general, i.e. of which the deleted assertion is an instance.
+    ///
+    /// Requires: [`Database::stmt_parse_pass`] on the new database
+    fn replacement(&self, new: &Self, stmt: StatementRef<'_>) -> Option<String> {
+        let frame = self.scope_result().get(stmt.label())?;
+        let grammar = new.grammar_result();
+        let formulas = new.stmt_parse_result();
+        let nset = new.name_result();
+        let parse = |math: &Vec<&[u8]>| {
+            let text = math.iter().map(|&token| as_str(token)).collect::<Vec<_>>();
+            grammar.parse_string(&text.join(" "), nset).ok()
+        };
+        let hyp_math = (frame.hypotheses.iter())
+            .filter(|hyp| matches!(hyp, Hyp::Essential(..)))
+            .map(|hyp| math_tokens(self.statement_by_address(hyp.address())))
+            .collect::<Vec<_>>();
+        let stmt_math = math_tokens(stmt);
+        let hyps = hyp_math.iter().map(parse).collect::<Option<Vec<_>>>()?;
+        let conclusion = parse(&stmt_math)?;
+        let disjoints = self
+            .disjoint_pairs(frame)
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let mut general = None;
+        for candidate in new.statements().filter(|stmt| stmt.is_assertion()) {
+            if candidate.math_iter().next().map(|token| token.slice) != stmt_math.first().copied() {
+                continue;
+            }
+            let (Some(candidate_frame), Some(candidate_conclusion)) = (
+                new.scope_result().get(candidate.label()),
+                formulas.get_formula(&candidate),
+            ) else {
+                continue;
+            };
+            let candidate_hyps = (candidate_frame.hypotheses.iter())
+                .filter(|hyp| matches!(hyp, Hyp::Essential(..)))
+                .map(|hyp| formulas.get_formula(&new.statement_by_address(hyp.address())))
+                .collect::<Option<Vec<_>>>();
+            let Some(candidate_hyps) = candidate_hyps else {
+                continue;
+            };
+            if candidate_hyps.len() > hyps.len() {
+                continue;
+            }
+            let mut substitutions = Substitutions::default();
+            if conclusion
+                .unify(candidate_conclusion, &mut substitutions)
+                .is_err()
+            {
+                continue;
+            }
+            let Some(substitutions) = unify_hyps(&hyps, &candidate_hyps, &substitutions) else {
+                continue;
+            };
+            if !new.disjoints_hold(candidate_frame, &substitutions, &disjoints) {
+                continue;
+            }
+            let candidate_hyp_math = (candidate_frame.hypotheses.iter())
+                .filter(|hyp| matches!(hyp, Hyp::Essential(..)))
+                .map(|hyp| math_tokens(new.statement_by_address(hyp.address())))
+                .collect::<Vec<_>>();
+            if math_tokens(candidate) == stmt_math && candidate_hyp_math == hyp_math {
+                return Some(as_str(candidate.label()).to_owned());
+            }
+            general.get_or_insert_with(|| as_str(candidate.label()).to_owned());
+        }
+        general
+    }
+
+    /// Compares this database, as the old version, to the given new version,
+    /// and returns the renamed and deleted assertions, in the order of
+    /// [`Database::diff`].
+    ///
+    /// Replacements of the deleted assertions are searched among the assertions
+    /// of the new version with the same typecode, by parsing the deleted
+    /// statements with the grammar of the new version.
+    ///
+    /// Requires: [`Database::scope_pass`] on both databases,
+    /// and [`Database::stmt_parse_pass`] on the new database
+    #[must_use]
+    pub fn label_changes(&self, new: &Self) -> Vec<LabelChange> {
+        (self.diff(new).into_iter())
+            .filter_map(|change| match change {
+                StatementChange::Renamed { from, to, .. } => {
+                    Some(LabelChange::Renamed { from, to })
+                }
+                StatementChange::Removed(label) => {
+                    let stmt = self.statement(label.as_bytes())?;
+                    let replacement = self.replacement(new, stmt);
+                    Some(LabelChange::Deleted { label, replacement })
+                }
+                StatementChange::Added(_) | StatementChange::Modified { .. } => None,
+            })
+            .collect()
+    }
+}
+
+/// Writes the given label changes as entries of set.mm's change log,
+/// all with the given date, like:
+///
+/// ```text
+///  1-Jan-2024 a1i       a1ii
+///  1-Jan-2024 foo       [deleted]   use bar
+/// ```
+pub fn write_changelog(
+    changes: &[LabelChange],
+    date: Date,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    let date = date.to_string();
+    for change in changes {
+        match change {
+            LabelChange::Renamed { from, to } => writeln!(out, "{date:>11} {from:<9} {to}")?,
+            LabelChange::Deleted {
+                label,
+                replacement: Some(replacement),
+            } => writeln!(
+                out,
+                "{date:>11} {label:<9} {:<11} use {replacement}",
+                "[deleted]"
+            )?,
+            LabelChange::Deleted {
+                label,
+                replacement: None,
+            } => writeln!(out, "{date:>11} {label:<9} [deleted]")?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::changelog::{write_changelog, LabelChange};
+use crate::comment_parser::Date;
+use crate::grammar_tests::mkdb;
+
+const PREAMBLE: &str = "$c ( ) -> wff |- $.
+  $v ph ps ch $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wch $f wff ch $.
+  wi $a wff ( ph -> ps ) $.
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ${
+    $d ph ps $.
+    ax-d $a |- ( ( ph -> ps ) -> ph ) $.
+  $}
+";
+
+const OLD_DB: &str = "
+  ${
+    a1i.1 $e |- ph $.
+    a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+  $}
+  foo $p |- ( ps -> ( ph -> ps ) ) $= wps wph ax-1 $.
+  baz $p |- ( ph -> ( ph -> ph ) ) $= wph wph ax-1 $.
+  qux $p |- ( ph -> ph ) $= ? $.
+  dvx $p |- ( ( ch -> ch ) -> ch ) $= ? $.
+  ${
+    $d ch ph $.
+    dvy $p |- ( ( ch -> ph ) -> ch ) $= wch wph ax-d $.
+  $}
+";
+
+const NEW_DB: &str = "
+  ${
+    a1ii.1 $e |- ph $.
+    a1ii $p |- ( ps -> ph ) $= wph wps wph wi a1ii.1 wph wps ax-1 ax-mp $.
+  $}
+  bar $p |- ( ps -> ( ph -> ps ) ) $= ? $.
+";
+
+#[test]
+fn test_label_changes() {
+    let mut old_db = mkdb([PREAMBLE, OLD_DB].concat().as_bytes());
+    old_db.scope_pass();
+    let mut new_db = mkdb([PREAMBLE, NEW_DB].concat().as_bytes());
+    new_db.scope_pass();
+    new_db.stmt_parse_pass();
+    let changes = old_db.label_changes(&new_db);
+    let deleted = |label: &str, replacement: Option<&str>| LabelChange::Deleted {
+        label: label.to_owned(),
+        replacement: replacement.map(str::to_owned),
+    };
+    assert_eq!(
+        changes,
+        vec![
+            LabelChange::Renamed {
+                from: "a1i".to_owned(),
+                to: "a1ii".to_owned(),
+            },
+            deleted("foo", Some("bar")),
+            deleted("baz", Some("ax-1")),
+            deleted("qux", None),
+            deleted("dvx", None),
+            deleted("dvy", Some("ax-d")),
+        ]
+    );
+    let date = Date {
+        year: 2024,
+        month: 1,
+        day: 1,
+    };
+    let mut out = vec![];
+    write_changelog(&changes[..4], date, &mut out).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out).unwrap(),
+        " 1-Jan-2024 a1i       a1ii
+ 1-Jan-2024 foo       [deleted]   use bar
+ 1-Jan-2024 baz       [deleted]   use ax-1
+ 1-Jan-2024 qux       [deleted]
+"
+    );
+}
@@ -495,7 +495,8 @@ pub enum Parenthetical {
     ContributedBy {
         /// The span of the author in the parenthetical
         author: Span,
-        /// The date, in the form `DD-MMM-YYYY`.
+        /// The date, normally in the form `DD-MMM-YYYY`; some other common forms,
+        /// like `DD Month YYYY` and `YYYY-MM-DD`, are also matched.
         /// To parse this further into a date, use the [`Date`] type's [`TryFrom`] impl.
         date: Span,
     },
@@ -503,7 +504,8 @@ pub enum Parenthetical {
     RevisedBy {
         /// The span of the author in the parenthetical
         author: Span,
-        /// The date, in the form `DD-MMM-YYYY`.
+        /// The date, normally in the form `DD-MMM-YYYY`; some other common forms,
+        /// like `DD Month YYYY` and `YYYY-MM-DD`, are also matched.
         /// To parse this further into a date, use the [`Date`] type's [`TryFrom`] impl.
         date: Span,
     },
@@ -511,7 +513,8 @@ pub enum Parenthetical {
     ProofShortenedBy {
         /// The span of the author in the parenthetical
         author: Span,
-        /// The date, in the form `DD-MMM-YYYY`.
+        /// The date, normally in the form `DD-MMM-YYYY`; some other common forms,
+        /// like `DD Month YYYY` and `YYYY-MM-DD`, are also matched.
         /// To parse this further into a date, use the [`Date`] type's [`TryFrom`] impl.
         date: Span,
     },
@@ -536,7 +539,8 @@ impl<'a> ParentheticalIter<'a> {
         let parentheticals = PARENTHETICALS.get_or_init(|| {
             Regex::new(concat!(
                 r"\((Contributed|Revised|Proof[ \r\n]+shortened)",
-                r"[ \r\n]+by[ \r\n]+([^,)]+),[ \r\n]+([0-9]{1,2}-[A-Z][a-z]{2}-[0-9]{4})\.\)|",
+                r"[ \r\n]+by[ \r\n]+([^,)]+),[ \r\n]+",
+                r"([0-9]{1,2}[- ][A-Za-z]{3,9}[- ][0-9]{4}|[0-9]{4}-[0-9]{1,2}-[0-9]{1,2})\.\)|",
                 r"\((Proof[ \r\n]+modification|New[ \r\n]+usage)[ \r\n]+is[ \r\n]+discouraged\.\)",
             ))
             .unwrap()
@@ -594,6 +598,24 @@ pub struct Date {
     pub day: u8,
 }
 
+impl Date {
+    /// Returns whether this is an actual date of the Gregorian calendar,
+    /// i.e. whether the day exists in its month, taking leap years into account.
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        let leap = self.year.is_multiple_of(4)
+            && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400));
+        let days = match self.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return false,
+        };
+        self.day >= 1 && self.day <= days
+    }
+}
+
 impl Display for Date {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         const MONTHS: [&str; 12] = [
@@ -618,6 +640,12 @@ impl TryFrom<&[u8]> for Date {
             11 => (&value[..2], &value[3..6], &value[7..]),
             _ => return Err(()),
         };
+        if value[value.len() - 9] != b'-'
+            || value[value.len() - 5] != b'-'
+            || !day.iter().chain(year).all(u8::is_ascii_digit)
+        {
+            return Err(());
+        }
         Ok(Date {
             year: std::str::from_utf8(year)
                 .map_err(|_| ())?
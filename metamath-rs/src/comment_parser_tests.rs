@@ -1,7 +1,7 @@
 use crate::{
     comment_parser::{
         CommentItem::{self, *},
-        CommentParser, Discouragements,
+        CommentParser, Date, Discouragements,
         Parenthetical::{self, *},
         ParentheticalIter,
     },
@@ -378,3 +378,33 @@ fn test_parentheticals() {
         ],
     );
 }
+
+#[test]
+fn test_date() {
+    let date = Date::try_from(&b"1-Jan-2024"[..]).unwrap();
+    assert_eq!((date.day, date.month, date.year), (1, 1, 2024));
+    let date = Date::try_from(&b"31-Dec-1999"[..]).unwrap();
+    assert_eq!((date.day, date.month, date.year), (31, 12, 1999));
+    for text in [
+        "1/Jan/2024",
+        "1 Jan 2024",
+        "+1-Jan-2024",
+        "1-Jan-+202",
+        "1-jan-2024",
+    ] {
+        assert!(Date::try_from(text.as_bytes()).is_err(), "{text}");
+    }
+    for (text, valid) in [
+        ("29-Feb-2024", true),
+        ("29-Feb-2000", true),
+        ("29-Feb-1900", false),
+        ("29-Feb-2023", false),
+        ("31-Feb-2024", false),
+        ("31-Apr-2024", false),
+        ("30-Apr-2024", true),
+        ("0-Jan-2024", false),
+    ] {
+        let date = Date::try_from(text.as_bytes()).unwrap();
+        assert_eq!(date.is_valid(), valid, "{text}");
+    }
+}
@@ -99,8 +99,9 @@
 
 use crate::as_str;
 use crate::axiom_use::UsageResult;
+use crate::cache::VerifyCache;
 use crate::diag;
-use crate::diag::Diagnostic;
+use crate::diag::{Diagnostic, LocatedDiagnostic};
 use crate::export;
 use crate::formula::Formula;
 use crate::formula::Label;
@@ -109,6 +110,7 @@ use crate::grammar;
 use crate::grammar::Grammar;
 use crate::grammar::StmtParse;
 use crate::line_cache::LineCache;
+use crate::lint::{LintPolicy, LintResult};
 use crate::nameck::Nameset;
 use crate::outline::Outline;
 use crate::outline::OutlineNodeRef;
@@ -119,16 +121,22 @@ use crate::segment::Comparer;
 use crate::segment_set::SegmentSet;
 use crate::statement::StatementAddress;
 use crate::typesetting::TypesettingData;
+use crate::unicode::UnicodeRenderer;
+use crate::util::HashSet;
 use crate::verify;
 use crate::verify::VerifyResult;
+use crate::Span;
 use crate::StatementRef;
+use crate::StatementType;
 use annotate_snippets::Message;
+use itertools::Itertools;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::fmt;
 use std::fmt::Debug;
 use std::fs::File;
 use std::ops::Bound;
+use std::ops::Range;
 use std::ops::RangeBounds;
 use std::panic;
 use std::sync::Arc;
@@ -165,6 +173,15 @@ pub struct DbOptions {
     pub incremental: bool,
     /// Number of jobs to run in parallel at any given time.
     pub jobs: usize,
+    /// If true, the verifier does not stop at the first failing step of a proof:
+    /// it pushes the expected result of the step and continues, so that all the
+    /// independent errors of a proof are reported, each with its step number.
+    /// Unknown `?` steps are also skipped this way.
+    pub verify_recovery: bool,
+    /// If true, the timing and recalculation traces enabled by `timing` and
+    /// `trace_recalc` are printed to stderr rather than stdout, so that they do
+    /// not interfere with machine-readable output.
+    pub log_to_stderr: bool,
 }
 
 impl Default for DbOptions {
@@ -175,6 +192,8 @@ impl Default for DbOptions {
             trace_recalc: false,
             incremental: false,
             jobs: 1,
+            verify_recovery: false,
+            log_to_stderr: false,
         }
     }
 }
@@ -401,7 +420,10 @@ pub struct Database {
     scopes: Option<Arc<ScopeResult>>,
     prev_verify: Option<Arc<VerifyResult>>,
     verify: Option<Arc<VerifyResult>>,
+    pub(crate) verify_cache: Option<VerifyCache>,
     usage: Option<Arc<UsageResult>>,
+    lint_policy: LintPolicy,
+    lint: Option<Arc<LintResult>>,
     typesetting: Option<Arc<TypesettingData>>,
     outline: Option<Arc<Outline>>,
     grammar: Option<Arc<Grammar>>,
@@ -414,12 +436,24 @@ impl Default for Database {
     }
 }
 
+/// Prints a timing or tracing message, to stdout or stderr according to [`DbOptions::log_to_stderr`].
+pub(crate) fn log(opts: &DbOptions, message: fmt::Arguments<'_>) {
+    if opts.log_to_stderr {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
 pub(crate) fn time<R, F: FnOnce() -> R>(opts: &DbOptions, name: &str, f: F) -> R {
     if opts.timing {
         let now = Instant::now();
         let ret = f();
         // no as_msecs :(
-        println!("{} {}ms", name, (now.elapsed() * 1000).as_secs());
+        log(
+            opts,
+            format_args!("{} {}ms", name, (now.elapsed() * 1000).as_secs()),
+        );
         ret
     } else {
         f()
@@ -457,7 +491,10 @@ impl Database {
             nameset: None,
             scopes: None,
             verify: None,
+            verify_cache: None,
             usage: None,
+            lint_policy: LintPolicy::default(),
+            lint: None,
             typesetting: None,
             outline: None,
             grammar: None,
@@ -504,6 +541,7 @@ impl Database {
             self.scopes = None;
             self.verify = None;
             self.usage = None;
+            self.lint = None;
             self.typesetting = None;
             self.outline = None;
             self.grammar = None;
@@ -597,7 +635,8 @@ impl Database {
                 let parse = self.parse_result();
                 let scope = self.scope_result();
                 let name = self.name_result();
-                verify::verify(Arc::make_mut(&mut ver), parse, name, scope);
+                let cache = self.verify_cache.as_ref();
+                verify::verify(Arc::make_mut(&mut ver), parse, name, scope, cache);
                 self.prev_verify = Some(ver.clone());
                 self.verify = Some(ver);
             });
@@ -656,6 +695,35 @@ impl Database {
         self.usage.as_ref()
     }
 
+    /// Sets the lint levels for the whole database, see [`crate::lint`].
+    ///
+    /// The lint levels are applied by [`Database::render_diags`] and
+    /// [`Database::locate_diags`], once [`Database::lint_pass`] has been called.
+    pub fn set_lint_policy(&mut self, policy: LintPolicy) {
+        self.lint_policy = policy;
+        self.lint = None;
+    }
+
+    /// Collects and returns the lint levels set for the database,
+    /// by the lint policy and by the lint commands in the database.
+    pub fn lint_pass(&mut self) -> &Arc<LintResult> {
+        if self.lint.is_none() {
+            time(&self.options.clone(), "lint", || {
+                let lint = crate::lint::lint(self.parse_result(), &self.lint_policy);
+                self.lint = Some(Arc::new(lint));
+            });
+        }
+        self.lint.as_ref().unwrap()
+    }
+
+    /// Returns the lint levels set for the database.
+    /// Returns `None` if [`Database::lint_pass`] was not previously called.
+    #[inline]
+    #[must_use]
+    pub const fn try_lint_result(&self) -> Option<&Arc<LintResult>> {
+        self.lint.as_ref()
+    }
+
     /// Computes and returns the typesetting data.
     pub fn typesetting_pass(&mut self) -> &Arc<TypesettingData> {
         if self.typesetting.is_none() {
@@ -817,6 +885,40 @@ impl Database {
         &self.parse_result().source_info(addr.segment_id).name
     }
 
+    /// Iterates over the names of the source files of the database, as loaded.
+    pub fn source_names(&self) -> impl Iterator<Item = &str> + '_ {
+        let sset = self.parse_result();
+        let names = sset
+            .segments(..)
+            .map(|seg| sset.source_info(seg.id).name.as_str());
+        names.unique()
+    }
+
+    /// Get the full text of a source file of the database, by its name as loaded.
+    #[must_use]
+    pub fn source_text(&self, name: &str) -> Option<&[u8]> {
+        let sset = self.parse_result();
+        sset.segments(..)
+            .map(|seg| sset.source_info(seg.id))
+            .find(|source| source.name == name)
+            .map(|source| &source.text[..])
+    }
+
+    /// Get the full text of the source file for a given statement.
+    #[inline]
+    #[must_use]
+    pub fn statement_source_text(&self, addr: StatementAddress) -> &[u8] {
+        &self.parse_result().source_info(addr.segment_id).text
+    }
+
+    /// Get the byte range within its source file of a span of the given statement,
+    /// like the ones returned by [`StatementRef::span`] or [`StatementRef::label_span`].
+    #[must_use]
+    pub fn statement_source_range(&self, addr: StatementAddress, span: Span) -> Range<usize> {
+        let start = self.parse_result().source_info(addr.segment_id).span.start as usize;
+        start + span.start as usize..start + span.end as usize
+    }
+
     /// Iterates over all the statements
     #[must_use]
     pub fn statements(&self) -> impl DoubleEndedIterator<Item = StatementRef<'_>> + Clone + '_ {
@@ -899,6 +1001,26 @@ impl Database {
         self.name_result().get_atom(sref.math_at(0).slice)
     }
 
+    /// Returns the typecodes of the grammar which are not the typecode of any
+    /// syntax axiom, like `setvar` for set.mm: the expressions of these typecodes
+    /// are always variables.
+    /// They are computed once per grammar.
+    /// Requires: [`Database::grammar_pass`]
+    pub(crate) fn variable_typecodes(&self) -> &HashSet<TypeCode> {
+        let grammar = self.grammar_result();
+        grammar.variable_typecodes.get_or_init(|| {
+            let provable = grammar.provable_typecode();
+            let syntax_typecodes = (self.statements())
+                .filter(|stmt| stmt.statement_type() == StatementType::Axiom)
+                .map(|stmt| self.name_result().get_atom(stmt.math_at(0).slice))
+                .collect::<HashSet<_>>();
+            (grammar.typecodes().iter())
+                .filter(|&typecode| *typecode != provable && !syntax_typecodes.contains(typecode))
+                .copied()
+                .collect()
+        })
+    }
+
     /// Export an mmp file for a given statement.
     /// Requires: [`Database::name_pass`], [`Database::scope_pass`]
     pub fn export(&self, stmt: &str) {
@@ -948,6 +1070,24 @@ impl Database {
         })
     }
 
+    /// Export the proof of the given theorem in DOT format, to the file `LABEL.dot`.
+    /// See [`ProofTreeArray::export_dot`] for the meaning of `show_exprs` and `show_syntax`.
+    /// Requires: [`Database::name_pass`], [`Database::scope_pass`]
+    #[cfg(feature = "dot")]
+    pub fn export_proof_dot(
+        &self,
+        stmt: StatementRef<'_>,
+        show_exprs: bool,
+        show_syntax: bool,
+    ) -> Result<(), export::ExportError> {
+        time(&self.options, "export_proof_dot", || {
+            let arr = ProofTreeArray::from_stmt(self, stmt, show_exprs)
+                .map_err(export::ExportError::Verify)?;
+            let mut file = File::create(format!("{}.dot", as_str(stmt.label())))?;
+            arr.export_dot(self, show_exprs, show_syntax, &mut file)
+        })
+    }
+
     /// Dump the grammar of this database.
     /// Requires: [`Database::name_pass`], [`Database::grammar_pass`]
     pub fn dump_grammar(&self) {
@@ -960,7 +1100,15 @@ impl Database {
     /// Requires: [`Database::name_pass`], [`Database::stmt_parse_pass`]
     pub fn dump_formula(&self) {
         time(&self.options, "dump_formulas", || {
-            self.stmt_parse_result().dump(self);
+            self.stmt_parse_result().dump(self, None);
+        })
+    }
+
+    /// Dump the formulas of this database, each followed by its rendering as Unicode text.
+    /// Requires: [`Database::name_pass`], [`Database::stmt_parse_pass`]
+    pub fn dump_formula_unicode(&self, renderer: &UnicodeRenderer) {
+        time(&self.options, "dump_formulas", || {
+            self.stmt_parse_result().dump(self, Some(renderer));
         })
     }
 
@@ -1017,6 +1165,9 @@ impl Database {
         if let Some(pass) = self.try_usage_result() {
             diags.extend_from_slice(&pass.diagnostics())
         }
+        if let Some(pass) = self.try_lint_result() {
+            diags.extend(pass.diagnostics())
+        }
         if let Some(pass) = self.try_grammar_result() {
             diags.extend(pass.diagnostics())
         }
@@ -1029,6 +1180,41 @@ impl Database {
         diags
     }
 
+    /// Convert a list of diagnostics collected by `diag_notations` to a list of
+    /// diagnostics located by file, line and column, for use by editors and other tools.
+    #[must_use]
+    pub fn locate_diags(
+        &self,
+        diags: Vec<(StatementAddress, Diagnostic)>,
+    ) -> Vec<LocatedDiagnostic> {
+        let mut lc = LineCache::default();
+        time(&self.options.clone(), "diag", move || {
+            diag::to_located(
+                self.parse_result(),
+                &mut lc,
+                self.try_lint_result().map(Arc::as_ref),
+                diags,
+            )
+        })
+    }
+
+    /// Locates each of the given diagnostics like [`Database::locate_diags`], but keeping
+    /// their order: the result has one entry per diagnostic, which is `None` for the
+    /// diagnostics allowed by the lint policy.
+    #[must_use]
+    pub fn locate_each_diag(
+        &self,
+        diags: &[(StatementAddress, Diagnostic)],
+    ) -> Vec<Option<LocatedDiagnostic>> {
+        let mut lc = LineCache::default();
+        time(&self.options.clone(), "diag", move || {
+            let lint = self.try_lint_result().map(Arc::as_ref);
+            (diags.iter())
+                .map(|(saddr, diag)| diag::locate(self.parse_result(), &mut lc, lint, *saddr, diag))
+                .collect()
+        })
+    }
+
     /// Convert a list of diagnostics collected by `diag_notations` to a list of snippets.
     pub fn render_diags<T>(
         &self,
@@ -1037,7 +1223,13 @@ impl Database {
     ) -> Vec<T> {
         let mut lc = LineCache::default();
         time(&self.options.clone(), "diag", move || {
-            diag::to_annotations(self.parse_result(), &mut lc, diags, f)
+            diag::to_annotations(
+                self.parse_result(),
+                &mut lc,
+                self.try_lint_result().map(Arc::as_ref),
+                diags,
+                f,
+            )
         })
     }
 }
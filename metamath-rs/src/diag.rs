@@ -5,7 +5,9 @@
 //! can be used for various human-readable outputs.
 
 use crate::as_str;
+use crate::fix::Fix;
 use crate::line_cache::LineCache;
+use crate::lint::{LintLevel, LintResult};
 use crate::parser::HeadingLevel;
 use crate::segment::Comparer;
 use crate::segment_set::SegmentSet;
@@ -17,6 +19,7 @@ use crate::statement::Token;
 use crate::statement::TokenAddress;
 use crate::statement::TokenIndex;
 use crate::statement::NO_STATEMENT;
+use crate::util::write_json_string;
 use crate::Span;
 use crate::StatementRef;
 use crate::StatementType;
@@ -27,6 +30,7 @@ use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::Display;
 use std::io;
+use std::ops::Range;
 use typed_arena::Arena;
 
 /// The three kinds of markup supported by `$t` typesetting comments.
@@ -97,6 +101,7 @@ pub enum Diagnostic {
     BadExplicitLabel(Token),
     BadFloating,
     BadLabel(Span),
+    BadLintCommand(Span),
     BibEscape(u32, Span),
     ChainBackref(Span),
     CommandExpectedAs(Span),
@@ -159,6 +164,9 @@ pub enum Diagnostic {
     ProofMalformedVarint,
     ProofModOnAxiom(Span),
     ProofNoSteps,
+    /// An error at the given step of a proof, whose token is at the given span,
+    /// reported in error recovery mode.
+    ProofStep(usize, Span, Box<Diagnostic>),
     ProofUnderflow,
     ProofUnterminatedRoster,
     ProofWrongExprEnd,
@@ -197,6 +205,7 @@ pub enum Diagnostic {
     UninterpretedEscape(u32),
     UninterpretedHtml(Span),
     UnknownLabel(Span),
+    UnknownLintCode(Span),
     UnknownKeyword(Span),
     UnknownTypesettingCommand(Span),
     UnmatchedCloseGroup,
@@ -214,23 +223,327 @@ impl From<io::Error> for Diagnostic {
 }
 
 /// Converts a collection of raw diagnostics to a notation list before output.
+/// Diagnostics allowed by the lint policy are skipped.
 #[must_use]
 pub(crate) fn to_annotations<T>(
     sset: &SegmentSet,
     lc: &mut LineCache,
+    lint: Option<&LintResult>,
     mut diags: Vec<(StatementAddress, Diagnostic)>,
     f: impl for<'a> FnOnce(Message<'a>) -> T + Copy,
 ) -> Vec<T> {
     diags.sort_by(|x, y| sset.order.cmp(&x.0, &y.0));
     diags
         .iter()
-        .map(move |(saddr, diag)| {
+        .filter_map(move |(saddr, diag)| {
+            let level = lint.and_then(|lint| lint.level(sset, *saddr, diag.without_step().code()));
+            if level == Some(LintLevel::Allow) {
+                return None;
+            }
             let stmt = sset.statement_or_dummy(*saddr);
-            diag.to_snippet(sset, stmt, lc, f)
+            Some(diag.to_snippet(sset, stmt, level.and_then(LintLevel::level), lc, f))
         })
         .collect::<Vec<_>>()
 }
 
+/// A diagnostic annotation, resolved to a location in a source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocatedAnnotation {
+    /// Severity level of the annotation.
+    pub level: Level,
+    /// The annotation message.
+    pub message: String,
+    /// Name of the source file, as loaded.
+    pub file: String,
+    /// Byte range of the annotated text within the source file.
+    pub range: Range<usize>,
+    /// Line and column (both starting from 1) of the start of the annotated text.
+    pub start: (u32, u32),
+    /// Line and column (both starting from 1) of the end of the annotated text.
+    pub end: (u32, u32),
+}
+
+impl LocatedAnnotation {
+    fn new(
+        level: Level,
+        message: Cow<'_, str>,
+        span: Span,
+        source: &SourceInfo,
+        lc: &mut LineCache,
+    ) -> Self {
+        let start = (span.start + source.span.start) as usize;
+        let end = (span.end + source.span.start) as usize;
+        Self {
+            level,
+            message: message.into_owned(),
+            file: source.name.clone(),
+            range: start..end,
+            start: lc.from_offset(&source.text, start),
+            end: lc.from_offset(&source.text, end),
+        }
+    }
+}
+
+/// A diagnostic, with its annotations resolved to locations in source files,
+/// for consumption by tools rather than humans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocatedDiagnostic {
+    /// A stable identifier for the kind of diagnostic, see [`Diagnostic::code`].
+    pub code: &'static str,
+    /// Severity level of the diagnostic, which is the level of its first annotation.
+    pub level: Level,
+    /// A global message for the diagnostic.
+    pub title: String,
+    /// The annotations, the first one being the primary location of the diagnostic.
+    pub annotations: Vec<LocatedAnnotation>,
+    /// Additional notes about the diagnostic.
+    pub notes: Vec<String>,
+    /// Machine-applicable fixes for the diagnostic, see [`crate::fix`].
+    pub fixes: Vec<Fix>,
+}
+
+/// Converts a raw diagnostic to a located diagnostic,
+/// or returns `None` if it is allowed by the lint policy.
+pub(crate) fn locate(
+    sset: &SegmentSet,
+    lc: &mut LineCache,
+    lint: Option<&LintResult>,
+    saddr: StatementAddress,
+    diag: &Diagnostic,
+) -> Option<LocatedDiagnostic> {
+    let level = lint.and_then(|lint| lint.level(sset, saddr, diag.without_step().code()));
+    if level == Some(LintLevel::Allow) {
+        return None;
+    }
+    let stmt = sset.statement_or_dummy(saddr);
+    let ((title, mut infos), notes) = diag.build_info(sset, stmt);
+    if let (Some(level), Some(info)) = (level.and_then(LintLevel::level), infos.first_mut()) {
+        info.0 = level;
+    }
+    let annotations = (infos.into_iter())
+        .map(|(level, message, stmt, span)| {
+            let source = sset.source_info(stmt.segment().id).borrow();
+            LocatedAnnotation::new(level, message, span, source, lc)
+        })
+        .collect::<Vec<_>>();
+    Some(LocatedDiagnostic {
+        code: diag.without_step().code(),
+        level: annotations.first().map_or(Level::Error, |ann| ann.level),
+        title: title.into_owned(),
+        annotations,
+        notes: notes.iter().map(|&note| note.to_owned()).collect(),
+        fixes: diag.fixes(sset, saddr, None),
+    })
+}
+
+/// Converts a collection of raw diagnostics to located diagnostics.
+/// Diagnostics allowed by the lint policy are skipped.
+#[must_use]
+pub(crate) fn to_located(
+    sset: &SegmentSet,
+    lc: &mut LineCache,
+    lint: Option<&LintResult>,
+    mut diags: Vec<(StatementAddress, Diagnostic)>,
+) -> Vec<LocatedDiagnostic> {
+    diags.sort_by(|x, y| sset.order.cmp(&x.0, &y.0));
+    (diags.iter())
+        .filter_map(|(saddr, diag)| locate(sset, lc, lint, *saddr, diag))
+        .collect()
+}
+
+/// Returns the lowercase name of a severity level, as used in JSON output.
+const fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Info => "info",
+        Level::Note => "note",
+        Level::Help => "help",
+    }
+}
+
+/// Writes a source location as JSON object fields,
+/// with lines and columns starting from 1.
+fn write_json_location(out: &mut impl io::Write, ann: &LocatedAnnotation) -> io::Result<()> {
+    write!(out, "\"file\": ")?;
+    write_json_string(out, ann.file.as_bytes())?;
+    let ((start_line, start_col), (end_line, end_col)) = (ann.start, ann.end);
+    write!(
+        out,
+        ", \"start\": {{\"line\": {start_line}, \"column\": {start_col}}}, \
+        \"end\": {{\"line\": {end_line}, \"column\": {end_col}}}"
+    )
+}
+
+/// Writes a list of located diagnostics as a JSON array.
+///
+/// Each diagnostic is an object with its `code`, `severity`, `message`,
+/// and the location of its primary annotation if any, as well as the list of all its
+/// `annotations`, each with a `severity`, `message`, and location, its `notes`,
+/// and its `fixes`, each with a `file`, the byte `offset` and `length` of the replaced
+/// text, and its `replacement`.
+/// Locations are given by `file`, and `start` and `end` positions, each with a
+/// `line` and `column`.
+pub fn write_json(diags: &[LocatedDiagnostic], out: &mut impl io::Write) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, diag) in diags.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ",\n" };
+        write!(
+            out,
+            "{sep}  {{\"code\": \"{}\", \"severity\": \"{}\", \"message\": ",
+            diag.code,
+            level_name(diag.level)
+        )?;
+        write_json_string(out, diag.title.as_bytes())?;
+        if let Some(ann) = diag.annotations.first() {
+            write!(out, ", ")?;
+            write_json_location(out, ann)?;
+        }
+        write!(out, ", \"annotations\": [")?;
+        for (j, ann) in diag.annotations.iter().enumerate() {
+            let sep = if j == 0 { "" } else { ", " };
+            write!(
+                out,
+                "{sep}{{\"severity\": \"{}\", \"message\": ",
+                level_name(ann.level)
+            )?;
+            write_json_string(out, ann.message.as_bytes())?;
+            write!(out, ", ")?;
+            write_json_location(out, ann)?;
+            write!(out, "}}")?;
+        }
+        write!(out, "], \"notes\": [")?;
+        for (j, note) in diag.notes.iter().enumerate() {
+            let sep = if j == 0 { "" } else { ", " };
+            write!(out, "{sep}")?;
+            write_json_string(out, note.as_bytes())?;
+        }
+        write!(out, "], \"fixes\": [")?;
+        for (j, fix) in diag.fixes.iter().enumerate() {
+            let sep = if j == 0 { "" } else { ", " };
+            write!(out, "{sep}{{\"file\": ")?;
+            write_json_string(out, fix.file.as_bytes())?;
+            write!(
+                out,
+                ", \"offset\": {}, \"length\": {}, \"replacement\": ",
+                fix.range.start,
+                fix.range.len()
+            )?;
+            write_json_string(out, fix.replacement.as_bytes())?;
+            write!(out, "}}")?;
+        }
+        write!(out, "]}}")?;
+    }
+    writeln!(out, "\n]")
+}
+
+/// Writes a SARIF physical location object for an annotation.
+fn write_sarif_location(out: &mut impl io::Write, ann: &LocatedAnnotation) -> io::Result<()> {
+    write!(out, "{{\"artifactLocation\": {{\"uri\": ")?;
+    write_json_string(out, ann.file.as_bytes())?;
+    let ((start_line, start_col), (end_line, end_col)) = (ann.start, ann.end);
+    write!(
+        out,
+        "}}, \"region\": {{\"startLine\": {start_line}, \"startColumn\": {start_col}, \
+        \"endLine\": {end_line}, \"endColumn\": {end_col}}}}}"
+    )
+}
+
+/// Writes a list of located diagnostics as a
+/// [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log,
+/// as produced by the tool with the given name and version.
+///
+/// Each diagnostic is a result whose rule identifier is the diagnostic's code.
+/// The primary annotation gives the location of the result,
+/// and the other annotations are given as related locations.
+/// Machine-applicable fixes are given as byte-based replacements.
+pub fn write_sarif(
+    diags: &[LocatedDiagnostic],
+    tool: &str,
+    version: &str,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    writeln!(out, "{{")?;
+    writeln!(
+        out,
+        "  \"$schema\": \"https://json.schemastore.org/sarif-2.1.0.json\",\n  \"version\": \"2.1.0\","
+    )?;
+    write!(
+        out,
+        "  \"runs\": [{{\n    \"tool\": {{\"driver\": {{\"name\": "
+    )?;
+    write_json_string(out, tool.as_bytes())?;
+    write!(out, ", \"version\": ")?;
+    write_json_string(out, version.as_bytes())?;
+    write!(out, ", \"rules\": [")?;
+    let codes = diags.iter().map(|diag| diag.code).sorted().dedup();
+    for (i, code) in codes.enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        write!(out, "{sep}{{\"id\": \"{code}\"}}")?;
+    }
+    writeln!(out, "]}}}},\n    \"results\": [")?;
+    for (i, diag) in diags.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ",\n" };
+        let level = match diag.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info | Level::Note | Level::Help => "note",
+        };
+        let mut message = diag.title.clone();
+        if let Some(ann) = diag.annotations.first() {
+            message = format!("{message}: {}", ann.message);
+        }
+        for note in &diag.notes {
+            message = format!("{message}\n{note}");
+        }
+        write!(
+            out,
+            "{sep}      {{\"ruleId\": \"{}\", \"level\": \"{level}\", \"message\": {{\"text\": ",
+            diag.code
+        )?;
+        write_json_string(out, message.as_bytes())?;
+        write!(out, "}}, \"locations\": [")?;
+        if let Some(ann) = diag.annotations.first() {
+            write!(out, "{{\"physicalLocation\": ")?;
+            write_sarif_location(out, ann)?;
+            write!(out, "}}")?;
+        }
+        write!(out, "], \"relatedLocations\": [")?;
+        for (j, ann) in diag.annotations.iter().enumerate().skip(1) {
+            let sep = if j == 1 { "" } else { ", " };
+            write!(out, "{sep}{{\"id\": {j}, \"message\": {{\"text\": ")?;
+            write_json_string(out, ann.message.as_bytes())?;
+            write!(out, "}}, \"physicalLocation\": ")?;
+            write_sarif_location(out, ann)?;
+            write!(out, "}}")?;
+        }
+        write!(out, "]")?;
+        if !diag.fixes.is_empty() {
+            write!(out, ", \"fixes\": [")?;
+            for (j, fix) in diag.fixes.iter().enumerate() {
+                let sep = if j == 0 { "" } else { ", " };
+                write!(
+                    out,
+                    "{sep}{{\"artifactChanges\": [{{\"artifactLocation\": {{\"uri\": "
+                )?;
+                write_json_string(out, fix.file.as_bytes())?;
+                write!(
+                    out,
+                    "}}, \"replacements\": [{{\"deletedRegion\": \
+                    {{\"byteOffset\": {}, \"byteLength\": {}}}, \"insertedContent\": {{\"text\": ",
+                    fix.range.start,
+                    fix.range.len()
+                )?;
+                write_json_string(out, fix.replacement.as_bytes())?;
+                write!(out, "}}}}]}}]}}")?;
+            }
+            write!(out, "]")?;
+        }
+        write!(out, "}}")?;
+    }
+    writeln!(out, "\n    ]\n  }}]\n}}")
+}
+
 /// Annotation info
 ///
 /// * `label` - A global error message for the diagnostic
@@ -253,6 +566,7 @@ type AnnInfo<'a> = (
 /// * `f` - A function for continuation passing style (CPS)
 #[must_use]
 fn make_snippet_from<'b, T>(
+    code: &'static str,
     label: &str,
     infos: impl Iterator<Item = (Level, Cow<'b, str>, Span, &'b SourceInfo)>,
     footer: &[&str],
@@ -283,6 +597,7 @@ fn make_snippet_from<'b, T>(
     f(level
         .unwrap()
         .title(label)
+        .id(code)
         .snippets(snippets)
         .footers(footer.iter().map(|msg| Level::Note.title(msg))))
 }
@@ -298,6 +613,7 @@ fn make_snippet_from<'b, T>(
 #[must_use]
 fn make_snippet<T>(
     sset: &SegmentSet,
+    code: &'static str,
     (label, infos): AnnInfo<'_>,
     footer: &[&str],
     lc: &mut LineCache,
@@ -307,21 +623,202 @@ fn make_snippet<T>(
         let source = sset.source_info(stmt.segment().id).borrow();
         (annotation_type, label, span, source)
     });
-    make_snippet_from(&label, iter, footer, lc, f)
+    make_snippet_from(code, &label, iter, footer, lc, f)
+}
+
+/// Defines [`Diagnostic::code`] and [`Diagnostic::CODES`] from the same list of
+/// variants, so that every code returned by the former is listed in the latter.
+macro_rules! diagnostic_codes {
+    ($($variant:ident $(($($fields:tt)*))?),* $(,)?) => {
+        impl Diagnostic {
+            /// Returns a stable identifier for the kind of this diagnostic,
+            /// for use in machine-readable outputs.
+            /// The errors at a given proof step are reported with the code of the
+            /// underlying error, see [`Diagnostic::without_step`].
+            #[must_use]
+            pub const fn code(&self) -> &'static str {
+                match self {
+                    $($variant $(($($fields)*))? => stringify!($variant),)*
+                }
+            }
+
+            /// The codes of all the kinds of diagnostics, see [`Diagnostic::code`].
+            pub const CODES: &'static [&'static str] = &[$(stringify!($variant)),*];
+        }
+    };
+}
+
+diagnostic_codes! {
+    BadCharacter(..),
+    BadCommand(..),
+    BadCommentEnd(..),
+    BadExplicitLabel(..),
+    BadFloating,
+    BadLabel(..),
+    BadLintCommand(..),
+    BibEscape(..),
+    ChainBackref(..),
+    CommandExpectedAs(..),
+    CommandExpectedString(..),
+    CommandIncomplete(..),
+    CommentMarkerNotStart(..),
+    ConstantNotTopLevel,
+    DisjointSingle,
+    DjNotVariable(..),
+    DjRepeatedVariable(..),
+    DateOrderError(..),
+    DateParseError(..),
+    DefaultAuthor(..),
+    DuplicateContributor(..),
+    DuplicateExplicitLabel(..),
+    DuplicateLabel(..),
+    DuplicateMarkupDef(..),
+    EmptyFilename,
+    EmptyMathString,
+    EmptyLabel(..),
+    EssentialAtTopLevel,
+    ExprNotConstantPrefix(..),
+    FilenameDollar,
+    FilenameSpaces,
+    FloatNotConstant(..),
+    FloatNotVariable(..),
+    FloatRedeclared(..),
+    FormulaVerificationFailed,
+    GrammarAmbiguous(..),
+    GrammarCantBuild(..),
+    GrammarProvableFloat,
+    HtmlParseError(..),
+    HeaderCommentParseError(..),
+    InvalidAxiomRestatement(..),
+    IoError(..),
+    LabelContainsUnderscore(..),
+    LineLengthExceeded(..),
+    LocalLabelAmbiguous(..),
+    LocalLabelDuplicate(..),
+    MarkupNeedsWhitespace(..),
+    MathboxCrossReference(..),
+    MathboxHeaderFormat(..),
+    MalformedAdditionalInfo(..),
+    MidStatementCommentMarker(..),
+    MissingContributor,
+    MissingLabel,
+    MissingMarkupDef(..),
+    MissingProof(..),
+    MissingSpaceAfterCommandToken(..),
+    MMReservedLabel(..),
+    NestedComment(..),
+    NotActiveSymbol(..),
+    NotAProvableStatement,
+    OldAltNotDiscouraged,
+    ParenOrderError(..),
+    ProofDvViolation,
+    ProofExcessEnd,
+    ProofIncomplete,
+    ProofInvalidSave,
+    ProofMalformedVarint,
+    ProofModOnAxiom(..),
+    ProofNoSteps,
+    ProofStep(..),
+    ProofUnderflow,
+    ProofUnterminatedRoster,
+    ProofWrongExprEnd,
+    ProofWrongTypeEnd,
+    RepeatedLabel(..),
+    ReservedAtToken(..),
+    ReservedQToken(..),
+    SpuriousLabel(..),
+    SpuriousProof(..),
+    StepEssenWrong,
+    StepEssenWrongType,
+    StepFloatWrongType,
+    StepMissing(..),
+    StepOutOfRange,
+    StepUsedAfterScope(..),
+    StepUsedBeforeDefinition(..),
+    StmtParseError(..),
+    SymbolDuplicatesLabel(..),
+    SymbolRedeclared(..),
+    TabUsed(..),
+    TrailingWhitespace(..),
+    UnclosedBeforeEof,
+    UnclosedBeforeInclude(..),
+    UnclosedCommandComment(..),
+    UnclosedCommandString(..),
+    UnclosedCommand(..),
+    UnclosedComment(..),
+    UnclosedHtml(..),
+    UnclosedInclude,
+    UnclosedMath,
+    UnclosedMathMarkup(..),
+    UnclosedProof,
+    UnconventionalAxiomLabel(..),
+    UndefinedBibTag(..),
+    UndefinedToken(..),
+    UninterpretedEscape(..),
+    UninterpretedHtml(..),
+    UnknownLabel(..),
+    UnknownLintCode(..),
+    UnknownKeyword(..),
+    UnknownTypesettingCommand(..),
+    UnmatchedCloseGroup,
+    UsageViolation(..),
+    VariableMissingFloat(..),
+    VariableRedeclaredAsConstant(..),
+    WindowsReservedLabel(..),
 }
 
 impl Diagnostic {
+    /// Returns the error found at a proof step in error recovery mode, see
+    /// [`Diagnostic::ProofStep`], or this diagnostic itself for other diagnostics.
+    #[must_use]
+    pub fn without_step(&self) -> &Self {
+        match self {
+            ProofStep(_, _, diag) => diag.without_step(),
+            _ => self,
+        }
+    }
+
+    /// Returns the level at which this diagnostic is reported by default,
+    /// which is the level of its primary annotation.
+    pub(crate) fn default_level(&self, sset: &SegmentSet, stmt: StatementRef<'_>) -> Level {
+        let ((_, infos), _) = self.build_info(sset, stmt);
+        infos.first().map_or(Level::Error, |info| info.0)
+    }
+
+    /// Builds the snippet for this diagnostic,
+    /// using the given level rather than the default one if provided.
     fn to_snippet<T>(
         &self,
         sset: &SegmentSet,
         stmt: StatementRef<'_>,
+        level: Option<Level>,
         lc: &mut LineCache,
         f: impl for<'a> FnOnce(Message<'a>) -> T,
     ) -> T {
+        let ((label, mut infos), notes) = self.build_info(sset, stmt);
+        if let (Some(level), Some(info)) = (level, infos.first_mut()) {
+            info.0 = level;
+        }
+        make_snippet(
+            sset,
+            self.without_step().code(),
+            (label, infos),
+            notes,
+            lc,
+            f,
+        )
+    }
+
+    /// Returns the annotations of this diagnostic, together with its footer notes.
+    fn build_info<'a>(
+        &'a self,
+        sset: &'a SegmentSet,
+        stmt: StatementRef<'a>,
+    ) -> (AnnInfo<'a>, &'static [&'static str]) {
         fn t(v: &Token) -> String {
             as_str(v).to_owned()
         }
-        let mut notes: &[&str] = &[];
+        let mut notes: &'static [&'static str] = &[];
         let infos = match self {
             BadCharacter(pos, byte) => ("Invalid character".into(), vec![(
                 Level::Error,
@@ -365,6 +862,12 @@ impl Diagnostic {
                 stmt,
                 *lbl,
             )]),
+            BadLintCommand(span) => ("Malformed lint command".into(), vec![(
+                Level::Warning,
+                "Expected `allow`, `warn` or `deny`, followed by diagnostic codes".into(),
+                stmt,
+                *span,
+            )]),
             &BibEscape(index, span) => {
                 notes = &["Avoid uses of escape characters in bibliography tags \
                     since they break regex-based implementations"];
@@ -850,6 +1353,16 @@ impl Diagnostic {
                 stmt,
                 stmt.span(),
             )]),
+            &ProofStep(step, span, ref diag) => {
+                let ((title, mut infos), step_notes) = diag.build_info(sset, stmt);
+                notes = step_notes;
+                if let Some(info) = infos.first_mut() {
+                    info.1 = format!("Step {step}: {}", info.1).into();
+                    info.2 = stmt;
+                    info.3 = span;
+                }
+                (title, infos)
+            }
             ProofUnderflow => ("Proof underflow".into(), vec![(
                 Level::Error,
                 "Too few statements on stack to satisfy step's mandatory hypotheses".into(),
@@ -1136,6 +1649,12 @@ impl Diagnostic {
                 stmt,
                 *span,
             )]),
+            UnknownLintCode(span) => ("Unknown diagnostic code".into(), vec![(
+                Level::Warning,
+                "This is not the code of any kind of diagnostic".into(),
+                stmt,
+                *span,
+            )]),
             UnmatchedCloseGroup => ("Unmatched close group".into(), vec![(
                 Level::Error,
                 "This $} does not match any open ${".into(),
@@ -1177,7 +1696,7 @@ impl Diagnostic {
             },
         };
 
-        make_snippet(sset, infos, notes, lc, f)
+        (infos, notes)
     }
 }
 
@@ -1284,6 +1803,9 @@ impl Error for StmtParseError {
     }
 }
 
+/// The title and the annotations of a bibliography error.
+type BibInfo = (Cow<'static, str>, Vec<(Level, Cow<'static, str>, Span)>);
+
 /// An error during bibliography parsing.
 #[derive(Debug, Clone, Copy)]
 #[allow(missing_docs)]
@@ -1292,6 +1814,23 @@ pub enum BibError {
 }
 
 impl BibError {
+    /// Returns a stable identifier for the kind of this error,
+    /// for use in machine-readable outputs.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            BibError::DuplicateBib(..) => "DuplicateBib",
+        }
+    }
+
+    /// Returns the level at which this error is reported.
+    #[must_use]
+    pub const fn level(&self) -> Level {
+        match self {
+            BibError::DuplicateBib(..) => Level::Warning,
+        }
+    }
+
     #[allow(clippy::wrong_self_convention)]
     fn to_snippet<T>(
         &self,
@@ -1299,22 +1838,27 @@ impl BibError {
         lc: &mut LineCache,
         f: impl for<'a> FnOnce(Message<'a>) -> T,
     ) -> T {
-        let (label, infos): (Cow<'_, str>, Vec<_>) = match self {
+        let (label, infos) = self.build_info();
+        let iter = (infos.into_iter())
+            .map(|(annotation_type, label, span)| (annotation_type, label, span, source));
+        make_snippet_from(self.code(), &label, iter, &[], lc, f)
+    }
+
+    /// Returns the title and the annotations of this error.
+    fn build_info(&self) -> BibInfo {
+        match self {
             &BibError::DuplicateBib(span, other) => (
                 "duplicate bibliography anchor".into(),
                 vec![
                     (
-                        Level::Warning,
+                        self.level(),
                         "this anchor has already appeared".into(),
                         span,
                     ),
                     (Level::Note, "previous occurrence".into(), other),
                 ],
             ),
-        };
-        let iter = (infos.into_iter())
-            .map(|(annotation_type, label, span)| (annotation_type, label, span, source));
-        make_snippet_from(&label, iter, &[], lc, f)
+        }
     }
 
     /// Convert a list of diagnostics collected by `diag_notations` to a list of snippets.
@@ -1328,4 +1872,29 @@ impl BibError {
             .map(move |&(source, ref diag)| diag.to_snippet(source, &mut lc, f))
             .collect::<Vec<_>>()
     }
+
+    /// Convert a list of bibliography errors to a list of located diagnostics.
+    #[must_use]
+    pub fn locate_list(diags: &[(&SourceInfo, BibError)]) -> Vec<LocatedDiagnostic> {
+        let mut lc = LineCache::default();
+        diags
+            .iter()
+            .map(|&(source, ref diag)| {
+                let (title, infos) = diag.build_info();
+                let annotations = (infos.into_iter())
+                    .map(|(level, message, span)| {
+                        LocatedAnnotation::new(level, message, span, source, &mut lc)
+                    })
+                    .collect::<Vec<_>>();
+                LocatedDiagnostic {
+                    code: diag.code(),
+                    level: annotations.first().map_or(Level::Error, |ann| ann.level),
+                    title: title.into_owned(),
+                    annotations,
+                    notes: vec![],
+                    fixes: vec![],
+                }
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,80 @@
+use crate::diag::{write_json, write_sarif, Diagnostic};
+use crate::grammar_tests::mkdb;
+use crate::statement::Span;
+use crate::util::HashSet;
+use annotate_snippets::Level;
+
+const DIAG_DB: &[u8] = b"$c wff |- $.
+$v ph $.
+wph $f wff ph $.
+ax-1 $a |- ph $.
+thm1 $p |- ph $=
+  wph ax-2 $.
+";
+
+#[test]
+fn test_locate_diags() {
+    let mut db = mkdb(DIAG_DB);
+    db.verify_pass();
+    let diags = db.locate_diags(db.diag_notations());
+    assert_eq!(diags.len(), 1);
+    let diag = &diags[0];
+    assert_eq!(diag.code, "StepMissing");
+    assert_eq!(diag.level, Level::Error);
+    assert_eq!(diag.annotations[0].file, "test.mm");
+    assert_eq!(diag.annotations[0].start, (5, 1));
+    assert_eq!(diag.annotations[0].end, (6, 14));
+}
+
+#[test]
+fn test_write_diags() {
+    let mut db = mkdb(DIAG_DB);
+    db.verify_pass();
+    let diags = db.locate_diags(db.diag_notations());
+    let mut json = vec![];
+    write_json(&diags, &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.starts_with("[\n  {\"code\": \"StepMissing\", \"severity\": \"error\""));
+    assert!(json.contains("\"file\": \"test.mm\", \"start\": {\"line\": 5, \"column\": 1}"));
+    let mut sarif = vec![];
+    write_sarif(&diags, "tool", "1.0", &mut sarif).unwrap();
+    let sarif = String::from_utf8(sarif).unwrap();
+    assert!(sarif.contains("\"rules\": [{\"id\": \"StepMissing\"}]"));
+    assert!(sarif.contains("\"region\": {\"startLine\": 5, \"startColumn\": 1"));
+}
+
+#[test]
+fn test_diag_codes() {
+    let codes = Diagnostic::CODES.iter().collect::<HashSet<_>>();
+    assert_eq!(codes.len(), Diagnostic::CODES.len());
+    assert!(codes.contains(&"StepMissing"));
+    let diag = Diagnostic::ProofStep(
+        0,
+        Span::NULL,
+        Box::new(Diagnostic::UnknownLabel(Span::NULL)),
+    );
+    assert_eq!(diag.code(), "ProofStep");
+    assert_eq!(diag.without_step().code(), "UnknownLabel");
+}
+
+#[test]
+fn test_default_level() {
+    let mut db = mkdb(DIAG_DB);
+    db.name_pass();
+    let (sset, stmt) = (db.parse_result(), db.statement(b"thm1").unwrap());
+    let diag = Diagnostic::ProofStep(
+        0,
+        Span::NULL,
+        Box::new(Diagnostic::UnknownLabel(Span::NULL)),
+    );
+    assert_eq!(diag.default_level(sset, stmt), Level::Warning);
+    assert_eq!(
+        Diagnostic::StepMissing(Box::default()).default_level(sset, stmt),
+        Level::Error
+    );
+    // The first annotation of a duplicate contributor is a note
+    assert_eq!(
+        Diagnostic::DuplicateContributor(Span::NULL, Span::NULL).default_level(sset, stmt),
+        Level::Note
+    );
+}
@@ -0,0 +1,313 @@
+//! Semantic comparison of two versions of a database.
+//!
+//! The assertions (`$a` and `$p` statements) of both versions are matched by label,
+//! and compared by their math string, hypotheses, distinct variable conditions,
+//! proof and comment. An assertion removed from the old version and added to the
+//! new one with an identical statement, hypotheses and proof is reported as renamed.
+
+use crate::as_str;
+use crate::scopeck::Frame;
+use crate::util::{write_json_string, HashMap, HashSet};
+use crate::{Database, StatementRef, StatementType};
+use std::fmt::{self, Display};
+use std::io;
+
+/// An aspect of an assertion which changed between two versions of a database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aspect {
+    /// The kind of the assertion (axiom or theorem), or its math string.
+    Math,
+    /// The labels or math strings of the essential hypotheses.
+    Hypotheses,
+    /// The distinct variable conditions.
+    Disjoints,
+    /// The proof.
+    Proof,
+    /// The comment preceding the assertion.
+    Comment,
+}
+
+impl Aspect {
+    /// The name of the aspect, as used in the output.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Math => "math",
+            Self::Hypotheses => "hypotheses",
+            Self::Disjoints => "disjoints",
+            Self::Proof => "proof",
+            Self::Comment => "comment",
+        }
+    }
+}
+
+/// A change of an assertion between two versions of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementChange {
+    /// The assertion only exists in the new version.
+    Added(String),
+    /// The assertion only exists in the old version.
+    Removed(String),
+    /// The assertion was renamed, keeping the same statement, hypotheses and proof.
+    /// Its distinct variable conditions and comment may have changed.
+    Renamed {
+        /// The label in the old version.
+        from: String,
+        /// The label in the new version.
+        to: String,
+        /// The aspects which changed, besides the label.
+        aspects: Vec<Aspect>,
+    },
+    /// The assertion exists in both versions, but some of its aspects changed.
+    Modified {
+        /// The label of the assertion.
+        label: String,
+        /// The aspects which changed.
+        aspects: Vec<Aspect>,
+    },
+}
+
+impl StatementChange {
+    const fn kind(&self) -> &'static str {
+        match self {
+            Self::Added(_) => "added",
+            Self::Removed(_) => "removed",
+            Self::Renamed { .. } => "renamed",
+            Self::Modified { .. } => "modified",
+        }
+    }
+
+    /// The label of the assertion, in the new version unless it was removed.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Added(label) | Self::Removed(label) | Self::Modified { label, .. } => label,
+            Self::Renamed { to, .. } => to,
+        }
+    }
+
+    fn aspects(&self) -> &[Aspect] {
+        match self {
+            Self::Added(_) | Self::Removed(_) => &[],
+            Self::Renamed { aspects, .. } | Self::Modified { aspects, .. } => aspects,
+        }
+    }
+}
+
+impl Display for StatementChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Renamed { from, to, .. } => write!(f, "renamed {from} -> {to}")?,
+            _ => write!(f, "{} {}", self.kind(), self.label())?,
+        }
+        let aspects = self.aspects();
+        if !aspects.is_empty() {
+            let names = aspects
+                .iter()
+                .map(|aspect| aspect.name())
+                .collect::<Vec<_>>();
+            write!(f, ": {}", names.join(", "))?;
+        }
+        if aspects == [Aspect::Comment] {
+            write!(f, " only")?;
+        }
+        Ok(())
+    }
+}
+
+/// The compared data of an assertion.
+struct AssertionInfo<'a> {
+    axiom: bool,
+    math: Vec<&'a [u8]>,
+    hyp_labels: Vec<&'a [u8]>,
+    hyp_math: Vec<Vec<&'a [u8]>>,
+    disjoints: Vec<(&'a [u8], &'a [u8])>,
+    /// The proof tokens, where the assertion's own hypotheses are replaced by
+    /// their index, so that renaming them does not change the proof.
+    proof: Vec<Result<&'a [u8], usize>>,
+    comment: Vec<&'a str>,
+}
+
+impl AssertionInfo<'_> {
+    /// Returns whether the other assertion can be a renaming of this one.
+    fn same_content(&self, other: &Self) -> bool {
+        self.axiom == other.axiom
+            && self.math == other.math
+            && self.hyp_math == other.hyp_math
+            && self.proof == other.proof
+    }
+
+    /// Returns the aspects which differ between the two assertions.
+    fn aspects(&self, other: &Self) -> Vec<Aspect> {
+        let mut aspects = vec![];
+        if self.axiom != other.axiom || self.math != other.math {
+            aspects.push(Aspect::Math);
+        }
+        if self.hyp_labels != other.hyp_labels || self.hyp_math != other.hyp_math {
+            aspects.push(Aspect::Hypotheses);
+        }
+        if self.disjoints != other.disjoints {
+            aspects.push(Aspect::Disjoints);
+        }
+        if self.proof != other.proof {
+            aspects.push(Aspect::Proof);
+        }
+        if self.comment != other.comment {
+            aspects.push(Aspect::Comment);
+        }
+        aspects
+    }
+}
+
+impl Database {
+    /// Returns the names of the pairs of variables with a distinct variable
+    /// condition in the given frame, including the optional ones, sorted.
+    pub(crate) fn disjoint_pairs(&self, frame: &Frame) -> Vec<(&[u8], &[u8])> {
+        let nset = self.name_result();
+        let var = |ix: usize| nset.atom_name(frame.var_list[ix]);
+        let mut pairs = (frame.mandatory_dv.iter())
+            .map(|&(ix1, ix2)| (var(ix1), var(ix2)))
+            .collect::<Vec<_>>();
+        for (ix1, set) in frame.optional_dv.iter().enumerate() {
+            pairs.extend(set.iter().map(|ix2| (var(ix1), var(ix2))));
+        }
+        for pair in &mut pairs {
+            if pair.0 > pair.1 {
+                *pair = (pair.1, pair.0);
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    /// Collects the compared data of an assertion.
+    fn assertion_info<'a>(&'a self, stmt: StatementRef<'a>) -> AssertionInfo<'a> {
+        let frame = self.scope_result().get(stmt.label());
+        let hyps = frame
+            .map(|frame| {
+                (frame.hypotheses.iter())
+                    .map(|hyp| self.statement_by_address(hyp.address()))
+                    .filter(|hyp| hyp.statement_type() == StatementType::Essential)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let disjoints = frame
+            .map(|frame| self.disjoint_pairs(frame))
+            .unwrap_or_default();
+        let buf = &stmt.segment().segment.buffer;
+        let proof = (stmt.proof_spans().iter())
+            .map(|span| {
+                let token = span.as_ref(buf);
+                (hyps.iter())
+                    .position(|hyp| hyp.label() == token)
+                    .map_or(Ok(token), Err)
+            })
+            .collect();
+        let comment = stmt.associated_comment().map_or_else(Vec::new, |comment| {
+            let buf = &comment.segment().segment.buffer;
+            as_str(comment.comment_contents().as_ref(buf))
+                .split_whitespace()
+                .collect()
+        });
+        AssertionInfo {
+            axiom: stmt.statement_type() == StatementType::Axiom,
+            math: stmt.math_iter().map(|token| token.slice).collect(),
+            hyp_labels: hyps.iter().map(StatementRef::label).collect(),
+            hyp_math: (hyps.iter())
+                .map(|hyp| hyp.math_iter().map(|token| token.slice).collect())
+                .collect(),
+            disjoints,
+            proof,
+            comment,
+        }
+    }
+
+    /// Compares this database, as the old version, to the given new version,
+    /// and returns the changes of their assertions.
+    ///
+    /// Added, modified and renamed assertions are listed in the order of the new
+    /// version, followed by the removed assertions in the order of the old version.
+    ///
+    /// Requires: [`Database::scope_pass`] on both databases
+    #[must_use]
+    pub fn diff(&self, new: &Self) -> Vec<StatementChange> {
+        let old_infos = (self.statements())
+            .filter(|stmt| stmt.is_assertion())
+            .map(|stmt| (stmt.label(), self.assertion_info(stmt)))
+            .collect::<Vec<_>>();
+        let old_index = (old_infos.iter().enumerate())
+            .map(|(ix, (label, _))| (*label, ix))
+            .collect::<HashMap<_, _>>();
+        let new_infos = (new.statements())
+            .filter(|stmt| stmt.is_assertion())
+            .map(|stmt| (stmt.label(), new.assertion_info(stmt)))
+            .collect::<Vec<_>>();
+        let new_labels = new_infos
+            .iter()
+            .map(|(label, _)| *label)
+            .collect::<HashSet<_>>();
+
+        // Assertions of the old version which are not in the new version
+        let mut removed = (old_infos.iter())
+            .filter(|(label, _)| !new_labels.contains(label))
+            .map(|(label, info)| (*label, info, false))
+            .collect::<Vec<_>>();
+        let mut changes = vec![];
+        for (label, info) in &new_infos {
+            if let Some(&ix) = old_index.get(label) {
+                let aspects = old_infos[ix].1.aspects(info);
+                if !aspects.is_empty() {
+                    changes.push(StatementChange::Modified {
+                        label: as_str(label).to_owned(),
+                        aspects,
+                    });
+                }
+            } else if let Some((from, old_info, renamed)) = (removed.iter_mut())
+                .find(|(_, old_info, renamed)| !renamed && old_info.same_content(info))
+            {
+                *renamed = true;
+                changes.push(StatementChange::Renamed {
+                    from: as_str(from).to_owned(),
+                    to: as_str(label).to_owned(),
+                    aspects: old_info.aspects(info),
+                });
+            } else {
+                changes.push(StatementChange::Added(as_str(label).to_owned()));
+            }
+        }
+        removed.retain(|(_, _, renamed)| !renamed);
+        changes.extend(
+            (removed.into_iter())
+                .map(|(label, _, _)| StatementChange::Removed(as_str(label).to_owned())),
+        );
+        changes
+    }
+}
+
+/// Writes a list of changes as a JSON array.
+///
+/// Each change is an object with its kind of `change` (`added`, `removed`,
+/// `renamed` or `modified`), the `label` of the assertion, the old label `from`
+/// for renamed assertions, and the list of changed `aspects`.
+pub fn write_json(changes: &[StatementChange], out: &mut impl io::Write) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, change) in changes.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ",\n" };
+        write!(
+            out,
+            "{sep}  {{\"change\": \"{}\", \"label\": ",
+            change.kind()
+        )?;
+        write_json_string(out, change.label().as_bytes())?;
+        if let StatementChange::Renamed { from, .. } = change {
+            write!(out, ", \"from\": ")?;
+            write_json_string(out, from.as_bytes())?;
+        }
+        let aspects = (change.aspects().iter())
+            .map(|aspect| format!("\"{}\"", aspect.name()))
+            .collect::<Vec<_>>();
+        write!(out, ", \"aspects\": [{}]}}", aspects.join(", "))?;
+    }
+    writeln!(out, "\n]")
+}
@@ -0,0 +1,122 @@
+use crate::diff::{self, Aspect, StatementChange};
+use crate::grammar_tests::mkdb;
+
+const OLD_DB: &[u8] = b"$c ( ) -> wff |- $.
+  $v ph ps ch $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wch $f wff ch $.
+  wi $a wff ( ph -> ps ) $.
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  $( Identity. $)
+  ax-2 $a |- ( ch -> ch ) $.
+  ${
+    a1i.1 $e |- ph $.
+    a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+  $}
+  ${
+    unused.1 $e |- ch $.
+    unused $p |- ch $= unused.1 $.
+  $}
+";
+
+const NEW_DB: &[u8] = b"$c ( ) -> wff |- $.
+  $v ph ps ch $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wch $f wff ch $.
+  wi $a wff ( ph -> ps ) $.
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  $( Identity of
+     wffs. $)
+  ax-2 $a |- ( ch -> ch ) $.
+  ax-3 $a |- ( ps -> ps ) $.
+  ${
+    $d ph ps $.
+    a1ii.1 $e |- ph $.
+    a1ii $p |- ( ps -> ph ) $= wph wps wph wi a1ii.1 wph wps ax-1 ax-mp $.
+  $}
+";
+
+#[test]
+fn test_diff() {
+    let mut old_db = mkdb(OLD_DB);
+    old_db.scope_pass();
+    let mut new_db = mkdb(NEW_DB);
+    new_db.scope_pass();
+    let changes = old_db.diff(&new_db);
+    assert_eq!(
+        changes,
+        vec![
+            StatementChange::Modified {
+                label: "ax-2".to_owned(),
+                aspects: vec![Aspect::Comment],
+            },
+            StatementChange::Added("ax-3".to_owned()),
+            StatementChange::Renamed {
+                from: "a1i".to_owned(),
+                to: "a1ii".to_owned(),
+                aspects: vec![Aspect::Hypotheses, Aspect::Disjoints],
+            },
+            StatementChange::Removed("unused".to_owned()),
+        ]
+    );
+    let text = changes.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        text,
+        [
+            "modified ax-2: comment only",
+            "added ax-3",
+            "renamed a1i -> a1ii: hypotheses, disjoints",
+            "removed unused",
+        ]
+    );
+    let mut out = vec![];
+    diff::write_json(&changes[2..], &mut out).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out).unwrap(),
+        "[
+  {\"change\": \"renamed\", \"label\": \"a1ii\", \"from\": \"a1i\", \"aspects\": [\"hypotheses\", \"disjoints\"]},
+  {\"change\": \"removed\", \"label\": \"unused\", \"aspects\": []}
+]
+"
+    );
+}
+
+#[test]
+fn test_diff_modified() {
+    let mut old_db = mkdb(OLD_DB);
+    old_db.scope_pass();
+    let new_text = String::from_utf8(OLD_DB.to_vec())
+        .unwrap()
+        .replace(
+            "ax-1 $a |- ( ph -> ( ps -> ph ) )",
+            "ax-1 $a |- ( ps -> ( ph -> ps ) )",
+        )
+        .replace("unused $p |- ch $= unused.1 $.", "unused $p |- ch $= ? $.");
+    let mut new_db = mkdb(new_text.as_bytes());
+    new_db.scope_pass();
+    assert_eq!(
+        old_db.diff(&new_db),
+        vec![
+            StatementChange::Modified {
+                label: "ax-1".to_owned(),
+                aspects: vec![Aspect::Math],
+            },
+            StatementChange::Modified {
+                label: "unused".to_owned(),
+                aspects: vec![Aspect::Proof],
+            },
+        ]
+    );
+}
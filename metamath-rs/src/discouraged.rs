@@ -22,36 +22,12 @@ impl Database {
                     usage_discouraged_map.insert(label, BTreeSet::new());
                 }
                 if sref.statement_type() == StatementType::Provable {
-                    let mut steps;
-                    if sref.proof_len() > 0 && sref.proof_slice_at(0) == b"(" {
-                        let mut i = 1;
-                        while i < sref.proof_len() {
-                            let tk = sref.proof_slice_at(i);
-                            i += 1;
-                            if tk == b")" {
-                                break;
-                            }
-                            if let Some(usage) = usage_discouraged_map.get_mut(tk) {
-                                usage.insert(label);
-                            }
+                    for (_, tk) in sref.use_iter() {
+                        if let Some(usage) = usage_discouraged_map.get_mut(tk) {
+                            usage.insert(label);
                         }
-                        steps = 0;
-                        while i < sref.proof_len() {
-                            let chunk = sref.proof_slice_at(i);
-                            steps += chunk.iter().filter(|ch| (b'A'..=b'T').contains(ch)).count();
-                            i += 1;
-                        }
-                    } else {
-                        let mut i = 0;
-                        while i < sref.proof_len() {
-                            let tk = sref.proof_slice_at(i);
-                            if let Some(usage) = usage_discouraged_map.get_mut(tk) {
-                                usage.insert(label);
-                            }
-                            i += 1;
-                        }
-                        steps = i as usize;
                     }
+                    let steps = sref.proof_step_count();
                     if modification_discouraged {
                         modif_discouraged_map.insert(label, steps);
                     }
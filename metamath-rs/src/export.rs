@@ -44,6 +44,16 @@ impl From<fmt::Error> for ExportError {
     }
 }
 
+impl From<ExportError> for Diagnostic {
+    fn from(err: ExportError) -> Diagnostic {
+        match err {
+            ExportError::Io(err) => err.into(),
+            ExportError::Verify(diag) => diag,
+            ExportError::Format(err) => Diagnostic::IoError(format!("{err}")),
+        }
+    }
+}
+
 impl fmt::Display for ExportError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -0,0 +1,213 @@
+//! Extraction of a minimal self-contained database for a set of theorems.
+//!
+//! This mirrors the `write source /extract` command of metamath.exe: the resulting
+//! database only contains the given assertions, and everything needed to verify
+//! them.
+
+use crate::statement::{CommandToken, StatementAddress};
+use crate::util::HashSet;
+use crate::{Database, StatementRef, StatementType};
+use std::io::{self, Write};
+
+/// Writes the kept statements, deferring the opening of scoping blocks
+/// until a kept statement is found inside them.
+struct Extractor<'a, W: Write> {
+    out: &'a mut W,
+    /// For each open scoping block, whether its `${` has been written,
+    /// and the output pending until a kept statement is found in the block.
+    blocks: Vec<(bool, Vec<u8>)>,
+}
+
+impl<W: Write> Extractor<'_, W> {
+    /// Writes a statement, indented according to the current block nesting.
+    fn write(&mut self, text: &[u8]) -> io::Result<()> {
+        let mut line = vec![b' '; 2 * self.blocks.len()];
+        line.extend_from_slice(text);
+        line.push(b'\n');
+        match self.blocks.last_mut() {
+            Some((false, pending)) => pending.extend(line),
+            _ => self.out.write_all(&line)?,
+        }
+        Ok(())
+    }
+
+    /// Writes the pending output of all blocks, when a kept statement is found.
+    fn flush(&mut self) -> io::Result<()> {
+        for (depth, (opened, pending)) in self.blocks.iter_mut().enumerate() {
+            if !*opened {
+                *opened = true;
+                writeln!(self.out, "{}${{", " ".repeat(2 * depth))?;
+                self.out.write_all(pending)?;
+                pending.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) {
+        self.blocks.push((false, vec![]));
+    }
+
+    /// Closes a block, dropping it entirely if it did not contain any kept statement.
+    fn close(&mut self) -> io::Result<()> {
+        if let Some((true, _)) = self.blocks.pop() {
+            writeln!(self.out, "{}$}}", " ".repeat(2 * self.blocks.len()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Returns the addresses of the given statements and of all the statements
+    /// they transitively depend on.
+    pub(crate) fn extract_dependencies(
+        &self,
+        stmts: &[StatementRef<'_>],
+    ) -> HashSet<StatementAddress> {
+        let scope = self.scope_result();
+        let formulas = self.try_stmt_parse_result();
+        let mut keep = HashSet::default();
+        let mut queue = stmts.to_vec();
+        while let Some(stmt) = queue.pop() {
+            if !keep.insert(stmt.address()) {
+                continue;
+            }
+            if stmt.is_assertion() {
+                if let Some(frame) = scope.get(stmt.label()) {
+                    queue.extend(
+                        (frame.hypotheses.iter())
+                            .map(|hyp| self.statement_by_address(hyp.address())),
+                    );
+                }
+            }
+            if stmt.statement_type() == StatementType::Provable {
+                queue.extend(
+                    stmt.use_iter()
+                        .filter_map(|(_, label)| self.statement(label)),
+                );
+            }
+            if let Some(formula) = formulas.and_then(|formulas| formulas.get_formula(&stmt)) {
+                queue.extend(
+                    (formula.labels_postorder_iter())
+                        .filter_map(|label| self.statement_by_label(label)),
+                );
+            }
+        }
+        keep
+    }
+
+    /// Writes a self-contained database containing only the given assertions,
+    /// and everything they transitively depend on: the assertions referenced in
+    /// their proofs, their hypotheses, the declarations of the constants and
+    /// variables they use, the `$d` conditions between these variables, and the
+    /// scoping blocks around them.
+    ///
+    /// The comments of the kept assertions are preserved, as well as the `$t`
+    /// typesetting definitions of the kept symbols and the `$j` commands which only
+    /// refer to kept symbols and labels.
+    ///
+    /// If [`Database::stmt_parse_pass`] was run, the syntax axioms needed to parse
+    /// the kept statements are also kept, instead of only the ones referenced in proofs.
+    ///
+    /// Requires: [`Database::scope_pass`]
+    pub fn write_extract(
+        &self,
+        stmts: &[StatementRef<'_>],
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let keep = self.extract_dependencies(stmts);
+        let nset = self.name_result();
+        let symbols = (keep.iter())
+            .flat_map(|&addr| self.statement_by_address(addr).math_iter())
+            .map(|token| token.slice)
+            .collect::<HashSet<_>>();
+        // Command arguments which are symbols or labels must be kept
+        let is_kept = |arg: &[u8]| {
+            if let Some(stmt) = self.statement(arg) {
+                keep.contains(&stmt.address())
+            } else {
+                nset.lookup_symbol(arg).is_none() || symbols.contains(arg)
+            }
+        };
+
+        let mut ex = Extractor {
+            out,
+            blocks: vec![],
+        };
+        for sref in self.parse_result().segments(..) {
+            let buf = &**sref.buffer;
+            for stmt in sref.range(..) {
+                match stmt.statement_type() {
+                    StatementType::OpenGroup => ex.open(),
+                    StatementType::CloseGroup => ex.close()?,
+                    StatementType::Constant | StatementType::Variable | StatementType::Disjoint => {
+                        let tokens = (stmt.math_iter())
+                            .filter(|token| symbols.contains(token.slice))
+                            .map(|token| token.slice)
+                            .collect::<Vec<_>>();
+                        let min_len = if stmt.statement_type() == StatementType::Disjoint {
+                            2
+                        } else {
+                            1
+                        };
+                        if tokens.len() >= min_len {
+                            let keyword = &stmt.span().as_ref(buf)[..2];
+                            ex.write(&[keyword, b" ", &tokens.join(&b" "[..]), b" $."].concat())?;
+                        }
+                    }
+                    StatementType::TypesettingComment | StatementType::AdditionalInfoComment => {
+                        let (keyword, commands) =
+                            if stmt.statement_type() == StatementType::TypesettingComment {
+                                ("$t", &sref.t_commands)
+                            } else {
+                                ("$j", &sref.j_commands)
+                            };
+                        let mut text = format!("$( {keyword}").into_bytes();
+                        let mut count = 0;
+                        for (_, (span, args)) in commands.iter().filter(|(ix, _)| *ix == stmt.index)
+                        {
+                            let kept = if keyword == "$t" {
+                                // Only the symbol defined by a typesetting definition matters
+                                match &**args {
+                                    [CommandToken::Keyword(cmd), symbol, ..]
+                                        if cmd.as_ref(buf).ends_with(b"def") =>
+                                    {
+                                        symbols.contains(&*symbol.value(buf))
+                                    }
+                                    _ => true,
+                                }
+                            } else {
+                                args.iter().skip(1).all(|arg| is_kept(&arg.value(buf)))
+                            };
+                            if kept {
+                                text.extend_from_slice(b"\n  ");
+                                text.extend_from_slice(span.as_ref(buf));
+                                count += 1;
+                            }
+                        }
+                        if count > 0 {
+                            text.extend_from_slice(b"\n$)");
+                            ex.write(&text)?;
+                        }
+                    }
+                    StatementType::Axiom
+                    | StatementType::Provable
+                    | StatementType::Essential
+                    | StatementType::Floating
+                        if keep.contains(&stmt.address()) =>
+                    {
+                        ex.flush()?;
+                        if stmt.is_assertion() {
+                            if let Some(comment) = stmt.associated_comment() {
+                                ex.write(comment.span().as_ref(buf))?;
+                            }
+                        }
+                        ex.write(stmt.span().as_ref(buf))?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::grammar_tests::mkdb;
+
+const EXTRACT_DB: &[u8] = b"$( $t htmldef \"ph\" as \"ph\"; htmldef \"ch\" as \"ch\"; $)
+  $c ( ) -> wff |- $.
+  $v ph ps ch $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wch $f wff ch $.
+  $( Implication. $)
+  wi $a wff ( ph -> ps ) $.
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ax-2 $a |- ( ch -> ch ) $.
+  ${
+    $d ph ps ch $.
+    a1i.1 $e |- ph $.
+    $( Inference. $)
+    a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+    ${
+      unused.1 $e |- ch $.
+      unused $p |- ch $= unused.1 $.
+    $}
+  $}
+";
+
+#[test]
+fn test_extract() {
+    let mut db = mkdb(EXTRACT_DB);
+    db.scope_pass();
+    let mut out = vec![];
+    db.write_extract(&[db.statement(b"a1i").unwrap()], &mut out)
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out).unwrap(),
+        "$( $t
+  htmldef \"ph\" as \"ph\";
+$)
+$c ( ) -> wff |- $.
+$v ph ps $.
+wph $f wff ph $.
+wps $f wff ps $.
+$( Implication. $)
+wi $a wff ( ph -> ps ) $.
+${
+  min $e |- ph $.
+  maj $e |- ( ph -> ps ) $.
+  ax-mp $a |- ps $.
+$}
+ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+${
+  $d ph ps $.
+  a1i.1 $e |- ph $.
+  $( Inference. $)
+  a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+$}
+"
+    );
+}
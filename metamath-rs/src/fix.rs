@@ -0,0 +1,239 @@
+//! Machine-applicable fixes for mechanical diagnostics.
+//!
+//! Some diagnostics have an obvious fix, which can be applied without human
+//! review:
+//!
+//! - `TrailingWhitespace`: the whitespace is removed;
+//! - `TabUsed`: the tabs are expanded to spaces, with tab stops every 8 columns;
+//! - `OldAltNotDiscouraged`: the missing `(New usage is discouraged.)` and
+//!   `(Proof modification is discouraged.)` parentheticals are added at the end
+//!   of the statement's comment;
+//! - `DateParseError`: dates in other common formats, like `1 January 2020`,
+//!   `1-jan-2020` or `2020-01-01`, are rewritten in the `1-Jan-2020` format;
+//! - `DefaultAuthor`: the `?who?` placeholder is replaced by an author name,
+//!   if one is provided.
+//!
+//! The fixes of a list of diagnostics are obtained with [`Database::diag_fixes`],
+//! and applied to the source texts with [`Database::apply_fixes`].
+
+use crate::comment_parser::{Date, Parenthetical};
+use crate::diag::Diagnostic;
+use crate::segment_set::SegmentSet;
+use crate::statement::StatementAddress;
+use crate::{Database, Span, StatementRef, StatementType};
+use std::ops::Range;
+
+/// A machine-applicable fix for a diagnostic:
+/// the replacement of a range of text in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// Name of the source file, as loaded.
+    pub file: String,
+    /// Byte range of the replaced text within the source file.
+    pub range: Range<usize>,
+    /// The replacement text.
+    pub replacement: String,
+}
+
+/// The number of columns between tab stops, when expanding tabs.
+const TAB_WIDTH: usize = 8;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Parses a date in one of the common formats `1-Jan-2020`, `1 January 2020`,
+/// or `2020-01-01`, ignoring the case of month names.
+fn parse_date_leniently(text: &[u8]) -> Option<Date> {
+    let text = std::str::from_utf8(text).ok()?;
+    let parts = text
+        .split(['-', ' ', '/', '.'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    let &[first, second, third] = &*parts else {
+        return None;
+    };
+    let number = |part: &str| -> Option<u16> {
+        Some(part)
+            .filter(|part| part.bytes().all(|c| c.is_ascii_digit()))?
+            .parse()
+            .ok()
+    };
+    let (year, month, day) = if first.len() == 4 {
+        (first, number(second)?, number(third)?)
+    } else {
+        let name = second.to_ascii_lowercase();
+        let month = (name.len() >= 3)
+            .then(|| MONTHS.iter().position(|month| month.starts_with(&name)))??;
+        (third, month as u16 + 1, number(first)?)
+    };
+    if year.len() != 4 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(Date {
+        year: number(year)?,
+        month: month as u8,
+        day: day as u8,
+    })
+}
+
+/// Returns the column of the given position in its line, expanding tabs.
+fn column(buf: &[u8], pos: usize) -> usize {
+    let line_start = buf[..pos]
+        .iter()
+        .rposition(|&c| c == b'\n' || c == b'\r')
+        .map_or(0, |i| i + 1);
+    buf[line_start..pos].iter().fold(0, |col, &c| {
+        if c == b'\t' {
+            col + TAB_WIDTH - col % TAB_WIDTH
+        } else {
+            col + 1
+        }
+    })
+}
+
+impl Diagnostic {
+    /// Returns the edits fixing this diagnostic, attached to the given statement,
+    /// as spans of the statement's segment and their replacements.
+    /// Returns an empty list if the diagnostic has no mechanical fix.
+    fn edits(&self, stmt: StatementRef<'_>, author: Option<&str>) -> Vec<(Span, String)> {
+        let buf = &**stmt.segment.buffer;
+        match *self {
+            Self::TrailingWhitespace(span) => vec![(span, String::new())],
+            Self::TabUsed(span) => {
+                let col = column(buf, span.start as usize);
+                let width = TAB_WIDTH - col % TAB_WIDTH + TAB_WIDTH * (span.len() - 1);
+                vec![(span, " ".repeat(width))]
+            }
+            Self::DateParseError(span) => parse_date_leniently(span.as_ref(buf))
+                .map(|date| (span, date.to_string()))
+                .into_iter()
+                .collect(),
+            Self::DefaultAuthor(span) => author
+                .map(|author| (span, author.to_owned()))
+                .into_iter()
+                .collect(),
+            Self::OldAltNotDiscouraged => {
+                let Some(comment) = stmt.associated_comment() else {
+                    return vec![];
+                };
+                let (mut proof_mod, mut new_usage) = (false, false);
+                for (_, paren) in comment.parentheticals() {
+                    match paren {
+                        Parenthetical::ProofModificationDiscouraged => proof_mod = true,
+                        Parenthetical::NewUsageDiscouraged => new_usage = true,
+                        _ => {}
+                    }
+                }
+                let mut parens = vec![];
+                if !proof_mod && stmt.statement_type() != StatementType::Axiom {
+                    parens.push("(Proof modification is discouraged.)");
+                }
+                if !new_usage {
+                    parens.push("(New usage is discouraged.)");
+                }
+                let contents = comment.comment_contents();
+                let Some(end) = contents
+                    .as_ref(buf)
+                    .iter()
+                    .rposition(|c| !c.is_ascii_whitespace())
+                else {
+                    return vec![];
+                };
+                // Add the parentheticals on a new line, aligned with the comment text
+                let pos = contents.start as usize + end + 1;
+                let indent = column(buf, contents.start as usize) + 1;
+                let text = format!("\n{}{}", " ".repeat(indent), parens.join("  "));
+                vec![(Span::new(pos, pos), text)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns the fixes of this diagnostic, attached to the given statement,
+    /// as replacements in the source files.
+    pub(crate) fn fixes(
+        &self,
+        sset: &SegmentSet,
+        addr: StatementAddress,
+        author: Option<&str>,
+    ) -> Vec<Fix> {
+        let source = sset.source_info(addr.segment_id);
+        let offset = source.span.start as usize;
+        (self
+            .edits(sset.statement_or_dummy(addr), author)
+            .into_iter())
+        .map(|(span, replacement)| Fix {
+            file: source.name.clone(),
+            range: offset + span.start as usize..offset + span.end as usize,
+            replacement,
+        })
+        .collect()
+    }
+}
+
+impl Database {
+    /// Returns the fixes of the given diagnostics, skipping the ones which are not
+    /// reported according to the lint policy.
+    ///
+    /// The `?who?` placeholders reported by `DefaultAuthor` are only fixed
+    /// if an `author` is provided.
+    #[must_use]
+    pub fn diag_fixes(
+        &self,
+        diags: &[(StatementAddress, Diagnostic)],
+        author: Option<&str>,
+    ) -> Vec<Fix> {
+        let sset = self.parse_result();
+        (diags.iter())
+            .filter(|(addr, diag)| self.diag_level(*addr, diag).is_some())
+            .flat_map(|(addr, diag)| diag.fixes(sset, *addr, author))
+            .collect()
+    }
+
+    /// Applies the given fixes to the source files of the database.
+    /// Returns the names of the modified files together with their new contents.
+    ///
+    /// Fixes overlapping a previous fix in the same file are skipped.
+    /// The database itself is not modified: the new contents shall be written
+    /// to the files and the database parsed again.
+    #[must_use]
+    pub fn apply_fixes(&self, fixes: &[Fix]) -> Vec<(String, Vec<u8>)> {
+        let mut by_file: Vec<(&str, Vec<&Fix>)> = vec![];
+        for fix in fixes {
+            match by_file.iter_mut().find(|(file, _)| *file == fix.file) {
+                Some((_, file_fixes)) => file_fixes.push(fix),
+                None => by_file.push((&fix.file, vec![fix])),
+            }
+        }
+        (by_file.into_iter())
+            .filter_map(|(file, mut file_fixes)| {
+                let text = self.source_text(file)?;
+                file_fixes.sort_by_key(|fix| (fix.range.start, fix.range.end));
+                let mut out = Vec::with_capacity(text.len());
+                let mut pos = 0;
+                for fix in file_fixes {
+                    if fix.range.start < pos || fix.range.end > text.len() {
+                        continue;
+                    }
+                    out.extend_from_slice(&text[pos..fix.range.start]);
+                    out.extend_from_slice(fix.replacement.as_bytes());
+                    pos = fix.range.end;
+                }
+                out.extend_from_slice(&text[pos..]);
+                Some((file.to_owned(), out))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::diag::Diagnostic;
+use crate::grammar_tests::mkdb;
+
+#[test]
+fn test_apply_fixes() {
+    let mut db = mkdb(
+        b"$c wff |- $.   
+$v ph $.
+wph $f wff ph $.
+\t$( Axiom.  (Contributed by ?who?, 1 january 2020.) $)
+ax-1 $a |- ph $.
+  $( Old version.
+     (Contributed by Foo Bar, 2020-03-04.) $)
+thmOLD $p |- ph $= ( ax-1 ) A $.
+",
+    );
+    db.scope_pass();
+    db.typesetting_pass();
+    let diags = db.verify_markup(None);
+    let fixes = db.diag_fixes(&diags, Some("Jane Doe"));
+    assert_eq!(fixes.len(), 6);
+    let output = db.apply_fixes(&fixes);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].0, "test.mm");
+    assert_eq!(
+        std::str::from_utf8(&output[0].1).unwrap(),
+        "$c wff |- $.
+$v ph $.
+wph $f wff ph $.
+        $( Axiom.  (Contributed by Jane Doe, 1-Jan-2020.) $)
+ax-1 $a |- ph $.
+  $( Old version.
+     (Contributed by Foo Bar, 4-Mar-2020.)
+     (Proof modification is discouraged.)  (New usage is discouraged.) $)
+thmOLD $p |- ph $= ( ax-1 ) A $.
+"
+    );
+}
+
+#[test]
+fn test_fixes_without_author() {
+    let mut db = mkdb(b"$( Axiom.  (Contributed by ?who?, 1-Jan-2020.) $)\nax-1 $a |- ph $.\n");
+    db.scope_pass();
+    db.typesetting_pass();
+    let diags = db.verify_markup(None);
+    assert!(matches!(&*diags, [(_, Diagnostic::DefaultAuthor(_))]));
+    assert!(db.diag_fixes(&diags, None).is_empty());
+}
+
+#[test]
+fn test_fix_space_separated_date() {
+    let mut db = mkdb(b"$( Axiom.  (Contributed by Foo Bar, 1 Jan 2020.) $)\nax-1 $a |- ph $.\n");
+    db.scope_pass();
+    db.typesetting_pass();
+    let diags = db.verify_markup(None);
+    assert!(matches!(&*diags, [(_, Diagnostic::DateParseError(_))]));
+    let fixes = db.diag_fixes(&diags, None);
+    assert_eq!(fixes.len(), 1);
+    assert_eq!(fixes[0].replacement, "1-Jan-2020");
+}
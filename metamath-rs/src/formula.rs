@@ -19,6 +19,7 @@
 
 use crate::as_str;
 use crate::bit_set::Bitset;
+use crate::mathml::MathMlRenderer;
 use crate::nameck::Atom;
 use crate::nameck::Nameset;
 use crate::scopeck::Hyp;
@@ -557,6 +558,64 @@ impl<'a> FormulaRef<'a> {
         }
     }
 
+    /// Convert this formula into a presentation `MathML` string.
+    #[must_use]
+    pub fn as_mathml(&self, renderer: &MathMlRenderer) -> String {
+        let mut s = String::new();
+        self.write_mathml(renderer, &mut s).unwrap();
+        s
+    }
+
+    /// Write this formula as a presentation `MathML` `<math>` element to the given writer.
+    /// Each syntax axiom application is rendered as an `<mrow>` element.
+    pub fn write_mathml(
+        &self,
+        renderer: &MathMlRenderer,
+        w: &mut impl std::fmt::Write,
+    ) -> std::fmt::Result {
+        write!(
+            w,
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>"
+        )?;
+        renderer.write_token(w, self.db.name_result().atom_name(self.typecode), false)?;
+        self.write_sub_mathml(self.root, renderer, w)?;
+        write!(w, "</mrow></math>")
+    }
+
+    /// Write a subformula as `MathML` to the given writer.
+    fn write_sub_mathml(
+        &self,
+        node_id: NodeId,
+        renderer: &MathMlRenderer,
+        w: &mut impl std::fmt::Write,
+    ) -> std::fmt::Result {
+        let nset = self.db.name_result();
+        let sref = self.db.parse_result().statement(
+            nset.lookup_label(nset.atom_name(self.tree[node_id]))
+                .unwrap()
+                .address,
+        );
+        let mut children = self.tree.children_iter(node_id);
+        let nested = sref.math_len() > 2;
+        if nested {
+            write!(w, "<mrow>")?;
+        }
+        for token in sref.math_iter().skip(1) {
+            let is_variable = nset
+                .lookup_symbol(token.slice)
+                .is_some_and(|symbol| symbol.stype == SymbolType::Variable);
+            // Variables are replaced by the corresponding child, except in floating hypotheses
+            match is_variable.then(|| children.next()).flatten() {
+                Some(child) => self.write_sub_mathml(child, renderer, w)?,
+                None => renderer.write_token(w, token.slice, is_variable)?,
+            }
+        }
+        if nested {
+            write!(w, "</mrow>")?;
+        }
+        Ok(())
+    }
+
     /// Handles the variables present in the formula but not in the substitution list
     /// The function `f` provided can modify on the fly the substitution list, adding any missing one.
     pub fn complete_substitutions<E>(
@@ -107,3 +107,56 @@ fn test_replace() {
         .unwrap();
     assert_eq!(expected, formula.replace(&old_sub_fmla, &new_sub_fmla));
 }
+
+#[test]
+/// Rendering of ` ( 1 + 2 ) = ( 2 + 1 ) ` to `MathML`, with one `<mrow>` per syntax axiom.
+fn test_mathml() {
+    let mut db = mkdb(FORMULA_DB);
+    let stmt_parse = db.stmt_parse_pass().clone();
+    db.typesetting_pass();
+    let formula = stmt_parse
+        .get_formula(&db.statement(b"1p2com").unwrap())
+        .unwrap();
+    let mut renderer = db.mathml_renderer();
+    renderer.set_override(b"|-", "<mo>&#x22A2;</mo>");
+    assert_eq!(
+        formula.as_ref(&db).as_mathml(&renderer),
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><mo>&#x22A2;</mo>\
+         <mrow><mrow><mo>(</mo><mn>1</mn><mo>+</mo><mn>2</mn><mo>)</mo></mrow><mo>=</mo>\
+         <mrow><mo>(</mo><mn>2</mn><mo>+</mo><mn>1</mn><mo>)</mo></mrow></mrow></mrow></math>"
+    );
+}
+
+/// Rendering of a formula to `MathML`, with glyphs from the `althtmldef` definitions.
+#[test]
+fn test_mathml_althtmldef() {
+    let mut db = mkdb(
+        b"$(
+$t
+althtmldef \"|-\" as '&#8866; ';
+althtmldef \"ph\" as '<SPAN CLASS=wff STYLE=\"color:blue\">&#x1D711;</SPAN>';
+althtmldef \"->\" as ' &rarr; ';
+althtmldef \"(\" as '(';
+althtmldef \")\" as ')';
+althtmldef \"T.\" as '<IMG SRC=\"top.gif\" ALT=\"T.\">';
+$)
+$c |- wff ( ) -> T. $.
+$( $j syntax 'wff'; syntax '|-' as 'wff'; $)
+$v ph $.
+wph $f wff ph $.
+wtru $a wff T. $.
+wi $a wff ( ph -> ph ) $.
+ax-tru $a |- ( T. -> ph ) $.
+",
+    );
+    let stmt_parse = db.stmt_parse_pass().clone();
+    db.typesetting_pass();
+    let formula = stmt_parse
+        .get_formula(&db.statement(b"ax-tru").unwrap())
+        .unwrap();
+    assert_eq!(
+        formula.as_ref(&db).as_mathml(&db.mathml_renderer()),
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><mo>\u{22a2}</mo>\
+         <mrow><mo>(</mo><mi>T.</mi><mo>\u{2192}</mo><mi>\u{1d711}</mi><mo>)</mo></mrow></mrow></math>"
+    );
+}
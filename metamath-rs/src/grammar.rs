@@ -9,13 +9,14 @@ use crate::nameck::{Atom, NameReader, Nameset};
 use crate::segment::Segment;
 use crate::segment_set::SegmentSet;
 use crate::statement::{CommandToken, SegmentId, StatementAddress, SymbolType, TokenRef};
-use crate::util::HashMap;
+use crate::unicode::UnicodeRenderer;
+use crate::util::{HashMap, HashSet};
 use crate::{as_str, Database, Span, StatementRef, StatementType};
 use log::{debug, warn};
 use std::collections::hash_map::Entry;
 use std::fmt;
 use std::fmt::Formatter;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tinyvec::ArrayVec;
 
 #[cfg(feature = "dot")]
@@ -38,8 +39,8 @@ fn as_string(node_id: NodeId) -> String {
 
 /// For the labels in DOT format
 #[cfg(feature = "dot")]
-fn escape(str: &str) -> String {
-    str.replace('\\', "\\\\ ").replace('\"', "\\\"")
+pub(crate) fn escape(str: &str) -> String {
+    str.replace('\\', "\\\\").replace('\"', "\\\"")
 }
 
 /// The grammar tree represents a Moore/Mealy Machine, where each node is a state of the automaton,
@@ -162,6 +163,9 @@ pub struct Grammar {
     root: NodeId, // The root of the Grammar tree
     diagnostics: HashMap<StatementAddress, Diagnostic>,
     debug: bool,
+    /// The typecodes which are not the typecode of any syntax axiom,
+    /// computed on first use by [`Database::variable_typecodes`].
+    pub(crate) variable_typecodes: OnceLock<HashSet<TypeCode>>,
 }
 
 /// A `Reduce` step applies a completed grammar rule to some of the recent parse trees,
@@ -397,6 +401,7 @@ impl Default for Grammar {
             root: 0,
             diagnostics: HashMap::default(),
             debug: false,
+            variable_typecodes: OnceLock::new(),
         }
     }
 }
@@ -1731,8 +1736,8 @@ impl StmtParse {
         diags
     }
 
-    /// Writes down all formulas
-    pub(crate) fn dump(&self, db: &Database) {
+    /// Writes down all formulas, followed by their Unicode rendering if a `renderer` is given
+    pub(crate) fn dump(&self, db: &Database, renderer: Option<&UnicodeRenderer>) {
         println!("Formula Dump:");
         let sset = db.parse_result();
         let nset = db.name_result();
@@ -1744,11 +1749,16 @@ impl StmtParse {
                 let Some(formula) = sps.formulas.get(&sref.address()) else {
                     continue;
                 };
-                println!(
-                    "{}: {}",
-                    as_str(nset.statement_name(&sref)),
-                    formula.as_ref(db).as_sexpr()
-                );
+                let label = as_str(nset.statement_name(&sref));
+                let sexpr = formula.as_ref(db).as_sexpr();
+                if let Some(renderer) = renderer {
+                    let tokens = std::iter::once(formula.get_typecode())
+                        .chain(formula.as_ref(db))
+                        .map(|symbol| nset.atom_name(symbol));
+                    println!("{label}: {sexpr} = {}", renderer.render(tokens));
+                } else {
+                    println!("{label}: {sexpr}");
+                }
             }
         }
     }
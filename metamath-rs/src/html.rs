@@ -0,0 +1,950 @@
+//! Generation of a static HTML website for the database.
+//!
+//! This module produces one page per `$a` and `$p` statement, in the style of
+//! the theorem pages generated by
+//! [`metamath.exe`](https://github.com/metamath/metamath-exe)'s
+//! `SHOW STATEMENT / HTML` command, using the typesetting information parsed
+//! from the `$t` comment (see [`crate::typesetting`]).
+//!
+//! Each page is generated in two variants: the GIF variant uses the `htmldef`
+//! definitions, and the Unicode variant uses the `althtmldef` definitions.
+//! The two variants are written in sibling directories, named after the
+//! `htmldir` and `althtmldir` typesetting commands.
+
+use crate::bit_set::Bitset;
+use crate::comment_parser::{CommentItem, CommentParser};
+use crate::database::{time, Promise};
+use crate::outline::OutlineNodeRef;
+use crate::parser::HeadingLevel;
+use crate::scopeck::Hyp;
+use crate::statement::{StatementAddress, TokenPtr};
+use crate::typesetting::TypesettingData;
+use crate::util::HashMap;
+use crate::{as_str, Database, Span, StatementRef, StatementType};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// TODO(Mario): remove hardcoded logical step symbol
+const PROVABLE_TC: &[u8] = b"|-";
+
+/// Number of pages generated by each job submitted to the executor.
+const PAGES_PER_JOB: usize = 64;
+
+/// Number of statements listed on each page of the theorem list.
+const THEOREMS_PER_PAGE: usize = 100;
+
+/// Name of the table of contents page.
+const TOC_FILE: &str = "mmtoc.html";
+
+/// Name of the page listing the most recent contributions.
+const RECENT_FILE: &str = "mmrecent.html";
+
+/// Returns the file name of the given page of the theorem list (starting from 0).
+fn theorem_list_file(page: usize) -> String {
+    if page == 0 {
+        "mmtheorems.html".to_owned()
+    } else {
+        format!("mmtheorems{}.html", page + 1)
+    }
+}
+
+/// The variants of the generated website.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlVariant {
+    /// Math symbols are rendered using the `htmldef` definitions,
+    /// which are generally GIF images.
+    Gif,
+    /// Math symbols are rendered using the `althtmldef` definitions,
+    /// which are generally Unicode characters.
+    Unicode,
+}
+
+impl HtmlVariant {
+    /// Returns the other variant, which the generated pages link to.
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            HtmlVariant::Gif => HtmlVariant::Unicode,
+            HtmlVariant::Unicode => HtmlVariant::Gif,
+        }
+    }
+
+    /// Returns the name of the directory in which pages of this variant are written,
+    /// taken from the `htmldir` and `althtmldir` typesetting commands.
+    #[must_use]
+    pub fn dir_name(self, typesetting: &TypesettingData) -> String {
+        let (dir, default) = match self {
+            HtmlVariant::Gif => (&typesetting.html_dir, "mpegif"),
+            HtmlVariant::Unicode => (&typesetting.alt_html_dir, "mpeuni"),
+        };
+        dir.as_ref()
+            .and_then(|(_, dir)| {
+                as_str(dir)
+                    .split('/')
+                    .rfind(|part| !part.is_empty() && *part != "." && *part != "..")
+            })
+            .unwrap_or(default)
+            .to_owned()
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            HtmlVariant::Gif => "GIF",
+            HtmlVariant::Unicode => "Unicode",
+        }
+    }
+}
+
+/// Cross-reference information shared by all the pages of the website.
+#[derive(Debug, Default)]
+struct SiteIndex {
+    /// The statements for which a page is generated, in database order.
+    pages: Vec<StatementAddress>,
+    /// Index in `pages` of the first statement using the `exthtml*` settings.
+    ext_start: Option<usize>,
+    /// The logical axioms and definitions, in database order.
+    axioms: Vec<StatementAddress>,
+    /// For each logical assertion, the set of indices in `axioms` it depends on.
+    axiom_use: HashMap<StatementAddress, Bitset>,
+    /// For each logical assertion, the theorems whose proofs reference it.
+    referenced_by: HashMap<StatementAddress, Vec<StatementAddress>>,
+    /// The heading comments, with the index in `pages` of the first statement
+    /// following them.
+    headings: Vec<(StatementAddress, usize)>,
+    /// The position of each heading comment in `headings`.
+    heading_index: HashMap<StatementAddress, usize>,
+    /// Index in `pages` of the `mathbox` statement, which starts the user mathboxes.
+    mathbox_start: Option<usize>,
+}
+
+fn is_logical(stmt: &StatementRef<'_>) -> bool {
+    stmt.math_len() > 0 && *stmt.math_at(0) == *PROVABLE_TC
+}
+
+/// Returns the kind of assertion, as displayed in the page titles.
+pub(crate) fn statement_kind(stmt: StatementRef<'_>) -> &'static str {
+    match stmt.statement_type() {
+        StatementType::Provable => "Theorem",
+        _ if !is_logical(&stmt) => "Syntax Definition",
+        _ if stmt.label().starts_with(b"df-") => "Definition",
+        _ => "Axiom",
+    }
+}
+
+impl SiteIndex {
+    fn new(db: &Database) -> Self {
+        let nset = db.name_result();
+        let ext_label = db
+            .typesetting_result()
+            .ext_html_label
+            .as_ref()
+            .map(|(_, label)| label.clone());
+        let mut index = SiteIndex::default();
+        for stmt in db.statements() {
+            let address = stmt.address();
+            if stmt.as_heading_comment().is_some() {
+                index.heading_index.insert(address, index.headings.len());
+                index.headings.push((address, index.pages.len()));
+            }
+            if !stmt.is_assertion() {
+                continue;
+            }
+            if index.ext_start.is_none() && ext_label.as_deref() == Some(stmt.label()) {
+                index.ext_start = Some(index.pages.len());
+            }
+            if index.mathbox_start.is_none() && stmt.label() == b"mathbox" {
+                index.mathbox_start = Some(index.pages.len());
+            }
+            index.pages.push(address);
+            if !is_logical(&stmt) {
+                continue;
+            }
+            let mut usage = Bitset::new();
+            if stmt.statement_type() == StatementType::Axiom {
+                usage.set_bit(index.axioms.len());
+                index.axioms.push(address);
+            } else {
+                for (_, label) in stmt.use_iter() {
+                    let Some(used) = nset.lookup_label(label) else {
+                        continue;
+                    };
+                    if let Some(used_axioms) = index.axiom_use.get(&used.address) {
+                        usage |= used_axioms;
+                        let users = index.referenced_by.entry(used.address).or_default();
+                        if users.last() != Some(&address) {
+                            users.push(address);
+                        }
+                    }
+                }
+            }
+            index.axiom_use.insert(address, usage);
+        }
+        index
+    }
+
+    /// Returns whether the `ix`-th page uses the `exthtml*` settings.
+    fn is_ext(&self, ix: usize) -> bool {
+        self.ext_start.is_some_and(|start| ix >= start)
+    }
+
+    /// Returns the number of pages of the theorem list.
+    fn theorem_list_len(&self) -> usize {
+        self.pages.len().div_ceil(THEOREMS_PER_PAGE).max(1)
+    }
+}
+
+/// Writes the given text, escaping the HTML special characters.
+pub(crate) fn write_escaped(out: &mut impl Write, text: &[u8]) -> io::Result<()> {
+    let mut last = 0;
+    for (i, &c) in text.iter().enumerate() {
+        let escape: &[u8] = match c {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            b'>' => b"&gt;",
+            b'"' => b"&quot;",
+            _ => continue,
+        };
+        out.write_all(&text[last..i])?;
+        out.write_all(escape)?;
+        last = i + 1;
+    }
+    out.write_all(&text[last..])
+}
+
+/// Writes a link to the page of the statement with the given label.
+fn write_link(out: &mut impl Write, label: &[u8]) -> io::Result<()> {
+    write!(out, "<A HREF=\"{0}.html\">{0}</A>", as_str(label))
+}
+
+/// Returns the "Previous" and "Next" navigation links to the given files.
+fn nav_links(prev: Option<String>, next: Option<String>) -> String {
+    let prev = prev.map_or_else(String::new, |prev| {
+        format!("<A HREF=\"{prev}\">&lt; Previous</A>&nbsp;&nbsp;")
+    });
+    let next = next.map_or_else(String::new, |next| {
+        format!("<A HREF=\"{next}\">Next &gt;</A>")
+    });
+    format!("{prev}{next}")
+}
+
+/// Writer for the pages of one variant of the website.
+struct PageWriter<'a> {
+    db: &'a Database,
+    typesetting: &'a TypesettingData,
+    site: &'a SiteIndex,
+    variant: HtmlVariant,
+}
+
+impl PageWriter<'_> {
+    /// Writes a math string, rendering each symbol with its HTML definition.
+    fn write_math<'b>(
+        &self,
+        out: &mut impl Write,
+        tokens: impl IntoIterator<Item = TokenPtr<'b>>,
+    ) -> io::Result<()> {
+        let defs = match self.variant {
+            HtmlVariant::Gif => &self.typesetting.html_defs,
+            HtmlVariant::Unicode => {
+                let font = self.typesetting.html_font.as_ref();
+                write!(
+                    out,
+                    "<SPAN {}>",
+                    font.map_or("CLASS=math", |f| as_str(&f.1))
+                )?;
+                &self.typesetting.alt_html_defs
+            }
+        };
+        for token in tokens {
+            if let Some((_, _, def)) = defs.get(token) {
+                out.write_all(def)?;
+            } else {
+                out.write_all(b"<TT>")?;
+                write_escaped(out, token)?;
+                out.write_all(b"</TT> ")?;
+            }
+        }
+        if self.variant == HtmlVariant::Unicode {
+            out.write_all(b"</SPAN>")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the contents of a comment, interpreting its markup.
+    fn write_markup(&self, out: &mut impl Write, buf: &[u8], span: Span) -> io::Result<()> {
+        let mut parser = CommentParser::new(buf, span);
+        let mut html_mode = false;
+        let mut math = vec![];
+        let mut unescaped = vec![];
+        while let Some(item) = parser.next() {
+            unescaped.clear();
+            match item {
+                CommentItem::Text(span) => {
+                    parser.unescape_text(span, &mut unescaped);
+                    if html_mode {
+                        out.write_all(&unescaped)?;
+                    } else {
+                        write_escaped(out, &unescaped)?;
+                    }
+                }
+                CommentItem::LineBreak(_) => out.write_all(b"\n<P>")?,
+                CommentItem::StartMathMode(_) => math.clear(),
+                CommentItem::MathToken(span) => {
+                    parser.unescape_math(span, &mut unescaped);
+                    math.push(unescaped.clone());
+                }
+                CommentItem::EndMathMode(_) => {
+                    self.write_math(out, math.iter().map(Vec::as_slice))?;
+                }
+                CommentItem::Label(_, span) => {
+                    parser.unescape_label(span, &mut unescaped);
+                    write_link(out, &unescaped)?;
+                }
+                CommentItem::Url(_, span) => {
+                    parser.unescape_label(span, &mut unescaped);
+                    out.write_all(b"<A HREF=\"")?;
+                    write_escaped(out, &unescaped)?;
+                    out.write_all(b"\">")?;
+                    write_escaped(out, &unescaped)?;
+                    out.write_all(b"</A>")?;
+                }
+                CommentItem::StartHtml(_) => html_mode = true,
+                CommentItem::EndHtml(_) => html_mode = false,
+                CommentItem::StartSubscript(_) => out.write_all(b"<SUB><FONT SIZE=\"-1\">")?,
+                CommentItem::EndSubscript(_) => out.write_all(b"</FONT></SUB>")?,
+                CommentItem::StartItalic(_) => out.write_all(b"<I>")?,
+                CommentItem::EndItalic(_) => out.write_all(b"</I>")?,
+                CommentItem::BibTag(span) => {
+                    let tag = span.as_ref(buf);
+                    out.write_all(b"[")?;
+                    if let Some((_, bib)) = &self.typesetting.html_bibliography {
+                        write!(out, "<A HREF=\"{}#{}\">", as_str(bib), as_str(tag))?;
+                        write_escaped(out, tag)?;
+                        out.write_all(b"</A>")?;
+                    } else {
+                        write_escaped(out, tag)?;
+                    }
+                    out.write_all(b"]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the beginning of a page, up to the horizontal rule below the title.
+    ///
+    /// `subtitle` is shown below the database title, `nav` is the HTML content of
+    /// the navigation cell, and `file` is the name of this page, used for the link
+    /// to the other variant.
+    fn write_page_head(
+        &self,
+        out: &mut impl Write,
+        ext: bool,
+        subtitle: &str,
+        nav: &str,
+        file: &str,
+    ) -> io::Result<()> {
+        let ts = self.typesetting;
+        let (title, home) = if ext {
+            (&ts.ext_html_title, &ts.ext_html_home)
+        } else {
+            (&ts.html_title, &ts.html_home)
+        };
+        let title = title.as_ref().map_or("Metamath", |(_, t)| as_str(t));
+        writeln!(
+            out,
+            "<!DOCTYPE HTML PUBLIC \"-//W3C//DTD HTML 4.01 Transitional//EN\"\n\
+             \x20   \"http://www.w3.org/TR/html4/loose.dtd\">\n\
+             <HTML LANG=\"EN-US\">\n<HEAD>\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=utf-8\">\n\
+             <TITLE>{subtitle} - {title}</TITLE>"
+        )?;
+        if let Some((_, css)) = &ts.html_css {
+            writeln!(out, "{}", as_str(css).replace("\\n", "\n"))?;
+        }
+        writeln!(out, "</HEAD>\n<BODY BGCOLOR=\"#FFFFFF\">")?;
+        writeln!(out, "<TABLE BORDER=0 WIDTH=\"100%\"><TR>")?;
+        write!(out, "<TD ALIGN=LEFT VALIGN=TOP WIDTH=\"25%\">")?;
+        if let Some((_, home)) = home {
+            out.write_all(home)?;
+        }
+        writeln!(out, "</TD>")?;
+        writeln!(
+            out,
+            "<TD NOWRAP ALIGN=CENTER ROWSPAN=2><FONT SIZE=\"+3\" COLOR=\"#006633\"><B>{title}</B></FONT>\
+             <BR><FONT SIZE=\"+2\" COLOR=\"#006633\"><B>{subtitle}</B></FONT></TD>"
+        )?;
+        writeln!(
+            out,
+            "<TD NOWRAP ALIGN=RIGHT VALIGN=TOP WIDTH=\"25%\"><FONT SIZE=-1 FACE=sans-serif>{nav}</FONT></TD></TR>"
+        )?;
+        write!(
+            out,
+            "<TR><TD COLSPAN=3 ALIGN=RIGHT><FONT SIZE=-1 FACE=sans-serif>"
+        )?;
+        let other = self.variant.other();
+        writeln!(
+            out,
+            "<A HREF=\"../{}/{file}\">{} version</A></FONT></TD></TR></TABLE>",
+            other.dir_name(ts),
+            other.name(),
+        )?;
+        writeln!(out, "<HR NOSHADE SIZE=1>")
+    }
+
+    /// Writes the page header of the `ix`-th statement, with navigation links.
+    fn write_header(
+        &self,
+        out: &mut impl Write,
+        ix: usize,
+        stmt: StatementRef<'_>,
+    ) -> io::Result<()> {
+        let label = as_str(stmt.label());
+        let page_file = |ix: usize| {
+            let stmt = self.db.statement_by_address(self.site.pages[ix]);
+            format!("{}.html", as_str(stmt.label()))
+        };
+        let prev = ix.checked_sub(1).map(page_file);
+        let next = Some(ix + 1).filter(|&next| next < self.site.pages.len());
+        let ext_url = self
+            .typesetting
+            .html_ext_url
+            .as_ref()
+            .map_or_else(String::new, |(_, url)| {
+                format!("<BR>{}", as_str(url).replace('*', label))
+            });
+        let nav = format!(
+            "{}<BR><A HREF=\"{}#{label}\">Nearby theorems</A>{ext_url}",
+            nav_links(prev, next.map(page_file)),
+            theorem_list_file(ix / THEOREMS_PER_PAGE)
+        );
+        self.write_page_head(
+            out,
+            self.site.is_ext(ix),
+            &format!("{} {label}", statement_kind(stmt)),
+            &nav,
+            &format!("{label}.html"),
+        )
+    }
+
+    /// Writes the hypotheses, assertion and distinct variable conditions.
+    fn write_statement(&self, out: &mut impl Write, stmt: StatementRef<'_>) -> io::Result<()> {
+        let label = as_str(stmt.label());
+        let frame = self.db.scope_result().get(stmt.label());
+        let hyps = self.essential_hyps(stmt);
+        if !hyps.is_empty() {
+            let caption = if hyps.len() == 1 {
+                "Hypothesis"
+            } else {
+                "Hypotheses"
+            };
+            writeln!(
+                out,
+                "<CENTER><TABLE BORDER CELLSPACING=0 BGCOLOR=\"#EEFFFA\" SUMMARY=\"{caption}\">\n\
+                 <CAPTION><B>{caption}</B></CAPTION>\n<TR><TH>Ref</TH><TH>Expression</TH></TR>"
+            )?;
+            for hyp in hyps {
+                let hyp_label = as_str(hyp.label());
+                write!(
+                    out,
+                    "<TR ALIGN=LEFT><TD><A NAME=\"{hyp_label}\">{hyp_label}</A></TD><TD>"
+                )?;
+                self.write_math(out, hyp.math_iter().map(|tk| tk.slice))?;
+                writeln!(out, "</TD></TR>")?;
+            }
+            writeln!(out, "</TABLE></CENTER>")?;
+        }
+        writeln!(
+            out,
+            "<CENTER><TABLE BORDER CELLSPACING=0 BGCOLOR=\"#EEFFFA\" SUMMARY=\"Assertion\">\n\
+             <CAPTION><B>Assertion</B></CAPTION>\n<TR><TH>Ref</TH><TH>Expression</TH></TR>"
+        )?;
+        write!(
+            out,
+            "<TR ALIGN=LEFT><TD><FONT COLOR=\"#006633\"><B>{label}</B></FONT></TD><TD>"
+        )?;
+        self.write_math(out, stmt.math_iter().map(|tk| tk.slice))?;
+        writeln!(out, "</TD></TR>\n</TABLE></CENTER>")?;
+
+        if let Some(frame) = frame.filter(|frame| !frame.mandatory_dv.is_empty()) {
+            let nset = self.db.name_result();
+            write!(out, "<CENTER>Distinct variable groups:")?;
+            for &(v1, v2) in &*frame.mandatory_dv {
+                write!(out, " &nbsp; ")?;
+                self.write_math(out, [nset.atom_name(frame.var_list[v1])])?;
+                write!(out, ",")?;
+                self.write_math(out, [nset.atom_name(frame.var_list[v2])])?;
+            }
+            writeln!(out, "</CENTER>")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the proof table of a theorem, listing its logical steps.
+    fn write_proof(&self, out: &mut impl Write, stmt: StatementRef<'_>) -> io::Result<()> {
+        let label = as_str(stmt.label());
+        let Some(arr) = self.db.get_proof_tree(stmt) else {
+            return writeln!(
+                out,
+                "<CENTER><B><FONT COLOR=\"#FF6600\">The proof of this theorem is incomplete or invalid.</FONT></B></CENTER>"
+            );
+        };
+        let exprs = arr.exprs().expect("proof trees are built with expressions");
+        let indent = arr.indent();
+        let steps = arr.with_logical_steps(self.db, |cur, ix, step, hyps| {
+            (cur, ix, step.address(), hyps)
+        });
+        writeln!(
+            out,
+            "<CENTER><TABLE BORDER CELLSPACING=0 BGCOLOR=\"#EEFFFA\" SUMMARY=\"Proof of theorem\">\n\
+             <CAPTION><B>Proof of Theorem <I>{label}</I></B></CAPTION>\n\
+             <TR><TH>Step</TH><TH>Hyp</TH><TH>Ref</TH><TH>Expression</TH></TR>"
+        )?;
+        for (cur, ix, address, hyps) in steps {
+            let step = self.db.statement_by_address(address);
+            let hyps = hyps.iter().map(usize::to_string).collect::<Vec<_>>();
+            write!(
+                out,
+                "<TR ALIGN=LEFT><TD>{ix}</TD><TD>{}</TD><TD>",
+                hyps.join(", ")
+            )?;
+            if step.is_assertion() {
+                write_link(out, step.label())?;
+            } else {
+                write!(out, "<A HREF=\"#{0}\">{0}</A>", as_str(step.label()))?;
+            }
+            write!(out, "</TD><TD><SPAN CLASS=i>")?;
+            for _ in 0..indent[cur] {
+                write!(out, ". ")?;
+            }
+            write!(out, "{}</SPAN> ", indent[cur] + 1)?;
+            let tokens = exprs[cur].split(|&c| c == b' ').filter(|tk| !tk.is_empty());
+            self.write_math(out, std::iter::once(step.math_at(0).slice).chain(tokens))?;
+            writeln!(out, "</TD></TR>")?;
+        }
+        writeln!(out, "</TABLE></CENTER>")
+    }
+
+    /// Writes the "proved from axioms" and "referenced by" lists.
+    fn write_references(&self, out: &mut impl Write, stmt: StatementRef<'_>) -> io::Result<()> {
+        let address = stmt.address();
+        if stmt.statement_type() == StatementType::Provable {
+            if let Some(usage) = self.site.axiom_use.get(&address) {
+                let (definitions, axioms): (Vec<_>, Vec<_>) = usage
+                    .iter()
+                    .map(|i| self.db.statement_by_address(self.site.axioms[i]).label())
+                    .partition(|label| label.starts_with(b"df-"));
+                for (list, caption) in [
+                    (axioms, "This theorem was proved from axioms:"),
+                    (definitions, "This theorem depends on definitions:"),
+                ] {
+                    if list.is_empty() {
+                        continue;
+                    }
+                    write!(
+                        out,
+                        "<TABLE BORDER=0 WIDTH=\"100%\"><TR><TD><B>{caption}</B>"
+                    )?;
+                    for label in list {
+                        write!(out, " &nbsp;")?;
+                        write_link(out, label)?;
+                    }
+                    writeln!(out, "</TD></TR></TABLE>")?;
+                }
+            }
+        }
+        if let Some(users) = self.site.referenced_by.get(&address) {
+            let kind = if stmt.statement_type() == StatementType::Provable {
+                "theorem"
+            } else {
+                "axiom"
+            };
+            write!(
+                out,
+                "<TABLE BORDER=0 WIDTH=\"100%\"><TR><TD><B>This {kind} is referenced by:</B>"
+            )?;
+            for &user in users {
+                write!(out, " &nbsp;")?;
+                write_link(out, self.db.statement_by_address(user).label())?;
+            }
+            writeln!(out, "</TD></TR></TABLE>")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the essential hypotheses of the given assertion.
+    fn essential_hyps(&self, stmt: StatementRef<'_>) -> Vec<StatementRef<'_>> {
+        let frame = self.db.scope_result().get(stmt.label());
+        frame
+            .iter()
+            .flat_map(|frame| frame.hypotheses.iter())
+            .filter(|hyp| matches!(hyp, Hyp::Essential(..)))
+            .map(|hyp| self.db.statement_by_address(hyp.address()))
+            .collect()
+    }
+
+    /// Writes a row of the theorem list, for the header comment `headings[n]`.
+    fn write_heading_row(&self, out: &mut impl Write, n: usize) -> io::Result<()> {
+        let stmt = self.db.statement_by_address(self.site.headings[n].0);
+        let (Some(heading), StatementType::HeadingComment(level)) =
+            (stmt.as_heading_comment(), stmt.statement_type())
+        else {
+            return Ok(());
+        };
+        let size = match level {
+            HeadingLevel::MajorPart => "+2",
+            HeadingLevel::Section => "+1",
+            _ => "+0",
+        };
+        let buf = &stmt.segment().segment.buffer;
+        write!(
+            out,
+            "<TR BGCOLOR=\"#FFFFF2\"><TD COLSPAN=3 ALIGN=CENTER><A NAME=\"mmhd{n}\"></A>\
+             <FONT SIZE=\"{size}\"><B>"
+        )?;
+        write_escaped(out, heading.header.as_ref(buf))?;
+        write!(out, "</B></FONT><BR><FONT SIZE=-1>")?;
+        self.write_markup(out, buf, heading.content)?;
+        writeln!(out, "</FONT></TD></TR>")
+    }
+
+    /// Writes the two rows of the theorem list for the `ix`-th statement.
+    fn write_theorem_row(&self, out: &mut impl Write, ix: usize) -> io::Result<()> {
+        let stmt = self.db.statement_by_address(self.site.pages[ix]);
+        let label = as_str(stmt.label());
+        let color = if self.site.mathbox_start.is_some_and(|start| ix >= start) {
+            "#FAEEFF"
+        } else {
+            "#EEFFFA"
+        };
+        write!(
+            out,
+            "<TR BGCOLOR=\"{color}\" ALIGN=LEFT><TD>{}</TD>\
+             <TD><A NAME=\"{label}\"></A><A HREF=\"{label}.html\">{label}</A> {}</TD><TD>",
+            statement_kind(stmt),
+            ix + 1,
+        )?;
+        if let Some(comment) = stmt.associated_comment() {
+            let buf = &comment.segment().segment.buffer;
+            self.write_markup(out, buf, comment.comment_contents())?;
+        }
+        write!(
+            out,
+            "</TD></TR>\n<TR BGCOLOR=\"{color}\"><TD COLSPAN=3 ALIGN=CENTER>"
+        )?;
+        let hyps = self.essential_hyps(stmt);
+        for (i, hyp) in hyps.iter().enumerate() {
+            if i > 0 {
+                write!(out, " &nbsp;&nbsp;&amp;&nbsp;&nbsp; ")?;
+            }
+            self.write_math(out, hyp.math_iter().map(|tk| tk.slice))?;
+        }
+        if !hyps.is_empty() {
+            write!(out, " &nbsp;&nbsp;&rArr;&nbsp;&nbsp; ")?;
+        }
+        self.write_math(out, stmt.math_iter().map(|tk| tk.slice))?;
+        writeln!(out, "</TD></TR>")
+    }
+
+    /// Writes the given page of the theorem list, starting from 0.
+    fn write_theorem_list(&self, out: &mut impl Write, page: usize) -> io::Result<()> {
+        let count = self.site.theorem_list_len();
+        let start = page * THEOREMS_PER_PAGE;
+        let end = self.site.pages.len().min(start + THEOREMS_PER_PAGE);
+        let prev = page.checked_sub(1).map(theorem_list_file);
+        let next = Some(page + 1).filter(|&next| next < count);
+        let nav = format!(
+            "{}<BR><A HREF=\"{TOC_FILE}\">Table of Contents</A>",
+            nav_links(prev, next.map(theorem_list_file))
+        );
+        self.write_page_head(
+            out,
+            self.site.is_ext(start),
+            &format!("Theorem List ({}-{end})", (start + 1).min(end)),
+            &nav,
+            &theorem_list_file(page),
+        )?;
+        writeln!(
+            out,
+            "<CENTER><TABLE BORDER CELLSPACING=0 BGCOLOR=\"#EEFFFA\" SUMMARY=\"Theorem List\">\n\
+             <TR><TH>Type</TH><TH>Label</TH><TH>Description</TH></TR>\n\
+             <TR><TH COLSPAN=3>Statement</TH></TR>"
+        )?;
+        // Headings following the last statement are shown on the last page.
+        let last = page + 1 == count;
+        let mut headings = (self.site.headings.iter().enumerate())
+            .filter(|&(_, &(_, next))| next >= start && (next < end || last))
+            .map(|(n, &(_, next))| (n, next))
+            .peekable();
+        for ix in start..end {
+            while let Some((n, _)) = headings.next_if(|&(_, next)| next <= ix) {
+                self.write_heading_row(out, n)?;
+            }
+            if self.site.mathbox_start == Some(ix) {
+                writeln!(
+                    out,
+                    "<TR BGCOLOR=\"#FAEEFF\"><TD COLSPAN=3 ALIGN=CENTER>\
+                     <FONT SIZE=\"+2\"><B>Users' Mathboxes</B></FONT></TD></TR>"
+                )?;
+            }
+            self.write_theorem_row(out, ix)?;
+        }
+        for (n, _) in headings {
+            self.write_heading_row(out, n)?;
+        }
+        writeln!(out, "</TABLE></CENTER>")?;
+        write!(out, "<P><CENTER><B>Page List:</B>")?;
+        for other in 0..count {
+            if other == page {
+                write!(out, " <B>{}</B>", other + 1)?;
+            } else {
+                write!(
+                    out,
+                    " <A HREF=\"{}\">{}</A>",
+                    theorem_list_file(other),
+                    other + 1
+                )?;
+            }
+        }
+        writeln!(out, "</CENTER>\n</BODY></HTML>")
+    }
+
+    /// Writes the page listing the `count` most recent contributions.
+    fn write_recent(&self, out: &mut impl Write, count: usize) -> io::Result<()> {
+        let nav = format!("<A HREF=\"{}\">Theorem List</A>", theorem_list_file(0));
+        self.write_page_head(out, false, "Most Recent Proofs", &nav, RECENT_FILE)?;
+        writeln!(
+            out,
+            "<CENTER><TABLE BORDER CELLSPACING=0 BGCOLOR=\"#EEFFFA\" SUMMARY=\"Most recent proofs\">\n\
+             <TR><TH>Date</TH><TH>Label</TH><TH>Contributed by</TH><TH>Revised by</TH>\
+             <TH>Description</TH></TR>\n\
+             <TR><TH COLSPAN=5>Statement</TH></TR>"
+        )?;
+        for entry in self.db.recent_statements(count) {
+            let label = as_str(entry.stmt.label());
+            write!(
+                out,
+                "<TR ALIGN=LEFT><TD NOWRAP>{}</TD><TD><A HREF=\"{label}.html\">{label}</A></TD><TD>",
+                entry.date
+            )?;
+            if let Some(contributor) = entry.contributor {
+                write_escaped(out, contributor)?;
+            }
+            write!(out, "</TD><TD>")?;
+            if let Some((author, date)) = entry.revised {
+                write_escaped(out, author)?;
+                write!(out, ", {date}")?;
+            }
+            write!(out, "</TD><TD>")?;
+            if let Some(comment) = entry.stmt.associated_comment() {
+                let buf = &comment.segment().segment.buffer;
+                self.write_markup(out, buf, comment.comment_contents())?;
+            }
+            write!(out, "</TD></TR>\n<TR><TD COLSPAN=5 ALIGN=CENTER>")?;
+            self.write_math(out, entry.stmt.math_iter().map(|tk| tk.slice))?;
+            writeln!(out, "</TD></TR>")?;
+        }
+        writeln!(out, "</TABLE></CENTER>\n</BODY></HTML>")
+    }
+
+    /// Writes the table of contents, listing the chapters of the outline.
+    fn write_toc(&self, out: &mut impl Write) -> io::Result<()> {
+        let nav = format!(
+            "<A HREF=\"{}\">Theorem List</A><BR><A HREF=\"{RECENT_FILE}\">Most Recent Proofs</A>",
+            theorem_list_file(0)
+        );
+        self.write_page_head(out, false, "Table of Contents", &nav, TOC_FILE)?;
+        self.write_toc_node(out, self.db.root_outline_node(), &mut false)?;
+        writeln!(out, "</BODY></HTML>")
+    }
+
+    /// Writes the sub-chapters of an outline node as a nested list.
+    /// `in_mathboxes` is set once the first chapter after the `mathbox` statement is met.
+    fn write_toc_node(
+        &self,
+        out: &mut impl Write,
+        node: OutlineNodeRef<'_>,
+        in_mathboxes: &mut bool,
+    ) -> io::Result<()> {
+        let chapters: Vec<_> = node
+            .children_iter()
+            .filter(|child| matches!(child, OutlineNodeRef::Chapter { .. }))
+            .collect();
+        if chapters.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "<UL>")?;
+        for chapter in chapters {
+            let name = chapter.get_name().as_bytes();
+            let heading = (self.site.heading_index)
+                .get(&chapter.get_statement().address())
+                .map(|&n| (n, self.site.headings[n].1));
+            if let (Some(start), Some((_, next))) = (self.site.mathbox_start, heading) {
+                if !*in_mathboxes && next > start {
+                    *in_mathboxes = true;
+                    writeln!(out, "<LI><HR><B>Users' Mathboxes</B></LI>")?;
+                }
+            }
+            write!(out, "<LI>")?;
+            if let Some((n, next)) = heading {
+                let next = next.min(self.site.pages.len().saturating_sub(1));
+                write!(
+                    out,
+                    "<A HREF=\"{}#mmhd{n}\">",
+                    theorem_list_file(next / THEOREMS_PER_PAGE)
+                )?;
+                write_escaped(out, name)?;
+                write!(out, "</A>")?;
+            } else {
+                write_escaped(out, name)?;
+            }
+            writeln!(out)?;
+            self.write_toc_node(out, chapter, in_mathboxes)?;
+            writeln!(out, "</LI>")?;
+        }
+        writeln!(out, "</UL>")
+    }
+
+    /// Writes the page of the `ix`-th statement of the site.
+    fn write_page(&self, out: &mut impl Write, ix: usize) -> io::Result<()> {
+        let stmt = self.db.statement_by_address(self.site.pages[ix]);
+        self.write_header(out, ix, stmt)?;
+        if let Some(comment) = stmt.associated_comment() {
+            write!(out, "<CENTER><B>Description: </B>")?;
+            let buf = &comment.segment().segment.buffer;
+            self.write_markup(out, buf, comment.comment_contents())?;
+            writeln!(out, "</CENTER><BR>")?;
+        }
+        self.write_statement(out, stmt)?;
+        if stmt.statement_type() == StatementType::Provable {
+            self.write_proof(out, stmt)?;
+        }
+        self.write_references(out, stmt)?;
+        if !self.typesetting.html_var_color.is_empty() {
+            write!(out, "<P><CENTER><B>Colors of variables:</B>")?;
+            for (_, color) in &self.typesetting.html_var_color {
+                write!(out, " {}", as_str(color))?;
+            }
+            writeln!(out, "</CENTER>")?;
+        }
+        writeln!(out, "</BODY></HTML>")
+    }
+}
+
+/// Creates the file `name` in `dir`, and fills it using the given function.
+fn write_file(
+    dir: &Path,
+    name: &str,
+    f: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(dir.join(name))?);
+    f(&mut out)?;
+    out.flush()
+}
+
+/// Queues a job generating some pages of the given variant of the site.
+fn queue_job(
+    db: &Database,
+    site: &Arc<SiteIndex>,
+    variant: HtmlVariant,
+    dir: &Arc<PathBuf>,
+    estimate: usize,
+    job: impl FnOnce(&PageWriter<'_>, &Path) -> io::Result<()> + Send + 'static,
+) -> Promise<io::Result<()>> {
+    let exec = &db.parse_result().exec;
+    let db = db.clone();
+    let site = site.clone();
+    let dir = dir.clone();
+    exec.exec(estimate, move || {
+        let writer = PageWriter {
+            db: &db,
+            typesetting: db.typesetting_result(),
+            site: &site,
+            variant,
+        };
+        job(&writer, &dir)
+    })
+}
+
+impl Database {
+    /// Writes a static HTML website for this database into the given directory,
+    /// in both GIF and Unicode variants.
+    ///
+    /// The website has one page per `$a` and `$p` statement, the theorem list
+    /// pages `mmtheorems*.html`, a table of contents, and the list of the most
+    /// recent contributions `mmrecent.html`, which lists `recent_count` statements
+    /// (see [`crate::recent::RECENT_COUNT`] for the usual value).
+    /// Pages are generated in parallel, using the database's executor.
+    /// The scope, typesetting and outline passes must have been run beforehand.
+    pub fn write_html_site(
+        &self,
+        dir: impl AsRef<Path>,
+        recent_count: usize,
+    ) -> Result<(), io::Error> {
+        time(&self.options.clone(), "html", || {
+            let site = Arc::new(SiteIndex::new(self));
+            let mut promises = vec![];
+            for variant in [HtmlVariant::Gif, HtmlVariant::Unicode] {
+                let dir = dir
+                    .as_ref()
+                    .join(variant.dir_name(self.typesetting_result()));
+                fs::create_dir_all(&dir)?;
+                let dir = Arc::new(dir);
+                for start in (0..site.pages.len()).step_by(PAGES_PER_JOB) {
+                    let end = site.pages.len().min(start + PAGES_PER_JOB);
+                    promises.push(queue_job(
+                        self,
+                        &site,
+                        variant,
+                        &dir,
+                        PAGES_PER_JOB,
+                        move |writer, dir| {
+                            for ix in start..end {
+                                let stmt = writer.db.statement_by_address(writer.site.pages[ix]);
+                                let name = format!("{}.html", as_str(stmt.label()));
+                                write_file(dir, &name, |out| writer.write_page(out, ix))?;
+                            }
+                            Ok(())
+                        },
+                    ));
+                }
+                for page in 0..site.theorem_list_len() {
+                    promises.push(queue_job(
+                        self,
+                        &site,
+                        variant,
+                        &dir,
+                        THEOREMS_PER_PAGE,
+                        move |writer, dir| {
+                            write_file(dir, &theorem_list_file(page), |out| {
+                                writer.write_theorem_list(out, page)
+                            })
+                        },
+                    ));
+                }
+                promises.push(queue_job(
+                    self,
+                    &site,
+                    variant,
+                    &dir,
+                    THEOREMS_PER_PAGE,
+                    |writer, dir| write_file(dir, TOC_FILE, |out| writer.write_toc(out)),
+                ));
+                promises.push(queue_job(
+                    self,
+                    &site,
+                    variant,
+                    &dir,
+                    recent_count,
+                    move |writer, dir| {
+                        write_file(dir, RECENT_FILE, |out| {
+                            writer.write_recent(out, recent_count)
+                        })
+                    },
+                ));
+            }
+            promises.into_iter().try_for_each(Promise::wait)
+        })
+    }
+}
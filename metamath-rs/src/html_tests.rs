@@ -0,0 +1,195 @@
+use crate::grammar_tests::mkdb;
+use crate::recent::RECENT_COUNT;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HTML_DB: &[u8] = b"$(
+$t
+htmldef \"wff\" as '<I>wff</I> ';
+htmldef \"|-\" as '&#8866; ';
+htmldef \"(\" as '( ';
+htmldef \")\" as ') ';
+htmldef \"->\" as '&rarr; ';
+htmldef \"ph\" as '<I>&phi;</I>';
+htmldef \"ps\" as '<I>&psi;</I>';
+althtmldef \"->\" as '&#8594; ';
+htmltitle \"Test Proof Explorer\";
+$)
+$c wff |- ( ) -> $.
+$v ph ps $.
+wph $f wff ph $.
+wps $f wff ps $.
+$( Define implication, see ~ http://example.com/?a=1&b=\"<i>\" . $)
+wi $a wff ( ph -> ps ) $.
+$(
+####
+  Logic
+####
+$)
+$(
+#*#*#*#*
+  Axioms
+#*#*#*#*
+$)
+$( Axiom _Simp_. (Contributed by A. Author, 1-Jan-2020.) $)
+ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+${
+  min $e |- ph $.
+  maj $e |- ( ph -> ps ) $.
+  $( Rule of modus ponens.  (Contributed by A. Author, 1-Jan-2020.)
+     (Revised by B. Reviser, 2-Feb-2021.) $)
+  ax-mp $a |- ps $.
+$}
+$(
+#*#*#*#*
+  Theorems
+#*#*#*#*
+$)
+$(
+=-=-=-=-
+  Inference
+=-=-=-=-
+$)
+${
+  a1i.1 $e |- ph $.
+  $( Inference adding an antecedent, using ~ ax-1 . $)
+  a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+$}
+";
+
+fn write_site(name: &str) -> PathBuf {
+    let mut db = mkdb(HTML_DB);
+    db.scope_pass();
+    db.typesetting_pass();
+    db.outline_pass();
+    let dir = std::env::temp_dir().join(format!("metamath-rs-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    db.write_html_site(&dir, RECENT_COUNT).unwrap();
+    dir
+}
+
+fn read(dir: &Path, file: &str) -> String {
+    fs::read_to_string(dir.join(file)).unwrap()
+}
+
+#[test]
+fn test_html_site() {
+    let dir = write_site("html-site");
+    for variant in ["mpegif", "mpeuni"] {
+        let mut files = (fs::read_dir(dir.join(variant)).unwrap())
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "a1i.html",
+                "ax-1.html",
+                "ax-mp.html",
+                "mmrecent.html",
+                "mmtheorems.html",
+                "mmtoc.html",
+                "wi.html"
+            ]
+        );
+    }
+
+    let page = read(&dir, "mpegif/a1i.html");
+    assert!(page.contains("<TITLE>Theorem a1i - Test Proof Explorer</TITLE>"));
+    assert!(page.contains(
+        "<A HREF=\"ax-mp.html\">&lt; Previous</A>&nbsp;&nbsp;\
+         <BR><A HREF=\"mmtheorems.html#a1i\">Nearby theorems</A>"
+    ));
+    assert!(page.contains("<A HREF=\"../mpeuni/a1i.html\">Unicode version</A>"));
+    assert!(page.contains(
+        "<CENTER><B>Description: </B> Inference adding an antecedent, \
+         using <A HREF=\"ax-1.html\">ax-1</A>. </CENTER>"
+    ));
+    assert!(page.contains(
+        "<TR ALIGN=LEFT><TD><A NAME=\"a1i.1\">a1i.1</A></TD><TD>&#8866; <I>&phi;</I></TD></TR>"
+    ));
+    assert!(page.contains(
+        "<TR ALIGN=LEFT><TD><FONT COLOR=\"#006633\"><B>a1i</B></FONT></TD>\
+         <TD>&#8866; ( <I>&psi;</I>&rarr; <I>&phi;</I>) </TD></TR>"
+    ));
+    assert!(page.contains(
+        "<TR ALIGN=LEFT><TD>3</TD><TD>1, 2</TD><TD><A HREF=\"ax-mp.html\">ax-mp</A></TD>\
+         <TD><SPAN CLASS=i>1</SPAN> &#8866; ( <I>&psi;</I>&rarr; <I>&phi;</I>) </TD></TR>"
+    ));
+    assert!(page.contains(
+        "<B>This theorem was proved from axioms:</B> \
+         &nbsp;<A HREF=\"ax-1.html\">ax-1</A> &nbsp;<A HREF=\"ax-mp.html\">ax-mp</A>"
+    ));
+
+    // The Unicode variant uses the `althtmldef` definitions
+    let page = read(&dir, "mpeuni/a1i.html");
+    assert!(page.contains(
+        "<SPAN CLASS=math><TT>|-</TT> <TT>(</TT> <TT>ps</TT> &#8594; <TT>ph</TT> <TT>)</TT> </SPAN>"
+    ));
+    assert!(page.contains("<A HREF=\"../mpegif/a1i.html\">GIF version</A>"));
+
+    let recent = read(&dir, "mpegif/mmrecent.html");
+    assert!(recent.contains(
+        "<TR ALIGN=LEFT><TD NOWRAP>1-Jan-2020</TD><TD><A HREF=\"ax-1.html\">ax-1</A></TD>\
+         <TD>A. Author</TD><TD></TD><TD> Axiom <I>Simp</I>."
+    ));
+    assert!(recent.contains(
+        "<TR ALIGN=LEFT><TD NOWRAP>2-Feb-2021</TD><TD><A HREF=\"ax-mp.html\">ax-mp</A></TD>\
+         <TD>A. Author</TD><TD>B. Reviser, 2-Feb-2021</TD><TD> Rule of modus ponens."
+    ));
+    // Most recent first
+    assert!(recent.find("ax-mp.html").unwrap() < recent.find("ax-1.html").unwrap());
+
+    // URLs are escaped, also within the link target
+    let page = read(&dir, "mpegif/wi.html");
+    assert!(page.contains(
+        "see <A HREF=\"http://example.com/?a=1&amp;b=&quot;&lt;i&gt;&quot;\">\
+         http://example.com/?a=1&amp;b=&quot;&lt;i&gt;&quot;</A>"
+    ));
+
+    // Axioms list the theorems referencing them
+    let page = read(&dir, "mpegif/ax-1.html");
+    assert!(page.contains("<B>This axiom is referenced by:</B> &nbsp;<A HREF=\"a1i.html\">a1i</A>"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_theorem_list_and_toc() {
+    let dir = write_site("html-toc");
+    let list = read(&dir, "mpegif/mmtheorems.html");
+    assert!(list.contains("Theorem List (1-4)"));
+    assert!(list.contains("<B>Page List:</B> <B>1</B>"));
+    // Statements are numbered in database order, each heading before the first statement it covers
+    let rows = [
+        "<A NAME=\"wi\"></A><A HREF=\"wi.html\">wi</A> 1</TD>",
+        "<A NAME=\"mmhd0\"></A><FONT SIZE=\"+2\"><B>Logic</B>",
+        "<A NAME=\"mmhd1\"></A><FONT SIZE=\"+1\"><B>Axioms</B>",
+        "<A HREF=\"ax-1.html\">ax-1</A> 2</TD>",
+        "<A HREF=\"ax-mp.html\">ax-mp</A> 3</TD>",
+        "<A NAME=\"mmhd2\"></A><FONT SIZE=\"+1\"><B>Theorems</B>",
+        "<A NAME=\"mmhd3\"></A><FONT SIZE=\"+0\"><B>Inference</B>",
+        "<A HREF=\"a1i.html\">a1i</A> 4</TD>",
+    ];
+    let positions = rows.map(|row| list.find(row).unwrap_or_else(|| panic!("missing {row}")));
+    assert!(positions.is_sorted());
+    assert!(list.contains("&nbsp;&nbsp;&amp;&nbsp;&nbsp;"));
+
+    let toc = read(&dir, "mpegif/mmtoc.html");
+    assert!(toc.contains(
+        "<UL>\n\
+         <LI><A HREF=\"mmtheorems.html#mmhd0\">Logic</A>\n\
+         <UL>\n\
+         <LI><A HREF=\"mmtheorems.html#mmhd1\">Axioms</A>\n\
+         </LI>\n\
+         <LI><A HREF=\"mmtheorems.html#mmhd2\">Theorems</A>\n\
+         <UL>\n\
+         <LI><A HREF=\"mmtheorems.html#mmhd3\">Inference</A>\n\
+         </LI>\n\
+         </UL>\n\
+         </LI>\n\
+         </UL>\n\
+         </LI>\n\
+         </UL>\n"
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,181 @@
+//! Export of the whole database as JSON, for tools which do not parse Metamath.
+//!
+//! The database is written as an array with one object per `$c`, `$v`, `$f`,
+//! `$e`, `$d`, `$a`, `$p`, `${` and `$}` statement, in database order, one per line,
+//! so that it can be written and read in a streaming fashion.
+//! Each object has the following fields:
+//!
+//! - `type`: the kind of statement, like `axiom` or `provable`,
+//! - `label`: the label, for the labelled statements,
+//! - `math`: the list of math tokens, starting with the typecode,
+//! - `formula`: the parsed formula, if the grammar is available. Variables are
+//!   written as the labels of their floating hypotheses, and other nodes as an
+//!   array with the label of the syntax axiom followed by its arguments, in the
+//!   order of its hypotheses, like `["wi", "wph", ["wn", "wps"]]`,
+//! - `outline`: the number of the chapter the statement is in, like `1.2.3`.
+//!
+//! Assertions also have the following fields:
+//!
+//! - `frame`: the labels of the mandatory hypotheses, in order, and the pairs
+//!   of variables with mandatory `$d` conditions,
+//! - `comment`: the text of their comment, if any,
+//! - `proof`: for theorems, the list of their logical steps, each with its
+//!   1-based step number, the numbers of its hypotheses, the label of the
+//!   assertion it applies, and its expression. It is `null` if the proof is
+//!   incomplete or invalid.
+
+use crate::database::time;
+use crate::proof::ProofTreeArray;
+use crate::util::write_json_string;
+use crate::{Database, StatementRef, StatementType};
+use std::io;
+
+/// Returns the name of a statement type in the JSON export,
+/// or `None` if the statement is not exported.
+const fn type_name(stype: StatementType) -> Option<&'static str> {
+    Some(match stype {
+        StatementType::Constant => "constant",
+        StatementType::Variable => "variable",
+        StatementType::Floating => "floating",
+        StatementType::Essential => "essential",
+        StatementType::Disjoint => "disjoint",
+        StatementType::Axiom => "axiom",
+        StatementType::Provable => "provable",
+        StatementType::OpenGroup => "open_group",
+        StatementType::CloseGroup => "close_group",
+        _ => return None,
+    })
+}
+
+/// Writes a list of byte strings as a JSON array.
+fn write_json_array<'a>(
+    out: &mut impl io::Write,
+    items: impl IntoIterator<Item = &'a [u8]>,
+) -> io::Result<()> {
+    write!(out, "[")?;
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(out, ", ")?;
+        }
+        write_json_string(out, item)?;
+    }
+    write!(out, "]")
+}
+
+impl Database {
+    /// Writes a syntax tree as nested JSON arrays.
+    fn write_json_formula(
+        &self,
+        out: &mut impl io::Write,
+        arr: &ProofTreeArray,
+        ix: usize,
+    ) -> io::Result<()> {
+        let tree = &arr.trees[ix];
+        let label = self.statement_by_address(tree.address).label();
+        if tree.children.is_empty() && !self.statement_by_address(tree.address).is_assertion() {
+            return write_json_string(out, label);
+        }
+        write!(out, "[")?;
+        write_json_string(out, label)?;
+        for &child in &tree.children {
+            write!(out, ", ")?;
+            self.write_json_formula(out, arr, child)?;
+        }
+        write!(out, "]")
+    }
+
+    /// Writes the frame, comment and proof of an assertion.
+    fn write_json_assertion(
+        &self,
+        out: &mut impl io::Write,
+        stmt: StatementRef<'_>,
+    ) -> io::Result<()> {
+        let nset = self.name_result();
+        if let Some(frame) = self.scope_result().get(stmt.label()) {
+            write!(out, ", \"frame\": {{\"hypotheses\": ")?;
+            write_json_array(
+                out,
+                (frame.hypotheses.iter())
+                    .map(|hyp| self.statement_by_address(hyp.address()).label()),
+            )?;
+            write!(out, ", \"disjoint\": [")?;
+            for (i, &pair) in frame.mandatory_dv.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write_json_array(
+                    out,
+                    <[_; 2]>::from(pair).map(|var| nset.atom_name(frame.var_list[var])),
+                )?;
+            }
+            write!(out, "]}}")?;
+        }
+        if let Some(comment) = stmt.associated_comment() {
+            let buf = &comment.segment().segment.buffer;
+            write!(out, ", \"comment\": ")?;
+            write_json_string(out, comment.comment_contents().as_ref(buf).trim_ascii())?;
+        }
+        if stmt.statement_type() != StatementType::Provable {
+            return Ok(());
+        }
+        write!(out, ", \"proof\": ")?;
+        let Some(arr) = self.get_proof_tree(stmt) else {
+            return write!(out, "null");
+        };
+        let exprs = arr.exprs().expect("proof trees are built with expressions");
+        let steps =
+            arr.with_logical_steps(self, |cur, ix, step, hyps| (cur, ix, step.address(), hyps));
+        write!(out, "[")?;
+        for (i, (cur, ix, address, hyps)) in steps.into_iter().enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            let step = self.statement_by_address(address);
+            write!(out, "{{\"step\": {ix}, \"hyps\": {hyps:?}, \"label\": ")?;
+            write_json_string(out, step.label())?;
+            write!(out, ", \"expr\": ")?;
+            write_json_string(out, &[step.math_at(0).slice, &exprs[cur]].concat())?;
+            write!(out, "}}")?;
+        }
+        write!(out, "]")
+    }
+
+    /// Writes all the statements of the database as JSON, in the format
+    /// described in [`crate::json`]. The parsed formulas are only written if
+    /// [`Database::stmt_parse_pass`] was run.
+    ///
+    /// Requires: [`Database::scope_pass`], [`Database::outline_pass`]
+    pub fn write_database_json(&self, out: &mut impl io::Write) -> io::Result<()> {
+        time(&self.options.clone(), "database_json", || {
+            let formulas = self.try_stmt_parse_result();
+            writeln!(out, "[")?;
+            let mut first = true;
+            for stmt in self.statements() {
+                let Some(stype) = type_name(stmt.statement_type()) else {
+                    continue;
+                };
+                let sep = if first { "" } else { ",\n" };
+                first = false;
+                write!(out, "{sep}{{\"type\": \"{stype}\"")?;
+                if !stmt.label().is_empty() {
+                    write!(out, ", \"label\": ")?;
+                    write_json_string(out, stmt.label())?;
+                }
+                write!(out, ", \"math\": ")?;
+                write_json_array(out, stmt.math_iter().map(|token| token.slice))?;
+                if let Some(formula) = formulas.and_then(|formulas| formulas.get_formula(&stmt)) {
+                    let arr = self.get_syntax_proof_tree(formula);
+                    write!(out, ", \"formula\": ")?;
+                    self.write_json_formula(out, &arr, arr.trees.len() - 1)?;
+                }
+                let number = self.get_outline_node(stmt).get_chapter_number();
+                write!(out, ", \"outline\": \"{number}\"")?;
+                if stmt.is_assertion() {
+                    self.write_json_assertion(out, stmt)?;
+                }
+                write!(out, "}}")?;
+            }
+            writeln!(out, "\n]")
+        })
+    }
+}
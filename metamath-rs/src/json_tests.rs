@@ -0,0 +1,79 @@
+use crate::grammar_tests::mkdb;
+
+const JSON_DB: &[u8] = b"
+  $( $j syntax 'setvar'; syntax 'wff'; syntax '|-' as 'wff'; $)
+  $c ( ) -> A. wff setvar |- $.
+  $v ph ps x $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  vx $f setvar x $.
+  wi $a wff ( ph -> ps ) $.
+  wal $a wff A. x ph $.
+  $(
+####
+  Part A
+####
+  $)
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    $( Rule of \"modus ponens\". $)
+    ax-mp $a |- ps $.
+  $}
+  ${
+    $d x ph $.
+    $( Axiom of quantifying a wff not containing ` x ` . $)
+    ax-5 $a |- ( ph -> A. x ph ) $.
+  $}
+  ${
+    $d x ph $.
+    a5i.1 $e |- ph $.
+    a5i $p |- A. x ph $= wph wph vx wal a5i.1 wph vx ax-5 ax-mp $.
+  $}
+";
+
+fn export() -> Vec<String> {
+    let mut db = mkdb(JSON_DB);
+    db.scope_pass();
+    db.stmt_parse_pass();
+    db.outline_pass();
+    let mut out = vec![];
+    db.write_database_json(&mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| line.trim_end_matches(',').to_owned())
+        .collect()
+}
+
+#[test]
+fn test_json_export() {
+    let lines = export();
+    assert_eq!(lines.len(), 23);
+    assert_eq!(lines[0], "[");
+    assert_eq!(lines[22], "]");
+    assert_eq!(
+        lines[1],
+        r#"{"type": "constant", "math": ["(", ")", "->", "A.", "wff", "setvar", "|-"], "outline": ""}"#
+    );
+    assert_eq!(
+        lines[3],
+        r#"{"type": "floating", "label": "wph", "math": ["wff", "ph"], "outline": ""}"#
+    );
+    assert_eq!(
+        lines[6],
+        r#"{"type": "axiom", "label": "wi", "math": ["wff", "(", "ph", "->", "ps", ")"], "formula": ["wi", "wph", "wps"], "outline": "", "frame": {"hypotheses": ["wph", "wps"], "disjoint": []}}"#
+    );
+    assert_eq!(
+        lines[11],
+        r#"{"type": "axiom", "label": "ax-mp", "math": ["|-", "ps"], "formula": "wps", "outline": "1", "frame": {"hypotheses": ["wph", "wps", "min", "maj"], "disjoint": []}, "comment": "Rule of \"modus ponens\"."}"#
+    );
+    assert_eq!(
+        lines[15],
+        r#"{"type": "axiom", "label": "ax-5", "math": ["|-", "(", "ph", "->", "A.", "x", "ph", ")"], "formula": ["wi", "wph", ["wal", "wph", "vx"]], "outline": "1", "frame": {"hypotheses": ["wph", "vx"], "disjoint": [["x", "ph"]]}, "comment": "Axiom of quantifying a wff not containing ` x ` ."}"#
+    );
+    assert_eq!(
+        lines[20],
+        r#"{"type": "provable", "label": "a5i", "math": ["|-", "A.", "x", "ph"], "formula": ["wal", "wph", "vx"], "outline": "1", "frame": {"hypotheses": ["wph", "vx", "a5i.1"], "disjoint": [["x", "ph"]]}, "proof": [{"step": 1, "hyps": [], "label": "a5i.1", "expr": "|- ph"}, {"step": 2, "hyps": [], "label": "ax-5", "expr": "|- ( ph -> A. x ph )"}, {"step": 3, "hyps": [1, 2], "label": "ax-mp", "expr": "|- A. x ph"}]}"#
+    );
+}
@@ -0,0 +1,325 @@
+//! Rendering of statements to LaTeX.
+//!
+//! This uses the `latexdef` definitions of the `$t` comment (see
+//! [`crate::typesetting`]) to typeset statements, their hypotheses and their
+//! proof tables, similarly to the `SHOW STATEMENT / TEX` and
+//! `SHOW STATEMENT / OLD_TEX` commands of
+//! [`metamath.exe`](https://github.com/metamath/metamath-exe).
+//!
+//! Tokens without a `latexdef` are rendered in a typewriter font,
+//! and reported with a [`Diagnostic::MissingMarkupDef`] diagnostic.
+
+use crate::comment_parser::{CommentItem, CommentParser};
+use crate::database::time;
+use crate::diag::Diagnostic;
+use crate::html::statement_kind;
+use crate::scopeck::Hyp;
+use crate::statement::{StatementAddress, TokenPtr};
+use crate::typesetting::TypesettingData;
+use crate::util::HashSet;
+use crate::{Database, Span, StatementRef, StatementType};
+use std::io::{self, Write};
+
+/// The style of the generated LaTeX.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LatexStyle {
+    /// Uses the `amsmath` and `longtable` packages,
+    /// like `metamath.exe`'s `SHOW STATEMENT / TEX`.
+    #[default]
+    Modern,
+    /// Only uses plain LaTeX constructs,
+    /// like `metamath.exe`'s `SHOW STATEMENT / OLD_TEX`.
+    Old,
+}
+
+/// Writes the given text, escaping the LaTeX special characters.
+fn write_escaped(out: &mut impl Write, text: &[u8]) -> io::Result<()> {
+    for &c in text {
+        match c {
+            b'\\' => out.write_all(b"\\textbackslash{}")?,
+            b'~' => out.write_all(b"\\textasciitilde{}")?,
+            b'^' => out.write_all(b"\\textasciicircum{}")?,
+            b'{' | b'}' | b'$' | b'&' | b'#' | b'_' | b'%' => out.write_all(&[b'\\', c])?,
+            _ => out.write_all(&[c])?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a label in a typewriter font.
+fn write_label(out: &mut impl Write, label: &[u8]) -> io::Result<()> {
+    out.write_all(b"\\texttt{")?;
+    write_escaped(out, label)?;
+    out.write_all(b"}")
+}
+
+/// Renders statements to LaTeX, collecting the tokens lacking a `latexdef`.
+struct LatexWriter<'a> {
+    db: &'a Database,
+    typesetting: &'a TypesettingData,
+    style: LatexStyle,
+    /// Tokens already reported as missing a definition.
+    missing: HashSet<Vec<u8>>,
+    diags: Vec<(StatementAddress, Diagnostic)>,
+}
+
+impl<'a> LatexWriter<'a> {
+    fn new(db: &'a Database, style: LatexStyle) -> Self {
+        Self {
+            db,
+            typesetting: db.typesetting_result(),
+            style,
+            missing: HashSet::default(),
+            diags: vec![],
+        }
+    }
+
+    /// Writes a math string, which must be within math mode.
+    fn write_math<'b>(
+        &mut self,
+        out: &mut impl Write,
+        tokens: impl IntoIterator<Item = TokenPtr<'b>>,
+    ) -> io::Result<()> {
+        for token in tokens {
+            if let Some((_, _, def)) = self.typesetting.latex_defs.get(token) {
+                out.write_all(def)?;
+            } else {
+                out.write_all(b"\\mbox{\\tt ")?;
+                write_escaped(out, token)?;
+                out.write_all(b"}")?;
+                self.report_missing(token);
+            }
+            out.write_all(b" ")?;
+        }
+        Ok(())
+    }
+
+    /// Reports a token lacking a `latexdef`, at its declaration.
+    fn report_missing(&mut self, token: TokenPtr<'_>) {
+        if !self.missing.insert(token.to_vec()) {
+            return;
+        }
+        if let Some(lookup) = self.db.name_result().lookup_symbol(token) {
+            let decl = self.db.statement_by_address(lookup.address.statement);
+            let span = decl.math_span(lookup.address.token_index);
+            self.diags.push((
+                decl.address(),
+                Diagnostic::MissingMarkupDef([false, false, true], span),
+            ));
+        }
+    }
+
+    /// Writes the contents of a comment, interpreting its markup.
+    fn write_markup(&mut self, out: &mut impl Write, buf: &[u8], span: Span) -> io::Result<()> {
+        let mut parser = CommentParser::new(buf, span);
+        let mut html_mode = false;
+        let mut math = vec![];
+        let mut unescaped = vec![];
+        while let Some(item) = parser.next() {
+            unescaped.clear();
+            match item {
+                // HTML markup cannot be rendered in LaTeX, so it is dropped.
+                CommentItem::Text(_) if html_mode => {}
+                CommentItem::Text(span) => {
+                    parser.unescape_text(span, &mut unescaped);
+                    write_escaped(out, &unescaped)?;
+                }
+                CommentItem::LineBreak(_) => out.write_all(b"\n\n")?,
+                CommentItem::StartMathMode(_) => math.clear(),
+                CommentItem::MathToken(span) => {
+                    parser.unescape_math(span, &mut unescaped);
+                    math.push(unescaped.clone());
+                }
+                CommentItem::EndMathMode(_) => {
+                    out.write_all(b"$")?;
+                    self.write_math(out, math.iter().map(Vec::as_slice))?;
+                    out.write_all(b"$")?;
+                }
+                CommentItem::Label(_, span) | CommentItem::Url(_, span) => {
+                    parser.unescape_label(span, &mut unescaped);
+                    write_label(out, &unescaped)?;
+                }
+                CommentItem::StartHtml(_) => html_mode = true,
+                CommentItem::EndHtml(_) => html_mode = false,
+                CommentItem::StartSubscript(_) => out.write_all(b"\\textsubscript{")?,
+                CommentItem::StartItalic(_) => out.write_all(b"\\textit{")?,
+                CommentItem::EndSubscript(_) | CommentItem::EndItalic(_) => {
+                    out.write_all(b"}")?;
+                }
+                CommentItem::BibTag(span) => {
+                    out.write_all(b"[")?;
+                    write_escaped(out, span.as_ref(buf))?;
+                    out.write_all(b"]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the document preamble.
+    fn write_preamble(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "\\documentclass{{article}}\n\
+             \\usepackage{{graphicx}} % packages used by the latexdef definitions\n\
+             \\usepackage{{amssymb}}"
+        )?;
+        if self.style == LatexStyle::Modern {
+            writeln!(out, "\\usepackage{{amsmath}}\n\\usepackage{{longtable}}")?;
+        }
+        writeln!(out, "\\raggedbottom\n\\raggedright\n\\begin{{document}}")
+    }
+
+    /// Writes the statement, its description, hypotheses, and proof if any.
+    fn write_statement(&mut self, out: &mut impl Write, stmt: StatementRef<'_>) -> io::Result<()> {
+        let kind = statement_kind(stmt);
+        match self.style {
+            LatexStyle::Modern => write!(out, "\n\\subsection*{{{kind} ")?,
+            LatexStyle::Old => write!(out, "\n\\vspace{{1ex}}\n\\noindent {{\\bf {kind}}} ")?,
+        }
+        write_label(out, stmt.label())?;
+        match self.style {
+            LatexStyle::Modern => writeln!(out, "}}")?,
+            LatexStyle::Old => writeln!(out, "\n")?,
+        }
+        if let Some(comment) = stmt.associated_comment() {
+            let buf = &comment.segment().segment.buffer;
+            self.write_markup(out, buf, comment.comment_contents())?;
+            writeln!(out, "\n")?;
+        }
+
+        let frame = self.db.scope_result().get(stmt.label());
+        let hyps = frame
+            .iter()
+            .flat_map(|frame| frame.hypotheses.iter())
+            .filter(|hyp| matches!(hyp, Hyp::Essential(..)))
+            .map(|hyp| self.db.statement_by_address(hyp.address()));
+        let lines = hyps.chain(std::iter::once(stmt)).collect::<Vec<_>>();
+        match self.style {
+            LatexStyle::Modern => {
+                writeln!(out, "\\begin{{align*}}")?;
+                for (i, line) in lines.iter().enumerate() {
+                    write!(out, "\\mbox{{")?;
+                    write_label(out, line.label())?;
+                    write!(out, "}} &: \\ ")?;
+                    self.write_math(out, line.math_iter().map(|tk| tk.slice))?;
+                    writeln!(out, "{}", if i + 1 < lines.len() { "\\\\" } else { "" })?;
+                }
+                writeln!(out, "\\end{{align*}}")?;
+            }
+            LatexStyle::Old => {
+                for line in lines {
+                    write!(out, "\\noindent ")?;
+                    write_label(out, line.label())?;
+                    write!(out, " \\quad $")?;
+                    self.write_math(out, line.math_iter().map(|tk| tk.slice))?;
+                    writeln!(out, "$\n")?;
+                }
+            }
+        }
+
+        if let Some(frame) = frame.filter(|frame| !frame.mandatory_dv.is_empty()) {
+            let nset = self.db.name_result();
+            write!(out, "\\noindent Distinct variable groups:")?;
+            for &(v1, v2) in &*frame.mandatory_dv {
+                write!(out, " \\quad $")?;
+                self.write_math(out, [nset.atom_name(frame.var_list[v1])])?;
+                write!(out, ", ")?;
+                self.write_math(out, [nset.atom_name(frame.var_list[v2])])?;
+                write!(out, "$")?;
+            }
+            writeln!(out, "\n")?;
+        }
+
+        if stmt.statement_type() == StatementType::Provable {
+            self.write_proof(out, stmt)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the proof table of a theorem, listing its logical steps.
+    fn write_proof(&mut self, out: &mut impl Write, stmt: StatementRef<'_>) -> io::Result<()> {
+        let Some(arr) = self.db.get_proof_tree(stmt) else {
+            return writeln!(
+                out,
+                "\\noindent The proof of this theorem is incomplete or invalid.\n"
+            );
+        };
+        let exprs = arr.exprs().expect("proof trees are built with expressions");
+        let indent = arr.indent();
+        let steps = arr.with_logical_steps(self.db, |cur, ix, step, hyps| {
+            (cur, ix, step.address(), hyps)
+        });
+        let env = match self.style {
+            LatexStyle::Modern => "longtable",
+            LatexStyle::Old => "tabular",
+        };
+        write!(out, "\\noindent Proof of {} ", statement_kind(stmt))?;
+        write_label(out, stmt.label())?;
+        writeln!(
+            out,
+            ":\n\n\\noindent \\begin{{{env}}}{{rlll}}\n\
+             Step & Hyp & Ref & Expression \\\\\n\\hline"
+        )?;
+        for (cur, ix, address, hyps) in steps {
+            let step = self.db.statement_by_address(address);
+            let hyps = hyps.iter().map(usize::to_string).collect::<Vec<_>>();
+            write!(out, "{ix} & {} & ", hyps.join(", "))?;
+            write_label(out, step.label())?;
+            write!(out, " & ")?;
+            for _ in 0..indent[cur] {
+                write!(out, ". ")?;
+            }
+            write!(out, "{} \\quad $", indent[cur] + 1)?;
+            let tokens = exprs[cur].split(|&c| c == b' ').filter(|tk| !tk.is_empty());
+            self.write_math(out, std::iter::once(step.math_at(0).slice).chain(tokens))?;
+            writeln!(out, "$ \\\\")?;
+        }
+        writeln!(out, "\\end{{{env}}}\n")
+    }
+}
+
+impl Database {
+    /// Renders the given statement to LaTeX, with its description, its hypotheses,
+    /// and its proof table if it is a theorem.
+    ///
+    /// The output is meant to be included in a document using the preamble
+    /// written by [`Database::write_latex`]. Returns a [`Diagnostic::MissingMarkupDef`]
+    /// diagnostic for each token lacking a `latexdef`.
+    ///
+    /// Requires: [`Database::scope_pass`], [`Database::typesetting_pass`]
+    pub fn render_latex(
+        &self,
+        stmt: StatementRef<'_>,
+        style: LatexStyle,
+        out: &mut impl Write,
+    ) -> Result<Vec<(StatementAddress, Diagnostic)>, io::Error> {
+        let mut writer = LatexWriter::new(self, style);
+        writer.write_statement(out, stmt)?;
+        Ok(writer.diags)
+    }
+
+    /// Writes a standalone LaTeX document rendering the given statements,
+    /// as described in [`Database::render_latex`].
+    ///
+    /// Returns a [`Diagnostic::MissingMarkupDef`] diagnostic for each token
+    /// lacking a `latexdef`.
+    ///
+    /// Requires: [`Database::scope_pass`], [`Database::typesetting_pass`]
+    pub fn write_latex(
+        &self,
+        stmts: &[StatementRef<'_>],
+        style: LatexStyle,
+        out: &mut impl Write,
+    ) -> Result<Vec<(StatementAddress, Diagnostic)>, io::Error> {
+        time(&self.options.clone(), "latex", || {
+            let mut writer = LatexWriter::new(self, style);
+            writer.write_preamble(out)?;
+            for &stmt in stmts {
+                writer.write_statement(out, stmt)?;
+            }
+            writeln!(out, "\n\\end{{document}}")?;
+            Ok(writer.diags)
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use crate::grammar_tests::mkdb;
+use crate::latex::LatexStyle;
+use crate::Database;
+
+const LATEX_DB: &[u8] = b"$(
+$t
+latexdef \"wff\" as \"{\\rm wff}\";
+latexdef \"|-\" as \"\\vdash\";
+latexdef \"(\" as \"(\";
+latexdef \")\" as \")\";
+latexdef \"->\" as \"\\rightarrow\";
+latexdef \"ph\" as \"\\varphi\";
+latexdef \"ps\" as \"\\psi\";
+$)
+$c wff |- ( ) -> $.
+$v ph ps $.
+wph $f wff ph $.
+wps $f wff ps $.
+wi $a wff ( ph -> ps ) $.
+$( Axiom _Simp_. $)
+ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+${
+  min $e |- ph $.
+  maj $e |- ( ph -> ps ) $.
+  $( Rule of modus ponens. $)
+  ax-mp $a |- ps $.
+$}
+${
+  a1i.1 $e |- ph $.
+  $( Inference adding an antecedent, using ~ ax-1 . $)
+  a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+$}
+";
+
+fn render(db: &Database, label: &[u8], style: LatexStyle) -> String {
+    let mut out = vec![];
+    let diags = (db.render_latex(db.statement(label).unwrap(), style, &mut out)).unwrap();
+    assert!(diags.is_empty());
+    String::from_utf8(out).unwrap()
+}
+
+fn setup() -> Database {
+    let mut db = mkdb(LATEX_DB);
+    db.scope_pass();
+    db.typesetting_pass();
+    db
+}
+
+#[test]
+fn test_latex_modern() {
+    let db = setup();
+    assert_eq!(
+        render(&db, b"ax-1", LatexStyle::Modern),
+        r"
+\subsection*{Axiom \texttt{ax-1}}
+ Axiom \textit{Simp}. 
+
+\begin{align*}
+\mbox{\texttt{ax-1}} &: \ \vdash ( \varphi \rightarrow ( \psi \rightarrow \varphi ) ) 
+\end{align*}
+"
+    );
+    assert_eq!(
+        render(&db, b"a1i", LatexStyle::Modern),
+        r"
+\subsection*{Theorem \texttt{a1i}}
+ Inference adding an antecedent, using \texttt{ax-1}. 
+
+\begin{align*}
+\mbox{\texttt{a1i.1}} &: \ \vdash \varphi \\
+\mbox{\texttt{a1i}} &: \ \vdash ( \psi \rightarrow \varphi ) 
+\end{align*}
+\noindent Proof of Theorem \texttt{a1i}:
+
+\noindent \begin{longtable}{rlll}
+Step & Hyp & Ref & Expression \\
+\hline
+1 &  & \texttt{a1i.1} & . 2 \quad $\vdash \varphi $ \\
+2 &  & \texttt{ax-1} & . 2 \quad $\vdash ( \varphi \rightarrow ( \psi \rightarrow \varphi ) ) $ \\
+3 & 1, 2 & \texttt{ax-mp} & 1 \quad $\vdash ( \psi \rightarrow \varphi ) $ \\
+\end{longtable}
+
+"
+    );
+}
+
+#[test]
+fn test_latex_old() {
+    let db = setup();
+    assert_eq!(
+        render(&db, b"ax-1", LatexStyle::Old),
+        r"
+\vspace{1ex}
+\noindent {\bf Axiom} \texttt{ax-1}
+
+ Axiom \textit{Simp}. 
+
+\noindent \texttt{ax-1} \quad $\vdash ( \varphi \rightarrow ( \psi \rightarrow \varphi ) ) $
+
+"
+    );
+    assert_eq!(
+        render(&db, b"a1i", LatexStyle::Old),
+        r"
+\vspace{1ex}
+\noindent {\bf Theorem} \texttt{a1i}
+
+ Inference adding an antecedent, using \texttt{ax-1}. 
+
+\noindent \texttt{a1i.1} \quad $\vdash \varphi $
+
+\noindent \texttt{a1i} \quad $\vdash ( \psi \rightarrow \varphi ) $
+
+\noindent Proof of Theorem \texttt{a1i}:
+
+\noindent \begin{tabular}{rlll}
+Step & Hyp & Ref & Expression \\
+\hline
+1 &  & \texttt{a1i.1} & . 2 \quad $\vdash \varphi $ \\
+2 &  & \texttt{ax-1} & . 2 \quad $\vdash ( \varphi \rightarrow ( \psi \rightarrow \varphi ) ) $ \\
+3 & 1, 2 & \texttt{ax-mp} & 1 \quad $\vdash ( \psi \rightarrow \varphi ) $ \\
+\end{tabular}
+
+"
+    );
+}
+
+#[test]
+fn test_write_latex() {
+    let db = setup();
+    let stmts = [
+        db.statement(b"ax-1").unwrap(),
+        db.statement(b"a1i").unwrap(),
+    ];
+    for style in [LatexStyle::Modern, LatexStyle::Old] {
+        let mut out = vec![];
+        assert!(db.write_latex(&stmts, style, &mut out).unwrap().is_empty());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("\\documentclass"));
+        let mut body = render(&db, b"ax-1", style);
+        body.push_str(&render(&db, b"a1i", style));
+        assert!(out.ends_with(&format!("{body}\n\\end{{document}}\n")));
+    }
+}
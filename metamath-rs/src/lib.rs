@@ -58,21 +58,38 @@ mod tree;
 mod util;
 
 pub mod axiom_use;
+pub mod bibliography;
+pub mod cache;
+pub mod changelog;
 pub mod comment_parser;
 pub mod database;
 pub mod diag;
+pub mod diff;
 pub mod discouraged;
 pub mod export;
+pub mod extract;
+pub mod fix;
 pub mod formula;
 pub mod grammar;
+pub mod html;
+pub mod json;
+pub mod latex;
 pub mod line_cache;
+pub mod lint;
+pub mod mathml;
+pub mod mm0;
 pub mod nameck;
 pub mod outline;
 pub mod parser;
 pub mod proof;
+pub mod recent;
 pub mod scopeck;
 pub mod statement;
+pub mod stats;
+pub mod table;
+pub mod tptp;
 pub mod typesetting;
+pub mod unicode;
 pub mod verify;
 
 #[cfg(feature = "verify_markup")]
@@ -81,18 +98,56 @@ pub mod verify_markup;
 #[cfg(feature = "xml")]
 pub mod export_deps;
 
+#[cfg(test)]
+mod bibliography_tests;
+#[cfg(test)]
+mod cache_tests;
+#[cfg(test)]
+mod changelog_tests;
 #[cfg(test)]
 mod comment_parser_tests;
 #[cfg(test)]
+mod diag_tests;
+#[cfg(test)]
+mod diff_tests;
+#[cfg(test)]
+mod extract_tests;
+#[cfg(all(test, feature = "verify_markup"))]
+mod fix_tests;
+#[cfg(test)]
 mod formula_tests;
 #[cfg(test)]
 mod grammar_tests;
 #[cfg(test)]
+mod html_tests;
+#[cfg(test)]
+mod json_tests;
+#[cfg(test)]
+mod latex_tests;
+#[cfg(test)]
+mod lint_tests;
+#[cfg(test)]
+mod mm0_tests;
+#[cfg(test)]
+mod outline_tests;
+#[cfg(test)]
 mod parser_tests;
+#[cfg(all(test, feature = "dot"))]
+mod proof_tests;
+#[cfg(test)]
+mod stats_tests;
+#[cfg(test)]
+mod table_tests;
+#[cfg(test)]
+mod tptp_tests;
+#[cfg(test)]
+mod unicode_tests;
 #[cfg(test)]
 mod usage_tests;
 #[cfg(test)]
 mod util_tests;
+#[cfg(test)]
+mod verify_tests;
 
 pub use database::Database;
 pub use formula::Formula;
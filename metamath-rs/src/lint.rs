@@ -0,0 +1,296 @@
+//! Lint policy: allowing, re-leveling or denying diagnostics by kind.
+//!
+//! Each kind of diagnostic is identified by its code (see [`Diagnostic::code`]),
+//! and is reported by default at the level hard-coded for it in [`crate::diag`].
+//! The lint policy changes this level, from the most general to the most specific
+//! setting:
+//!
+//! - for the whole database, with a [`LintPolicy`] given to
+//!   [`Database::set_lint_policy`], typically from the command line;
+//! - with `$j lint` commands in the database, like
+//!   `$( $j lint allow 'TabUsed' 'LineLengthExceeded'; $)`.
+//!   Commands placed before the first heading apply to the whole database.
+//!   Other commands apply until the next heading of the same or a higher level,
+//!   i.e. to the rest of the enclosing part, section or mathbox;
+//! - for a single statement, with a `(Lint allow TabUsed.)` marker in its
+//!   description comment, which applies to the diagnostics attached to the
+//!   statement and to its comment.
+//!
+//! The available levels are `allow`, which hides the diagnostics, and `warn` and
+//! `deny`, which report them as warnings and errors respectively.
+
+use crate::diag::Diagnostic;
+use crate::parser::HeadingLevel;
+use crate::segment::Comparer;
+use crate::segment_set::SegmentSet;
+use crate::statement::{CommandToken, StatementAddress};
+use crate::util::HashMap;
+use crate::{Database, Span, StatementType};
+use annotate_snippets::Level;
+use std::cmp::Ordering;
+
+/// The level at which a kind of diagnostic is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// The diagnostics are not reported.
+    Allow,
+    /// The diagnostics are reported as warnings.
+    Warn,
+    /// The diagnostics are reported as errors.
+    Deny,
+}
+
+impl LintLevel {
+    /// Parses a lint level from its name, `allow`, `warn` or `deny`.
+    #[must_use]
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"allow" => Some(Self::Allow),
+            b"warn" => Some(Self::Warn),
+            b"deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+
+    /// The level of the reported diagnostics, or `None` if they are not reported.
+    #[must_use]
+    pub const fn level(self) -> Option<Level> {
+        match self {
+            Self::Allow => None,
+            Self::Warn => Some(Level::Warning),
+            Self::Deny => Some(Level::Error),
+        }
+    }
+}
+
+/// Returns the static code matching the given diagnostic code, if it is known.
+fn known_code(code: &[u8]) -> Option<&'static str> {
+    Diagnostic::CODES
+        .iter()
+        .find(|known| known.as_bytes() == code)
+        .copied()
+}
+
+/// Lint levels set for the whole database, overriding the default levels.
+#[derive(Debug, Clone, Default)]
+pub struct LintPolicy {
+    levels: HashMap<&'static str, LintLevel>,
+}
+
+impl LintPolicy {
+    /// Sets the level for the diagnostics with the given code.
+    /// Returns `false` if the code is not the code of any kind of diagnostic.
+    pub fn set(&mut self, code: &str, level: LintLevel) -> bool {
+        let Some(code) = known_code(code.as_bytes()) else {
+            return false;
+        };
+        self.levels.insert(code, level);
+        true
+    }
+}
+
+/// Lint levels set by a `$j lint` command for a part of the database.
+#[derive(Debug)]
+struct LintRegion {
+    /// The `$j` comment holding the command.
+    start: StatementAddress,
+    /// The next heading of the same or a higher level, if any.
+    end: Option<StatementAddress>,
+    /// The level of the heading the command is in.
+    heading: HeadingLevel,
+    code: &'static str,
+    level: LintLevel,
+}
+
+/// The lint levels set for the database, for its parts, and for its statements.
+#[derive(Debug, Default)]
+pub struct LintResult {
+    global: HashMap<&'static str, LintLevel>,
+    regions: Vec<LintRegion>,
+    statements: HashMap<StatementAddress, Vec<(&'static str, LintLevel)>>,
+    diagnostics: Vec<(StatementAddress, Diagnostic)>,
+}
+
+impl LintResult {
+    /// Returns the list of errors found in the lint commands and markers.
+    #[must_use]
+    pub fn diagnostics(&self) -> Vec<(StatementAddress, Diagnostic)> {
+        self.diagnostics.clone()
+    }
+
+    /// Returns the lint level set for the diagnostics with the given code
+    /// attached to the given statement, if any.
+    pub(crate) fn level(
+        &self,
+        sset: &SegmentSet,
+        addr: StatementAddress,
+        code: &str,
+    ) -> Option<LintLevel> {
+        if let Some(levels) = self.statements.get(&addr) {
+            if let Some(&(_, level)) = levels.iter().rev().find(|(c, _)| *c == code) {
+                return Some(level);
+            }
+        }
+        let mut innermost: Option<&LintRegion> = None;
+        for region in &self.regions {
+            if region.code == code
+                && sset.order.cmp(&region.start, &addr) == Ordering::Less
+                && region
+                    .end
+                    .is_none_or(|end| sset.order.cmp(&addr, &end) == Ordering::Less)
+                && innermost.is_none_or(|inner| {
+                    sset.order.cmp(&inner.start, &region.start) == Ordering::Less
+                })
+            {
+                innermost = Some(region);
+            }
+        }
+        innermost
+            .map(|region| region.level)
+            .or_else(|| self.global.get(code).copied())
+    }
+
+    /// Parses a `(Lint allow Code1 Code2.)` marker in a comment,
+    /// and records its levels for the given statements.
+    fn parse_marker(
+        &mut self,
+        buf: &[u8],
+        span: Span,
+        addr: StatementAddress,
+        stmts: &[StatementAddress],
+    ) {
+        let text = span.as_ref(buf);
+        let marker_start = match text.windows(6).position(|w| w == b"(Lint ") {
+            Some(pos) => pos + 6,
+            None => return,
+        };
+        let Some(len) = text[marker_start..].iter().position(|&c| c == b')') else {
+            return;
+        };
+        let mut words = text[marker_start..marker_start + len]
+            .split(|&c| c.is_ascii_whitespace() || c == b',')
+            .scan(span.start as usize + marker_start, |pos, word| {
+                let start = *pos;
+                *pos += word.len() + 1;
+                Some((start, word))
+            })
+            .filter(|(_, word)| !word.is_empty());
+        let marker_span = Span::new(
+            span.start as usize + marker_start,
+            span.start as usize + marker_start + len,
+        );
+        let Some(level) = words
+            .next()
+            .and_then(|(_, word)| LintLevel::from_name(word))
+        else {
+            self.diagnostics
+                .push((addr, Diagnostic::BadLintCommand(marker_span)));
+            return;
+        };
+        for (start, word) in words {
+            let word = word.strip_suffix(b".").unwrap_or(word);
+            if let Some(code) = known_code(word) {
+                for &stmt in stmts {
+                    self.statements.entry(stmt).or_default().push((code, level));
+                }
+            } else {
+                let span = Span::new(start, start + word.len());
+                self.diagnostics
+                    .push((addr, Diagnostic::UnknownLintCode(span)));
+            }
+        }
+    }
+}
+
+/// Collects the lint levels set by the policy, and by the `$j lint` commands and
+/// the `(Lint ...)` markers in the database.
+pub(crate) fn lint(sset: &SegmentSet, policy: &LintPolicy) -> LintResult {
+    let mut result = LintResult {
+        global: policy.levels.clone(),
+        ..LintResult::default()
+    };
+    let mut open_regions: Vec<LintRegion> = vec![];
+    let mut heading = HeadingLevel::Database;
+    for sref in sset.segments(..) {
+        let buf = &**sref.buffer;
+        let mut headings = sref.outline.iter().peekable();
+        let mut j_commands = sref.j_commands.iter().peekable();
+        for stmt in sref.range(..) {
+            while let Some(def) = headings.next_if(|def| def.index == stmt.index) {
+                heading = def.level;
+                let (closed, open) = std::mem::take(&mut open_regions)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|region| def.level <= region.heading);
+                open_regions = open;
+                result
+                    .regions
+                    .extend(closed.into_iter().map(|region| LintRegion {
+                        end: Some(stmt.address()),
+                        ..region
+                    }));
+            }
+            while let Some((_, (_, args))) = j_commands.next_if(|(ix, _)| *ix == stmt.index) {
+                use CommandToken::*;
+                let [Keyword(cmd), level, codes @ ..] = &**args else {
+                    continue;
+                };
+                if cmd.as_ref(buf) != b"lint" {
+                    continue;
+                }
+                let Some(level) = LintLevel::from_name(&level.value(buf)) else {
+                    let diag = Diagnostic::BadLintCommand(level.full_span());
+                    result.diagnostics.push((stmt.address(), diag));
+                    continue;
+                };
+                for token in codes {
+                    let Some(code) = known_code(&token.value(buf)) else {
+                        let diag = Diagnostic::UnknownLintCode(token.full_span());
+                        result.diagnostics.push((stmt.address(), diag));
+                        continue;
+                    };
+                    if heading == HeadingLevel::Database {
+                        result.global.insert(code, level);
+                    } else {
+                        open_regions.push(LintRegion {
+                            start: stmt.address(),
+                            end: None,
+                            heading,
+                            code,
+                            level,
+                        });
+                    }
+                }
+            }
+            if stmt.statement_type() == StatementType::Comment {
+                let mut stmts = vec![stmt.address()];
+                if stmt.index + 1 < sref.statements.len() as i32 {
+                    let next = sref.statement(stmt.index + 1);
+                    if next.statement_type() != StatementType::Comment {
+                        stmts.push(next.address());
+                    }
+                }
+                result.parse_marker(buf, stmt.comment_contents(), stmt.address(), &stmts);
+            }
+        }
+    }
+    result.regions.extend(open_regions);
+    result
+}
+
+impl Database {
+    /// Returns the level at which the given diagnostic is reported,
+    /// according to the lint policy, or `None` if it is not reported.
+    ///
+    /// If [`Database::lint_pass`] was not previously called,
+    /// this is the default level of the diagnostic.
+    #[must_use]
+    pub fn diag_level(&self, addr: StatementAddress, diag: &Diagnostic) -> Option<Level> {
+        let sset = self.parse_result();
+        match (self.try_lint_result())
+            .and_then(|lint| lint.level(sset, addr, diag.without_step().code()))
+        {
+            Some(level) => level.level(),
+            None => Some(diag.default_level(sset, sset.statement_or_dummy(addr))),
+        }
+    }
+}
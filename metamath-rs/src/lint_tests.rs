@@ -0,0 +1,86 @@
+use crate::diag::Diagnostic;
+use crate::grammar_tests::mkdb;
+use crate::lint::{LintLevel, LintPolicy};
+use annotate_snippets::Level;
+
+const LINT_DB: &[u8] = b"$( $j lint warn 'StepMissing'; $)
+$c wff |- $.
+$v ph $.
+wph $f wff ph $.
+ax-1 $a |- ph $.
+thm1 $p |- ph $= wph ax-2 $.
+$(
+#*#*#*#*
+  Section A
+#*#*#*#*
+$)
+$( $j lint allow 'StepMissing'; $)
+thm2 $p |- ph $= wph ax-2 $.
+$(
+#*#*#*#*
+  Section B
+#*#*#*#*
+$)
+thm3 $p |- ph $= wph ax-2 $.
+$( Not allowed here. (Lint deny StepMissing.) $)
+thm4 $p |- ph $= wph ax-2 $.
+$( $j lint allow 'NoSuchCode'; $)
+";
+
+/// Returns the labels of the statements with missing steps, and their levels, sorted.
+fn step_missing_levels(policy: LintPolicy) -> Vec<(String, Option<Level>)> {
+    let mut db = mkdb(LINT_DB);
+    db.set_lint_policy(policy);
+    db.verify_pass();
+    db.lint_pass();
+    let mut levels = (db.diag_notations().into_iter())
+        .filter(|(_, diag)| matches!(diag, Diagnostic::StepMissing(..)))
+        .map(|(addr, diag)| {
+            let label = db.statement_by_address(addr).label();
+            (
+                String::from_utf8_lossy(label).into_owned(),
+                db.diag_level(addr, &diag),
+            )
+        })
+        .collect::<Vec<_>>();
+    levels.sort_by(|a, b| a.0.cmp(&b.0));
+    levels
+}
+
+#[test]
+fn test_lint_levels() {
+    assert_eq!(
+        step_missing_levels(LintPolicy::default()),
+        vec![
+            ("thm1".to_owned(), Some(Level::Warning)),
+            ("thm2".to_owned(), None),
+            ("thm3".to_owned(), Some(Level::Warning)),
+            ("thm4".to_owned(), Some(Level::Error)),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_policy() {
+    let mut policy = LintPolicy::default();
+    assert!(!policy.set("NoSuchCode", LintLevel::Allow));
+    assert!(policy.set("UnknownLintCode", LintLevel::Deny));
+    let mut db = mkdb(LINT_DB);
+    db.set_lint_policy(policy);
+    db.verify_pass();
+    db.lint_pass();
+    let diags = db.locate_diags(db.diag_notations());
+    let mut codes = (diags.iter())
+        .map(|diag| (diag.code, diag.level == Level::Error))
+        .collect::<Vec<_>>();
+    codes.sort_unstable();
+    assert_eq!(
+        codes,
+        vec![
+            ("StepMissing", false),
+            ("StepMissing", false),
+            ("StepMissing", true),
+            ("UnknownLintCode", true),
+        ]
+    );
+}
@@ -0,0 +1,104 @@
+//! Rendering of formulas to presentation `MathML`.
+//!
+//! Unlike the `htmldef` strings, which are concatenated token by token,
+//! `MathML` rendering follows the structure of the parsed [`Formula`]:
+//! each syntax axiom application becomes an `<mrow>` element,
+//! so that browsers can lay out and stretch brackets accordingly.
+//!
+//! Symbol glyphs are derived from the `althtmldef` definitions, like for
+//! [`crate::unicode`], and can be overridden with raw `MathML` markup
+//! for individual tokens.
+//!
+//! [`Formula`]: crate::formula::Formula
+
+use crate::statement::{Token, TokenPtr};
+use crate::unicode::UnicodeRenderer;
+use crate::util::HashMap;
+use crate::{as_str, Database};
+use std::fmt::{self, Write};
+
+/// Writes the given text, escaping the XML special characters.
+fn write_escaped(w: &mut impl Write, text: &str) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '&' => w.write_str("&amp;")?,
+            '<' => w.write_str("&lt;")?,
+            '>' => w.write_str("&gt;")?,
+            '"' => w.write_str("&quot;")?,
+            _ => w.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Renders the tokens of formulas as `MathML` elements.
+#[derive(Debug, Default, Clone)]
+pub struct MathMlRenderer {
+    glyphs: UnicodeRenderer,
+    overrides: HashMap<Token, String>,
+}
+
+impl MathMlRenderer {
+    /// Builds a renderer using the glyphs from the `althtmldef` definitions
+    /// of the given Unicode renderer.
+    #[must_use]
+    pub fn new(glyphs: UnicodeRenderer) -> Self {
+        Self {
+            glyphs,
+            overrides: HashMap::default(),
+        }
+    }
+
+    /// Overrides the rendering of the given token with a piece of `MathML` markup,
+    /// like `<mo stretchy="false">(</mo>`.
+    pub fn set_override(&mut self, token: TokenPtr<'_>, mathml: impl Into<String>) {
+        self.overrides.insert(token.into(), mathml.into());
+    }
+
+    /// Writes a single token as a `MathML` element:
+    /// `<mi>` for variables and alphabetic constants, `<mn>` for numbers,
+    /// and `<mo>` for other constants.
+    /// Tokens whose `althtmldef` has no text, like images, are written as an `<mi>` of the token.
+    pub(crate) fn write_token(
+        &self,
+        w: &mut impl Write,
+        token: TokenPtr<'_>,
+        is_variable: bool,
+    ) -> fmt::Result {
+        if let Some(mathml) = self.overrides.get(token) {
+            return w.write_str(mathml);
+        }
+        let glyph = match self.glyphs.text(token).map(str::trim) {
+            Some("") => {
+                write!(w, "<mi>")?;
+                write_escaped(w, as_str(token))?;
+                return write!(w, "</mi>");
+            }
+            Some(glyph) => glyph,
+            None => as_str(token),
+        };
+        let element = if is_variable {
+            "mi"
+        } else if glyph.chars().all(|c| c.is_ascii_digit()) {
+            "mn"
+        } else if glyph.chars().all(char::is_alphabetic) {
+            "mi"
+        } else {
+            "mo"
+        };
+        write!(w, "<{element}>")?;
+        write_escaped(w, glyph)?;
+        write!(w, "</{element}>")
+    }
+}
+
+impl Database {
+    /// Builds a renderer of formulas to `MathML`, using the `althtmldef` definitions
+    /// of this database for the symbol glyphs.
+    ///
+    /// Requires: [`Database::typesetting_pass`]
+    #[must_use]
+    pub fn mathml_renderer(&self) -> MathMlRenderer {
+        MathMlRenderer::new(self.unicode_renderer(false))
+    }
+}
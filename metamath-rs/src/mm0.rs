@@ -0,0 +1,815 @@
+//! Export to Metamath Zero.
+//!
+//! [Metamath Zero](https://github.com/digama0/mm0) separates the specification of
+//! a formal system, in a `.mm0` file, from the proofs of its theorems, in a `.mmu`
+//! file. The database is translated using its grammar:
+//!
+//! - The typecodes of the grammar become sorts, the logical typecode (`wff` for
+//!   set.mm) being the provable sort. The variables of the sorts which are not
+//!   produced by any syntax axiom, like `setvar`, are bound variables, and each
+//!   other variable of an assertion depends on the bound variables it has no
+//!   `$d` condition with.
+//! - Syntax axioms become term constructors. The bound variables of a term are
+//!   binding if the term has other arguments, like `x` in `A. x ph`, and free
+//!   otherwise, like `x` in `x e. y`.
+//! - A `df-` axiom of the form `|- ( ( T x y ) <-> rhs )`, with any binary root
+//!   symbol, makes `T` a definition with the body `rhs`, provided that `T` was not
+//!   used before, that `rhs` does not use `T`, and that the variables only in
+//!   `rhs` are bound variables with `$d` conditions from all the others.
+//!   The `df-` axiom is still exported as an axiom.
+//! - Syntax theorems are expanded in the expressions using them.
+//! - Axioms and theorems are exported as such, the proofs of the theorems being
+//!   written in the `.mmu` file, with the subproofs used several times bound by
+//!   `:let`.
+//!
+//! Labels are turned into identifiers by replacing the characters other than
+//! letters, digits and `_` by `_`, and adding `_` suffixes in case of clash.
+//! Variables are named after their floating hypotheses. Since no notations are
+//! exported, math strings are written in prefix form, like `$ wi wph ( wn wps ) $`.
+//!
+//! Some valid Metamath proofs are rejected by MM0 checkers: the ones using dummy
+//! variables of a sort which is not bound, like `wff`, and the ones substituting
+//! the same variable for two bound variables without a `$d` condition between them.
+
+use crate::diag::{Diagnostic, StmtParseError};
+use crate::export::ExportError;
+use crate::formula::TypeCode;
+use crate::proof::ProofTreeArray;
+use crate::scopeck::{Frame, Hyp};
+use crate::statement::StatementAddress;
+use crate::util::{HashMap, HashSet};
+use crate::verify::ProofBuilder;
+use crate::{as_str, Database, StatementRef, StatementType};
+use std::fmt::Write as _;
+use std::io;
+use std::rc::Rc;
+
+/// The keywords of MM0, which cannot be used as identifiers.
+const KEYWORDS: [&str; 22] = [
+    "abstract",
+    "axiom",
+    "coercion",
+    "def",
+    "delimiter",
+    "free",
+    "infixl",
+    "infixr",
+    "input",
+    "local",
+    "max",
+    "notation",
+    "output",
+    "prec",
+    "prefix",
+    "provable",
+    "pub",
+    "pure",
+    "sort",
+    "strict",
+    "term",
+    "theorem",
+];
+
+/// The syntax of the written expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// The math strings of the `.mm0` file, like `wi wph ( wn wps )`.
+    Math,
+    /// The s-expressions of the `.mmu` file, like `(wi wph (wn wps))`.
+    SExpr,
+}
+
+/// A variable of a term, definition or assertion.
+struct Binder<'a> {
+    name: &'a str,
+    sort: TypeCode,
+    bound: bool,
+    /// The bound variables this variable may contain.
+    deps: Vec<&'a str>,
+}
+
+/// An assertion being exported, with the expressions of its hypotheses and
+/// conclusion, and its proof, in a single proof tree array.
+struct Item<'a> {
+    stmt: StatementRef<'a>,
+    frame: &'a Frame,
+    arr: ProofTreeArray,
+    hyps: Vec<(StatementAddress, usize)>,
+    conclusion: usize,
+    proof: Option<usize>,
+}
+
+/// A definition recognized from a `df-` axiom.
+struct Definition {
+    /// The syntax axiom being defined.
+    term: StatementAddress,
+    /// The indices in the frame of the `df-` axiom of the arguments of the
+    /// defined term, in argument order.
+    args: Vec<usize>,
+    /// The indices in the frame of the `df-` axiom of the dummy variables.
+    dummies: Vec<usize>,
+    /// The body of the definition.
+    body: usize,
+}
+
+struct Mm0Exporter<'a> {
+    db: &'a Database,
+    /// The identifiers of the labelled statements.
+    names: HashMap<StatementAddress, String>,
+    /// The identifiers in use, for naming the `:let` bindings.
+    used_names: HashSet<String>,
+    /// The provable typecode, `|-` for set.mm.
+    provable: TypeCode,
+    /// The sorts of the bound variables.
+    bound_sorts: &'a HashSet<TypeCode>,
+    /// The syntax axioms which were not exported yet, in database order.
+    pending_terms: Vec<StatementAddress>,
+    /// The proofs of the syntax theorems, expanded in the expressions.
+    syntax_proofs: HashMap<StatementAddress, Rc<ProofTreeArray>>,
+}
+
+/// Turns a label or typecode into an MM0 identifier.
+fn identifier(token: &[u8]) -> String {
+    let mut name = as_str(token)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Writes a list of identifiers, each preceded by a space.
+fn prefixed(names: &[&str]) -> String {
+    names.iter().fold(String::new(), |mut out, name| {
+        let _ = write!(out, " {name}");
+        out
+    })
+}
+
+/// Returns whether the given pair of variables has a `$d` condition in a frame.
+fn disjoint(frame: &Frame, var1: usize, var2: usize) -> bool {
+    (frame.mandatory_dv.iter()).any(|&pair| pair == (var1, var2) || pair == (var2, var1))
+}
+
+impl<'a> Mm0Exporter<'a> {
+    fn new(db: &'a Database) -> Self {
+        let provable = db.grammar_result().provable_typecode();
+        let mut names = HashMap::default();
+        let mut used_names = KEYWORDS
+            .iter()
+            .map(|&name| name.to_owned())
+            .collect::<HashSet<_>>();
+        for stmt in db.statements() {
+            if stmt.label().is_empty() {
+                continue;
+            }
+            let mut name = identifier(stmt.label());
+            while !used_names.insert(name.clone()) {
+                name.push('_');
+            }
+            names.insert(stmt.address(), name);
+        }
+        Self {
+            db,
+            names,
+            used_names,
+            provable,
+            bound_sorts: db.variable_typecodes(),
+            pending_terms: vec![],
+            syntax_proofs: HashMap::default(),
+        }
+    }
+
+    fn name(&self, address: StatementAddress) -> &str {
+        self.names.get(&address).map_or("_", String::as_str)
+    }
+
+    fn sort_name(&self, typecode: TypeCode) -> String {
+        identifier(self.db.name_result().atom_name(typecode))
+    }
+
+    fn typecode(&self, address: StatementAddress) -> TypeCode {
+        let stmt = self.db.statement_by_address(address);
+        self.db.name_result().get_atom(stmt.math_at(0).slice)
+    }
+
+    /// Returns the frame of an assertion, or an error if it has none, which
+    /// happens if its scope is invalid.
+    fn frame(&self, address: StatementAddress) -> Result<&'a Frame, ExportError> {
+        let stmt = self.db.statement_by_address(address);
+        (self.db.scope_result().get(stmt.label()))
+            .ok_or_else(|| Diagnostic::UnknownLabel(stmt.label_span()).into())
+    }
+
+    /// Returns whether the given statement is a syntax axiom or theorem.
+    fn is_syntax(&self, stmt: StatementRef<'_>) -> bool {
+        stmt.is_assertion() && self.typecode(stmt.address()) != self.provable
+    }
+
+    /// Returns whether the given hypothesis is a bound variable.
+    fn is_bound(&self, hyp: &Hyp) -> bool {
+        matches!(hyp, Hyp::Floating(_, _, typecode) if self.bound_sorts.contains(typecode))
+    }
+
+    /// Returns the indices of the hypotheses of a frame, in the order of the
+    /// arguments of the MM0 assertion: bound variables, other variables, and
+    /// essential hypotheses.
+    fn order(&self, frame: &Frame) -> Vec<usize> {
+        let hyps = &frame.hypotheses;
+        let is_float = |ix: usize| matches!(hyps[ix], Hyp::Floating(..));
+        let mut order = (0..hyps.len())
+            .filter(|&ix| self.is_bound(&hyps[ix]))
+            .collect::<Vec<_>>();
+        order.extend((0..hyps.len()).filter(|&ix| is_float(ix) && !self.is_bound(&hyps[ix])));
+        order.extend((0..hyps.len()).filter(|&ix| !is_float(ix)));
+        order
+    }
+
+    /// Returns the binders of the given variables of a frame. Each variable
+    /// depends on the bound variables among them it has no `$d` condition with,
+    /// or on all of them if `all_deps` is set.
+    fn binders(&self, frame: &Frame, vars: &[usize], all_deps: bool) -> Vec<Binder<'_>> {
+        let binders = vars.iter().filter_map(|&ix| match frame.hypotheses[ix] {
+            Hyp::Floating(address, var, sort) => Some((address, var, sort)),
+            Hyp::Essential(..) => None,
+        });
+        let bound = (binders.clone())
+            .filter(|(_, _, sort)| self.bound_sorts.contains(sort))
+            .collect::<Vec<_>>();
+        binders
+            .map(|(address, var, sort)| {
+                let is_bound = self.bound_sorts.contains(&sort);
+                let deps = (bound.iter())
+                    .filter(|&&(_, bvar, _)| !is_bound && (all_deps || !disjoint(frame, var, bvar)))
+                    .map(|&(baddress, _, _)| self.name(baddress))
+                    .collect();
+                Binder {
+                    name: self.name(address),
+                    sort,
+                    bound: is_bound,
+                    deps,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the free variables of the result of a term or definition: its
+    /// bound variables which none of its other variables depend on.
+    fn return_deps<'b>(binders: &[Binder<'b>]) -> Vec<&'b str> {
+        (binders.iter())
+            .filter(|binder| {
+                binder.bound
+                    && !binders
+                        .iter()
+                        .any(|other| other.deps.contains(&binder.name))
+            })
+            .map(|binder| binder.name)
+            .collect()
+    }
+
+    /// Writes binders, in the `.mm0` syntax if `dummy` is `None`, and in the `.mmu`
+    /// syntax otherwise. In the `.mm0` syntax, dummy variables are prefixed with a dot.
+    fn write_binders(&self, out: &mut String, binders: &[Binder<'_>], style: Style, dummy: bool) {
+        for binder in binders {
+            let sort = self.sort_name(binder.sort);
+            match style {
+                Style::Math => {
+                    let (open, close) = if binder.bound { ('{', '}') } else { ('(', ')') };
+                    let dot = if dummy { "." } else { "" };
+                    let _ = write!(out, " {open}{dot}{}: {sort}", binder.name);
+                    for dep in &binder.deps {
+                        let _ = write!(out, " {dep}");
+                    }
+                    out.push(close);
+                }
+                Style::SExpr if binder.bound => {
+                    let _ = write!(out, " ({} {sort})", binder.name);
+                }
+                Style::SExpr => {
+                    let _ = write!(out, " ({} {sort} ({}))", binder.name, binder.deps.join(" "));
+                }
+            }
+        }
+    }
+
+    /// Returns the proof of a syntax theorem.
+    fn syntax_proof(&mut self, stmt: StatementRef<'_>) -> Result<Rc<ProofTreeArray>, ExportError> {
+        if let Some(proof) = self.syntax_proofs.get(&stmt.address()) {
+            return Ok(proof.clone());
+        }
+        let proof = Rc::new(ProofTreeArray::from_stmt(self.db, stmt, false)?);
+        self.syntax_proofs.insert(stmt.address(), proof.clone());
+        Ok(proof)
+    }
+
+    /// Copies a proof tree into `dst`, replacing the hypotheses in `subst` by the
+    /// given trees of `dst`, and expanding the syntax theorems.
+    /// The copies are memoized in `memo` if given.
+    fn normalize(
+        &mut self,
+        src: &ProofTreeArray,
+        ix: usize,
+        subst: &HashMap<StatementAddress, usize>,
+        dst: &mut ProofTreeArray,
+        mut memo: Option<&mut HashMap<usize, usize>>,
+    ) -> Result<usize, ExportError> {
+        if let Some(&done) = memo.as_deref().and_then(|memo| memo.get(&ix)) {
+            return Ok(done);
+        }
+        let tree = &src.trees[ix];
+        if let Some(&sub) = subst.get(&tree.address) {
+            return Ok(sub);
+        }
+        let mut children = vec![];
+        for &child in &tree.children {
+            children.push(self.normalize(src, child, subst, dst, memo.as_deref_mut())?);
+        }
+        let stmt = self.db.statement_by_address(tree.address);
+        let out = if stmt.statement_type() == StatementType::Provable && self.is_syntax(stmt) {
+            let inner = (self.frame(tree.address)?.hypotheses.iter())
+                .map(Hyp::address)
+                .zip(children)
+                .collect();
+            let proof = self.syntax_proof(stmt)?;
+            self.normalize(&proof, proof.qed, &inner, dst, None)?
+        } else {
+            dst.build(tree.address, children, &[], 0..0)
+        };
+        if let Some(memo) = memo {
+            memo.insert(ix, out);
+        }
+        Ok(out)
+    }
+
+    /// Builds the expressions of the hypotheses and conclusion of an assertion,
+    /// and its proof for a theorem.
+    fn item(&mut self, stmt: StatementRef<'a>) -> Result<Item<'a>, ExportError> {
+        let frame = self.frame(stmt.address())?;
+        let formulas = self.db.stmt_parse_result();
+        let mut raw = ProofTreeArray::new(false);
+        let mut stack_buffer = vec![];
+        let mut parse = |stmt: StatementRef<'_>| {
+            let formula = formulas.get_formula(&stmt).ok_or_else(|| {
+                Diagnostic::StmtParseError(StmtParseError::UnparseableStatement(stmt.span()))
+            })?;
+            Ok::<_, ExportError>(
+                formula
+                    .as_ref(self.db)
+                    .build_syntax_proof(&mut stack_buffer, &mut raw),
+            )
+        };
+        let mut raw_hyps = vec![];
+        for hyp in &*frame.hypotheses {
+            if let Hyp::Essential(address, _) = *hyp {
+                raw_hyps.push((address, parse(self.db.statement_by_address(address))?));
+            }
+        }
+        let raw_conclusion = parse(stmt)?;
+
+        let mut arr = ProofTreeArray::new(false);
+        let subst = HashMap::default();
+        let mut hyps = vec![];
+        for (address, ix) in raw_hyps {
+            hyps.push((address, self.normalize(&raw, ix, &subst, &mut arr, None)?));
+        }
+        let conclusion = self.normalize(&raw, raw_conclusion, &subst, &mut arr, None)?;
+        let proof = if stmt.statement_type() == StatementType::Provable {
+            let raw = ProofTreeArray::from_stmt(self.db, stmt, false)?;
+            let mut memo = HashMap::default();
+            Some(self.normalize(&raw, raw.qed, &subst, &mut arr, Some(&mut memo))?)
+        } else {
+            None
+        };
+        Ok(Item {
+            stmt,
+            frame,
+            arr,
+            hyps,
+            conclusion,
+            proof,
+        })
+    }
+
+    /// Writes an expression or a proof. The steps in `lets` are written as
+    /// references to their `:let` bindings, except at the top level.
+    fn write_expr(
+        &self,
+        out: &mut String,
+        arr: &ProofTreeArray,
+        ix: usize,
+        style: Style,
+        lets: &HashMap<usize, String>,
+        top: bool,
+    ) {
+        if let Some(name) = lets.get(&ix).filter(|_| !top) {
+            out.push_str(name);
+            return;
+        }
+        let tree = &arr.trees[ix];
+        let name = self.name(tree.address);
+        if !self.db.statement_by_address(tree.address).is_assertion() {
+            out.push_str(name);
+            return;
+        }
+        let nested = style == Style::SExpr || (!top && !tree.children.is_empty());
+        if nested {
+            out.push('(');
+            if style == Style::Math {
+                out.push(' ');
+            }
+        }
+        out.push_str(name);
+        let order = (self.frame(tree.address)).map_or_else(|_| vec![], |frame| self.order(frame));
+        for arg in order {
+            out.push(' ');
+            self.write_expr(out, arr, tree.children[arg], style, lets, false);
+        }
+        if nested {
+            if style == Style::Math {
+                out.push(' ');
+            }
+            out.push(')');
+        }
+    }
+
+    /// Writes a math string.
+    fn math(&self, arr: &ProofTreeArray, ix: usize) -> String {
+        let mut out = "$ ".to_owned();
+        self.write_expr(&mut out, arr, ix, Style::Math, &HashMap::default(), true);
+        out.push_str(" $");
+        out
+    }
+
+    /// Writes an s-expression.
+    fn sexpr(&self, arr: &ProofTreeArray, ix: usize) -> String {
+        let mut out = String::new();
+        self.write_expr(&mut out, arr, ix, Style::SExpr, &HashMap::default(), true);
+        out
+    }
+
+    /// Writes the sorts.
+    fn write_sorts(
+        &self,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        let grammar = self.db.grammar_result();
+        let mut done = HashSet::default();
+        for &typecode in &grammar.typecodes() {
+            if typecode == self.provable || !done.insert(typecode) {
+                continue;
+            }
+            let name = self.sort_name(typecode);
+            if typecode == grammar.logic_typecode() {
+                writeln!(mm0, "provable sort {name};")?;
+                writeln!(mmu, "(sort {name} provable)")?;
+            } else {
+                writeln!(mm0, "sort {name};")?;
+                writeln!(mmu, "(sort {name})")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the term constructor for a syntax axiom.
+    fn write_term(
+        &self,
+        address: StatementAddress,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        let frame = self.frame(address)?;
+        let binders = self.binders(frame, &self.order(frame), true);
+        let name = self.name(address);
+        let sort = self.sort_name(self.typecode(address));
+        let deps = Self::return_deps(&binders);
+        let mut args = String::new();
+        self.write_binders(&mut args, &binders, Style::Math, false);
+        writeln!(mm0, "term {name}{args}: {sort}{};", prefixed(&deps))?;
+        args.clear();
+        self.write_binders(&mut args, &binders, Style::SExpr, false);
+        writeln!(
+            mmu,
+            "(term {name} ({}) ({sort} ({})))",
+            args.trim_start(),
+            deps.join(" ")
+        )?;
+        Ok(())
+    }
+
+    /// Writes the term constructors for the pending syntax axioms used in the
+    /// given proof tree array, except `except`.
+    fn flush_terms(
+        &mut self,
+        arr: &ProofTreeArray,
+        except: Option<StatementAddress>,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        let used = (arr.trees.iter())
+            .map(|tree| tree.address)
+            .filter(|&address| Some(address) != except)
+            .collect::<HashSet<_>>();
+        let mut pending = std::mem::take(&mut self.pending_terms);
+        let mut result = Ok(());
+        pending.retain(|&address| {
+            if result.is_ok() && used.contains(&address) {
+                result = self.write_term(address, mm0, mmu);
+                false
+            } else {
+                true
+            }
+        });
+        self.pending_terms = pending;
+        result
+    }
+
+    /// Recognizes a definition in a `df-` axiom.
+    fn definition(&self, item: &Item<'_>) -> Option<Definition> {
+        let root = &item.arr.trees[item.conclusion];
+        if !item.hyps.is_empty() || root.children.len() != 2 {
+            return None;
+        }
+        let lhs = &item.arr.trees[root.children[0]];
+        let body = root.children[1];
+        if !self.pending_terms.contains(&lhs.address) {
+            return None;
+        }
+        let hyps = &item.frame.hypotheses;
+        let mut vars = vec![];
+        for &child in &lhs.children {
+            let address = item.arr.trees[child].address;
+            let var = hyps
+                .iter()
+                .position(|hyp| matches!(hyp, Hyp::Floating(..)) && hyp.address() == address)?;
+            if vars.contains(&var) {
+                return None;
+            }
+            vars.push(var);
+        }
+        let mut stack = vec![body];
+        while let Some(ix) = stack.pop() {
+            let tree = &item.arr.trees[ix];
+            if tree.address == lhs.address {
+                return None;
+            }
+            stack.extend(&tree.children);
+        }
+        let var_index = |ix: usize| match hyps[ix] {
+            Hyp::Floating(_, var, _) => var,
+            Hyp::Essential(..) => unreachable!(),
+        };
+        let dummies = (0..hyps.len())
+            .filter(|ix| !vars.contains(ix))
+            .collect::<Vec<_>>();
+        for &dummy in &dummies {
+            let all_disjoint =
+                (vars.iter()).all(|&var| disjoint(item.frame, var_index(dummy), var_index(var)));
+            if !self.is_bound(&hyps[dummy]) || !all_disjoint {
+                return None;
+            }
+        }
+        let args = (self.order(self.frame(lhs.address).ok()?).into_iter())
+            .map(|arg| vars[arg])
+            .collect();
+        Some(Definition {
+            term: lhs.address,
+            args,
+            dummies,
+            body,
+        })
+    }
+
+    /// Writes a definition.
+    fn write_definition(
+        &mut self,
+        item: &Item<'_>,
+        def: &Definition,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        self.pending_terms.retain(|&address| address != def.term);
+        let binders = self.binders(item.frame, &def.args, false);
+        let dummies = self.binders(item.frame, &def.dummies, false);
+        let name = self.name(def.term);
+        let sort = self.sort_name(self.typecode(def.term));
+        let deps = Self::return_deps(&binders);
+        let mut args = String::new();
+        self.write_binders(&mut args, &binders, Style::Math, false);
+        self.write_binders(&mut args, &dummies, Style::Math, true);
+        let body = self.math(&item.arr, def.body);
+        writeln!(mm0, "def {name}{args}: {sort}{} = {body};", prefixed(&deps))?;
+        args.clear();
+        self.write_binders(&mut args, &binders, Style::SExpr, false);
+        let mut dummy_args = String::new();
+        self.write_binders(&mut dummy_args, &dummies, Style::SExpr, false);
+        writeln!(
+            mmu,
+            "(def {name} ({}) ({sort} ({})) ({}) {})",
+            args.trim_start(),
+            deps.join(" "),
+            dummy_args.trim_start(),
+            self.sexpr(&item.arr, def.body),
+        )?;
+        Ok(())
+    }
+
+    /// Writes the proof of a theorem, binding the steps used several times with
+    /// `:let`.
+    fn write_proof(&self, out: &mut String, arr: &ProofTreeArray, proof: usize) {
+        // Count the references to the proof steps, and list them children first
+        fn visit(
+            exporter: &Mm0Exporter<'_>,
+            arr: &ProofTreeArray,
+            ix: usize,
+            refs: &mut HashMap<usize, usize>,
+            steps: &mut Vec<usize>,
+        ) {
+            let count = refs.entry(ix).or_default();
+            *count += 1;
+            if *count > 1 {
+                return;
+            }
+            let tree = &arr.trees[ix];
+            let stmt = exporter.db.statement_by_address(tree.address);
+            if !stmt.is_assertion() {
+                return;
+            }
+            let Ok(frame) = exporter.frame(tree.address) else {
+                return;
+            };
+            for (hyp, &child) in frame.hypotheses.iter().zip(&tree.children) {
+                if let Hyp::Essential(..) = hyp {
+                    visit(exporter, arr, child, refs, steps);
+                }
+            }
+            steps.push(ix);
+        }
+        let mut refs = HashMap::default();
+        let mut steps = vec![];
+        visit(self, arr, proof, &mut refs, &mut steps);
+        let mut lets = HashMap::default();
+        let mut count = 0;
+        for ix in steps {
+            if refs[&ix] < 2 {
+                continue;
+            }
+            let name = loop {
+                count += 1;
+                let name = format!("_{count}");
+                if !self.used_names.contains(&name) {
+                    break name;
+                }
+            };
+            let _ = write!(out, "(:let {name} ");
+            self.write_expr(out, arr, ix, Style::SExpr, &lets, true);
+            out.push(' ');
+            lets.insert(ix, name);
+        }
+        self.write_expr(out, arr, proof, Style::SExpr, &lets, true);
+        for _ in 0..lets.len() {
+            out.push(')');
+        }
+    }
+
+    /// Writes an axiom or theorem.
+    fn write_assertion(
+        &self,
+        item: &Item<'_>,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        let name = self.name(item.stmt.address());
+        let binders = self.binders(item.frame, &self.order(item.frame), false);
+        let keyword = if item.proof.is_some() {
+            "theorem"
+        } else {
+            "axiom"
+        };
+        let mut args = String::new();
+        self.write_binders(&mut args, &binders, Style::Math, false);
+        for &(address, ix) in &item.hyps {
+            let _ = write!(
+                args,
+                " ({}: {})",
+                self.name(address),
+                self.math(&item.arr, ix)
+            );
+        }
+        let conclusion = self.math(&item.arr, item.conclusion);
+        writeln!(mm0, "{keyword} {name}{args}: {conclusion};")?;
+
+        args.clear();
+        self.write_binders(&mut args, &binders, Style::SExpr, false);
+        let hyps = (item.hyps.iter())
+            .map(|&(address, ix)| format!("({} {})", self.name(address), self.sexpr(&item.arr, ix)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let conclusion = self.sexpr(&item.arr, item.conclusion);
+        write!(
+            mmu,
+            "({keyword} {name} ({}) ({hyps}) {conclusion}",
+            args.trim_start()
+        )?;
+        if let Some(proof) = item.proof {
+            let frame_vars = (item.frame.hypotheses.iter())
+                .map(Hyp::address)
+                .collect::<HashSet<_>>();
+            let mut dummies = vec![];
+            for tree in &item.arr.trees {
+                let stmt = self.db.statement_by_address(tree.address);
+                if stmt.statement_type() == StatementType::Floating
+                    && !frame_vars.contains(&tree.address)
+                    && !dummies.contains(&tree.address)
+                {
+                    dummies.push(tree.address);
+                }
+            }
+            let dummies = (dummies.iter())
+                .map(|&address| {
+                    format!(
+                        "({} {})",
+                        self.name(address),
+                        self.sort_name(self.typecode(address))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut out = String::new();
+            self.write_proof(&mut out, &item.arr, proof);
+            write!(mmu, " ({dummies}) {out}")?;
+        }
+        writeln!(mmu, ")")?;
+        Ok(())
+    }
+
+    /// Writes the sorts, terms, definitions, axioms and theorems of the
+    /// statements in `keep`, or of the whole database.
+    fn write(
+        &mut self,
+        keep: Option<&HashSet<StatementAddress>>,
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        self.write_sorts(mm0, mmu)?;
+        let scope = self.db.scope_result();
+        for stmt in self.db.statements() {
+            if !stmt.is_assertion() || scope.get(stmt.label()).is_none() {
+                continue;
+            }
+            let syntax = self.is_syntax(stmt);
+            if syntax && stmt.statement_type() == StatementType::Axiom {
+                self.pending_terms.push(stmt.address());
+            }
+            if syntax || keep.is_some_and(|keep| !keep.contains(&stmt.address())) {
+                continue;
+            }
+            let item = self.item(stmt)?;
+            let def = (stmt.statement_type() == StatementType::Axiom
+                && stmt.label().starts_with(b"df-"))
+            .then(|| self.definition(&item))
+            .flatten();
+            if let Some(def) = def {
+                self.flush_terms(&item.arr, Some(def.term), mm0, mmu)?;
+                self.write_definition(&item, &def, mm0, mmu)?;
+            }
+            self.flush_terms(&item.arr, None, mm0, mmu)?;
+            self.write_assertion(&item, mm0, mmu)?;
+        }
+        for address in std::mem::take(&mut self.pending_terms) {
+            if keep.is_none_or(|keep| keep.contains(&address)) {
+                self.write_term(address, mm0, mmu)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Exports the database to Metamath Zero, writing the specification to `mm0`
+    /// and the proofs to `mmu`, as described in [`crate::mm0`].
+    ///
+    /// If `stmts` is not empty, only these statements and everything they
+    /// transitively depend on are exported.
+    ///
+    /// Fails if the typecodes are not declared with a `$j syntax` command.
+    ///
+    /// Requires: [`Database::scope_pass`], [`Database::stmt_parse_pass`]
+    pub fn write_mm0(
+        &self,
+        stmts: &[StatementRef<'_>],
+        mm0: &mut impl io::Write,
+        mmu: &mut impl io::Write,
+    ) -> Result<(), ExportError> {
+        if self.grammar_result().typecodes().is_empty() {
+            return Err(Diagnostic::GrammarCantBuild(
+                "No typecodes declared with a `$j syntax` command",
+            )
+            .into());
+        }
+        let keep = (!stmts.is_empty()).then(|| self.extract_dependencies(stmts));
+        Mm0Exporter::new(self).write(keep.as_ref(), mm0, mmu)
+    }
+}
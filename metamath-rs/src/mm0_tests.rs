@@ -0,0 +1,124 @@
+use crate::grammar_tests::mkdb;
+
+const MM0_DB: &[u8] = b"
+  $( $j syntax 'setvar'; syntax 'wff'; syntax '|-' as 'wff'; $)
+  $c ( ) -> -. <-> A. E. wff setvar |- $.
+  $v ph ps x y $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  vx $f setvar x $.
+  vy $f setvar y $.
+  wn $a wff -. ph $.
+  wi $a wff ( ph -> ps ) $.
+  wb $a wff ( ph <-> ps ) $.
+  wal $a wff A. x ph $.
+  wex $a wff E. x ph $.
+  wnn $p wff -. -. ph $= wph wn wn $.
+  ${
+    $d x y $. $d y ph $.
+    df-ex $a |- ( E. x ph <-> -. A. y -. A. x ph ) $.
+  $}
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ax-nn $a |- ( -. -. ph -> ph ) $.
+  ${
+    ax-dup.1 $e |- ph $.
+    ax-dup.2 $e |- ph $.
+    ax-dup $a |- ( ph -> ph ) $.
+  $}
+  ${
+    a1i.1 $e |- ph $.
+    a1i $p |- ( ps -> ph ) $= wph wps wph wi a1i.1 wph wps ax-1 ax-mp $.
+  $}
+  ${
+    th.1 $e |- ph $.
+    th $p |- ( ( ps -> ph ) -> ( ps -> ph ) ) $=
+      wps wph wi wph wps th.1 a1i wph wps th.1 a1i ax-dup $.
+  $}
+  nn $p |- ( -. -. -. -. ph -> -. -. ph ) $= wph wnn ax-nn $.
+  ax-gen $a |- ( A. x ph -> ph ) $.
+";
+
+fn export(labels: &[&[u8]]) -> (String, String) {
+    let mut db = mkdb(MM0_DB);
+    db.scope_pass();
+    db.stmt_parse_pass();
+    let stmts = (labels.iter())
+        .map(|&label| db.statement(label).unwrap())
+        .collect::<Vec<_>>();
+    let mut mm0 = vec![];
+    let mut mmu = vec![];
+    db.write_mm0(&stmts, &mut mm0, &mut mmu).unwrap();
+    (
+        String::from_utf8(mm0).unwrap(),
+        String::from_utf8(mmu).unwrap(),
+    )
+}
+
+#[test]
+fn test_mm0() {
+    let (mm0, mmu) = export(&[]);
+    assert_eq!(
+        mm0,
+        "sort setvar;
+provable sort wff;
+term wn (wph: wff): wff;
+term wb (wph: wff) (wps: wff): wff;
+term wal {vx: setvar} (wph: wff vx): wff;
+def wex {vx: setvar} (wph: wff vx) {.vy: setvar}: wff = $ wn ( wal vy ( wn ( wal vx wph ) ) ) $;
+axiom df_ex {vx: setvar} {vy: setvar} (wph: wff vx): $ wb ( wex vx wph ) ( wn ( wal vy ( wn ( wal vx wph ) ) ) ) $;
+term wi (wph: wff) (wps: wff): wff;
+axiom ax_mp (wph: wff) (wps: wff) (min: $ wph $) (maj: $ wi wph wps $): $ wps $;
+axiom ax_1 (wph: wff) (wps: wff): $ wi wph ( wi wps wph ) $;
+axiom ax_nn (wph: wff): $ wi ( wn ( wn wph ) ) wph $;
+axiom ax_dup (wph: wff) (ax_dup_1: $ wph $) (ax_dup_2: $ wph $): $ wi wph wph $;
+theorem a1i (wph: wff) (wps: wff) (a1i_1: $ wph $): $ wi wps wph $;
+theorem th (wph: wff) (wps: wff) (th_1: $ wph $): $ wi ( wi wps wph ) ( wi wps wph ) $;
+theorem nn (wph: wff): $ wi ( wn ( wn ( wn ( wn wph ) ) ) ) ( wn ( wn wph ) ) $;
+axiom ax_gen {vx: setvar} (wph: wff vx): $ wi ( wal vx wph ) wph $;
+"
+    );
+    assert_eq!(
+        mmu,
+        "(sort setvar)
+(sort wff provable)
+(term wn ((wph wff ())) (wff ()))
+(term wb ((wph wff ()) (wps wff ())) (wff ()))
+(term wal ((vx setvar) (wph wff (vx))) (wff ()))
+(def wex ((vx setvar) (wph wff (vx))) (wff ()) ((vy setvar)) (wn (wal vy (wn (wal vx wph)))))
+(axiom df_ex ((vx setvar) (vy setvar) (wph wff (vx))) () (wb (wex vx wph) (wn (wal vy (wn (wal vx wph))))))
+(term wi ((wph wff ()) (wps wff ())) (wff ()))
+(axiom ax_mp ((wph wff ()) (wps wff ())) ((min wph) (maj (wi wph wps))) wps)
+(axiom ax_1 ((wph wff ()) (wps wff ())) () (wi wph (wi wps wph)))
+(axiom ax_nn ((wph wff ())) () (wi (wn (wn wph)) wph))
+(axiom ax_dup ((wph wff ())) ((ax_dup_1 wph) (ax_dup_2 wph)) (wi wph wph))
+(theorem a1i ((wph wff ()) (wps wff ())) ((a1i_1 wph)) (wi wps wph) () \
+(ax_mp wph (wi wps wph) a1i_1 (ax_1 wph wps)))
+(theorem th ((wph wff ()) (wps wff ())) ((th_1 wph)) (wi (wi wps wph) (wi wps wph)) () \
+(:let _1 (a1i wph wps th_1) (ax_dup (wi wps wph) _1 _1)))
+(theorem nn ((wph wff ())) () (wi (wn (wn (wn (wn wph)))) (wn (wn wph))) () (ax_nn (wn (wn wph))))
+(axiom ax_gen ((vx setvar) (wph wff (vx))) () (wi (wal vx wph) wph))
+"
+    );
+}
+
+#[test]
+fn test_mm0_extract() {
+    let (mm0, _) = export(&[b"th"]);
+    assert_eq!(
+        mm0,
+        "sort setvar;
+provable sort wff;
+term wi (wph: wff) (wps: wff): wff;
+axiom ax_mp (wph: wff) (wps: wff) (min: $ wph $) (maj: $ wi wph wps $): $ wps $;
+axiom ax_1 (wph: wff) (wps: wff): $ wi wph ( wi wps wph ) $;
+axiom ax_dup (wph: wff) (ax_dup_1: $ wph $) (ax_dup_2: $ wph $): $ wi wph wph $;
+theorem a1i (wph: wff) (wps: wff) (a1i_1: $ wph $): $ wi wps wph $;
+theorem th (wph: wff) (wps: wff) (th_1: $ wph $): $ wi ( wi wps wph ) ( wi wps wph ) $;
+"
+    );
+}
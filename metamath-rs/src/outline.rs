@@ -17,11 +17,13 @@ use crate::statement::StatementIndex;
 use crate::statement::Token;
 use crate::tree::NodeId;
 use crate::tree::Tree;
+use crate::util::write_json_string;
 use crate::Database;
 use crate::StatementRef;
 use crate::StatementType;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::io;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -363,11 +365,87 @@ impl<'a> OutlineNodeRef<'a> {
 
     // TODO(tirix) Getters for next and previous references in the database order
 
-    // TODO(tirix)
-    // /// Returns the chapter number
-    // pub fn get_chapter_numbers(&self) -> impl Iterator<Item = usize> + '_ {
-    //     vec![0].into_iter()
-    // }
+    /// Returns the chapter numbers of this node, like `[5, 3, 2]` for section 5.3.2,
+    /// each number being the position of a chapter among its siblings, starting at 1.
+    /// The root node has no numbers, and a statement has the numbers of its chapter.
+    #[must_use]
+    pub fn get_chapter_numbers(&self) -> Vec<usize> {
+        match *self {
+            OutlineNodeRef::Chapter {
+                database,
+                mut node_id,
+            } => {
+                let tree = &database.outline_result().tree;
+                let mut numbers = vec![];
+                while tree[node_id].level != HeadingLevel::Database {
+                    let parent = tree[node_id].parent;
+                    let position = tree.children_iter(parent).position(|id| id == node_id);
+                    numbers.push(position.map_or(0, |position| position + 1));
+                    node_id = parent;
+                }
+                numbers.reverse();
+                numbers
+            }
+            OutlineNodeRef::Statement { .. } => self
+                .parent()
+                .map(|chapter| chapter.get_chapter_numbers())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the chapter numbers of this node joined with dots, like `5.3.2`,
+    /// or an empty string for the root node.
+    #[must_use]
+    pub fn get_chapter_number(&self) -> String {
+        let numbers = self.get_chapter_numbers();
+        numbers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Returns the chapters containing this node, from the outermost one,
+    /// excluding the root node. For a chapter, the last one is the chapter itself.
+    #[must_use]
+    pub fn breadcrumb(&self) -> Vec<OutlineNodeRef<'a>> {
+        let mut chapters = self
+            .ancestors_iter()
+            .filter(|node| {
+                matches!(node, OutlineNodeRef::Chapter { .. })
+                    && node.get_level() != HeadingLevel::Database
+            })
+            .collect::<Vec<_>>();
+        chapters.reverse();
+        chapters
+    }
+
+    /// Returns the number of assertions directly in this chapter, outside of its sub-chapters.
+    #[must_use]
+    pub fn statement_count(&self) -> usize {
+        match *self {
+            OutlineNodeRef::Chapter { database, node_id } => {
+                ChapterStatementIter::new(database, node_id).count()
+            }
+            OutlineNodeRef::Statement { .. } => 0,
+        }
+    }
+
+    /// Returns the number of assertions in this chapter, including its sub-chapters.
+    #[must_use]
+    pub fn total_statement_count(&self) -> usize {
+        self.statement_count()
+            + self
+                .chapters()
+                .map(|chapter| chapter.total_statement_count())
+                .sum::<usize>()
+    }
+
+    /// Returns the sub-chapters of this node.
+    fn chapters(&self) -> impl Iterator<Item = OutlineNodeRef<'a>> {
+        self.children_iter()
+            .filter(|child| matches!(child, OutlineNodeRef::Chapter { .. }))
+    }
 
     // TODO(tirix): it would be nice to also have a method returning the heading chapter comment,
     // if there is any.
@@ -375,9 +453,13 @@ impl<'a> OutlineNodeRef<'a> {
 
 impl Display for OutlineNodeRef<'_> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        // TODO(tirix) Complete with chapter numbers
-        // fmt.write_str(&self.get_chapter_numbers().map(|i| { i.to_string() }).collect::<Vec<String>>().join("."))?;
-        // fmt.write_str(" ")?;
+        if let OutlineNodeRef::Chapter { .. } = self {
+            let number = self.get_chapter_number();
+            if !number.is_empty() {
+                fmt.write_str(&number)?;
+                fmt.write_str(" ")?;
+            }
+        }
         fmt.write_str(self.get_name())
     }
 }
@@ -450,6 +532,101 @@ impl Database {
             node_id: chapter_ref,
         }
     }
+
+    /// Returns the smallest chapter containing the statement with the given label,
+    /// which is the root node if the statement precedes all headings.
+    /// Its [`OutlineNodeRef::breadcrumb`] gives the numbered path to the statement.
+    /// Requires: [`Database::name_pass`], [`Database::outline_pass`]
+    #[must_use]
+    pub fn statement_chapter(&self, label: &[u8]) -> Option<OutlineNodeRef<'_>> {
+        self.get_outline_node(self.statement(label)?).parent()
+    }
+
+    /// Writes the outline as a JSON tree of chapters, starting with the root node.
+    ///
+    /// Each chapter is an object with its `number` (like `"5.3.2"`), `level`, `name`,
+    /// the number of `statements` directly in it, the `total_statements` including
+    /// its sub-chapters, and the list of its sub-chapters as `children`.
+    /// Requires: [`Database::outline_pass`]
+    pub fn write_outline_json(&self, out: &mut impl io::Write) -> io::Result<()> {
+        write_outline_json_node(self.root_outline_node(), 0, out)?;
+        writeln!(out)
+    }
+
+    /// Writes the outline as a nested Markdown list of numbered chapters,
+    /// with their statement counts.
+    /// Requires: [`Database::outline_pass`]
+    pub fn write_outline_markdown(&self, out: &mut impl io::Write) -> io::Result<()> {
+        let root = self.root_outline_node();
+        writeln!(out, "# {root}")?;
+        writeln!(out)?;
+        write_statement_counts(root, out)?;
+        writeln!(out)?;
+        writeln!(out)?;
+        for chapter in root.chapters() {
+            write_outline_markdown_node(chapter, 0, out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a chapter of the outline and its sub-chapters as JSON, see
+/// [`Database::write_outline_json`].
+fn write_outline_json_node(
+    node: OutlineNodeRef<'_>,
+    indent: usize,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    write!(
+        out,
+        "{{\"number\": \"{}\", \"level\": \"{:?}\", \"name\": ",
+        node.get_chapter_number(),
+        node.get_level()
+    )?;
+    write_json_string(out, node.get_name().as_bytes())?;
+    write!(
+        out,
+        ", \"statements\": {}, \"total_statements\": {}, \"children\": [",
+        node.statement_count(),
+        node.total_statement_count()
+    )?;
+    for (i, chapter) in node.chapters().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        write!(out, "{sep}\n{:indent$}", "", indent = 2 * indent + 2)?;
+        write_outline_json_node(chapter, indent + 1, out)?;
+    }
+    if node.chapters().next().is_some() {
+        write!(out, "\n{:indent$}", "", indent = 2 * indent)?;
+    }
+    write!(out, "]}}")
+}
+
+/// Writes the statement counts of a chapter, for [`Database::write_outline_markdown`].
+fn write_statement_counts(node: OutlineNodeRef<'_>, out: &mut impl io::Write) -> io::Result<()> {
+    let total = node.total_statement_count();
+    let count = node.statement_count();
+    let plural = if total == 1 { "" } else { "s" };
+    write!(out, "{total} statement{plural}")?;
+    if count != total {
+        write!(out, ", {count} outside of sub-chapters")?;
+    }
+    Ok(())
+}
+
+/// Writes a chapter of the outline and its sub-chapters as Markdown list items,
+/// see [`Database::write_outline_markdown`].
+fn write_outline_markdown_node(
+    node: OutlineNodeRef<'_>,
+    indent: usize,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    write!(out, "{:indent$}- {node} (", "", indent = 2 * indent)?;
+    write_statement_counts(node, out)?;
+    writeln!(out, ")")?;
+    for chapter in node.chapters() {
+        write_outline_markdown_node(chapter, indent + 1, out)?;
+    }
+    Ok(())
 }
 
 impl Outline {
@@ -0,0 +1,96 @@
+use crate::grammar_tests::mkdb;
+use crate::outline::OutlineNodeRef;
+
+const OUTLINE_DB: &[u8] = b"$c wff |- $.
+$v ph $.
+wph $f wff ph $.
+ax-0 $a |- ph $.
+$(
+####
+  Part A
+####
+$)
+$(
+#*#*#*#*
+  Chapter A1
+#*#*#*#*
+$)
+ax-1 $a |- ph $.
+ax-2 $a |- ph $.
+$(
+#*#*#*#*
+  Chapter A2
+#*#*#*#*
+$)
+$(
+=-=-=-=-
+  Section A2a
+=-=-=-=-
+$)
+ax-3 $a |- ph $.
+$(
+####
+  Part B
+####
+$)
+ax-4 $a |- ph $.
+";
+
+#[test]
+fn test_chapter_numbers() {
+    let mut db = mkdb(OUTLINE_DB);
+    db.name_pass();
+    db.outline_pass();
+    let chapter = db.statement_chapter(b"ax-3").unwrap();
+    assert_eq!(chapter.get_chapter_numbers(), [1, 2, 1]);
+    assert_eq!(chapter.to_string(), "1.2.1 Section A2a");
+    let breadcrumb = (chapter.breadcrumb().iter())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        breadcrumb,
+        ["1 Part A", "1.2 Chapter A2", "1.2.1 Section A2a"]
+    );
+    let chapter = db.statement_chapter(b"ax-4").unwrap();
+    assert_eq!(chapter.to_string(), "2 Part B");
+    let statement = OutlineNodeRef::statement_node(&db, db.statement(b"ax-2").unwrap());
+    assert_eq!(statement.get_chapter_number(), "1.1");
+    assert_eq!(statement.to_string(), "ax-2");
+    assert!(db.root_outline_node().get_chapter_numbers().is_empty());
+}
+
+#[test]
+fn test_outline_export() {
+    let mut db = mkdb(OUTLINE_DB);
+    db.outline_pass();
+    let mut json = vec![];
+    db.write_outline_json(&mut json).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        r#"{"number": "", "level": "Database", "name": "Database", "statements": 1, "total_statements": 5, "children": [
+  {"number": "1", "level": "MajorPart", "name": "Part A", "statements": 0, "total_statements": 3, "children": [
+    {"number": "1.1", "level": "Section", "name": "Chapter A1", "statements": 2, "total_statements": 2, "children": []},
+    {"number": "1.2", "level": "Section", "name": "Chapter A2", "statements": 0, "total_statements": 1, "children": [
+      {"number": "1.2.1", "level": "SubSection", "name": "Section A2a", "statements": 1, "total_statements": 1, "children": []}
+    ]}
+  ]},
+  {"number": "2", "level": "MajorPart", "name": "Part B", "statements": 1, "total_statements": 1, "children": []}
+]}
+"#
+    );
+    let mut markdown = vec![];
+    db.write_outline_markdown(&mut markdown).unwrap();
+    assert_eq!(
+        String::from_utf8(markdown).unwrap(),
+        "# Database
+
+5 statements, 1 outside of sub-chapters
+
+- 1 Part A (3 statements, 0 outside of sub-chapters)
+  - 1.1 Chapter A1 (2 statements)
+  - 1.2 Chapter A2 (1 statement, 0 outside of sub-chapters)
+    - 1.2.1 Section A2a (1 statement)
+- 2 Part B (1 statement)
+"
+    );
+}
@@ -31,6 +31,7 @@ use std::fmt::Debug;
 use std::mem;
 use std::str;
 use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 /// State used by the scanning process
 #[derive(Default)]
@@ -589,6 +590,7 @@ impl Scanner<'_> {
             outline: Vec::new(),
             t_commands: Vec::new(),
             j_commands: Vec::new(),
+            parse_time: Duration::ZERO,
         };
         let mut top_group = NO_STATEMENT;
         let is_end;
@@ -981,7 +983,9 @@ pub fn parse_segments(input: &BufferRef) -> Vec<Arc<Segment>> {
     assert!(input.len() < FilePos::MAX as usize);
 
     loop {
-        let (seg, last) = scanner.get_segment();
+        let now = Instant::now();
+        let (mut seg, last) = scanner.get_segment();
+        seg.parse_time = now.elapsed();
         // we can almost use seg.next_file == Span::null here, but for the error
         // case
         closed_spans.push(Arc::new(seg));
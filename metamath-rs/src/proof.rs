@@ -16,6 +16,15 @@ use std::fmt::{self, Write};
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 
+#[cfg(feature = "dot")]
+use crate::export::ExportError;
+#[cfg(feature = "dot")]
+use crate::grammar::escape;
+#[cfg(feature = "dot")]
+use dot_writer::{Attributes, Color, DotWriter};
+#[cfg(feature = "dot")]
+use std::fs::File;
+
 /// A tree structure for storing proofs and grammar derivations.
 #[derive(Clone, Debug, Eq)]
 pub struct ProofTree {
@@ -303,6 +312,57 @@ impl ProofTreeArray {
     pub fn exprs(&self) -> Option<&[Vec<u8>]> {
         self.exprs.as_deref()
     }
+
+    #[cfg(feature = "dot")]
+    /// Exports the proof tree in the "dot" format, with one node per step, labeled
+    /// with the assertion applied, and with an edge to each of its hypotheses.
+    /// Shared subproofs are drawn only once, and the hypotheses are drawn in red.
+    /// The expression of each step is added to its node if `show_exprs` is set and
+    /// the expressions were built, and the syntax steps are omitted unless `show_syntax` is set.
+    /// This dot file can then be converted to an SVG image using ` dot -Tsvg -o proof.svg proof.dot `
+    pub fn export_dot(
+        &self,
+        db: &Database,
+        show_exprs: bool,
+        show_syntax: bool,
+        write: &mut File,
+    ) -> Result<(), ExportError> {
+        let mut shown = vec![show_syntax; self.trees.len()];
+        if !show_syntax {
+            for cur in self.with_logical_steps(db, |cur, _, _, _| cur) {
+                shown[cur] = true;
+            }
+        }
+        let exprs = self.exprs().filter(|_| show_exprs);
+        let mut dot_writer = DotWriter::from(write);
+        let mut digraph = dot_writer.digraph();
+        for (cur, tree) in self.trees.iter().enumerate().filter(|&(cur, _)| shown[cur]) {
+            let stmt = db.statement_by_address(tree.address);
+            let mut label = escape(as_str(stmt.label()));
+            if let Some(exprs) = exprs {
+                write!(
+                    label,
+                    "\\n{}{}",
+                    escape(as_str(stmt.math_at(0).slice)),
+                    escape(as_str(&exprs[cur]))
+                )?;
+            }
+            let mut node = digraph.node_named(cur.to_string());
+            node.set_label(&label);
+            if matches!(
+                stmt.statement_type(),
+                StatementType::Essential | StatementType::Floating
+            ) {
+                node.set_color(Color::Red).set_font_color(Color::Red);
+            }
+        }
+        for (cur, tree) in self.trees.iter().enumerate().filter(|&(cur, _)| shown[cur]) {
+            for &child in tree.children.iter().filter(|&&child| shown[child]) {
+                digraph.edge(cur.to_string(), child.to_string());
+            }
+        }
+        Ok(())
+    }
 }
 
 /// An iterator which loops over the steps of the proof in tree order
@@ -0,0 +1,73 @@
+use crate::grammar::escape;
+use crate::grammar_tests::mkdb;
+use crate::proof::ProofTreeArray;
+use crate::Database;
+use std::fs::{self, File};
+
+const DOT_DB: &[u8] = b"$c |- wff ( ) -> $.
+  $v ph ps $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wi $a wff ( ph -> ps ) $.
+  ${ min $e |- ph $. maj $e |- ( ph -> ps ) $. ax-mp $a |- ps $. $}
+  ${
+    h1 $e |- ph $.
+    h2 $e |- ( ph -> ps ) $.
+    th1 $p |- ps $= wph wps h1 h2 ax-mp $.
+  $}
+";
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape(r#"a\b"c"#), r#"a\\b\"c"#);
+}
+
+/// Exports the proof of `th1` in the dot format, and returns the output
+/// without whitespace, with the database and the proof tree.
+fn export_dot(show_exprs: bool, show_syntax: bool) -> (String, Database, ProofTreeArray) {
+    let mut db = mkdb(DOT_DB);
+    db.scope_pass();
+    let arr = db.get_proof_tree(db.statement(b"th1").unwrap()).unwrap();
+    let path = std::env::temp_dir().join(format!("metamath-rs-dot-{}.dot", std::process::id()));
+    arr.export_dot(
+        &db,
+        show_exprs,
+        show_syntax,
+        &mut File::create(&path).unwrap(),
+    )
+    .unwrap();
+    let dot = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let dot = dot.chars().filter(|c| !c.is_whitespace()).collect();
+    (dot, db, arr)
+}
+
+#[test]
+fn test_export_dot() {
+    let (dot, db, arr) = export_dot(false, false);
+    let index = |label: &[u8]| {
+        (arr.trees.iter())
+            .position(|tree| db.statement_by_address(tree.address).label() == label)
+            .unwrap()
+    };
+    let (h1, h2, qed) = (index(b"h1"), index(b"h2"), index(b"ax-mp"));
+    assert!(dot.contains(&format!("{h1}[label=\"h1\"")));
+    assert!(dot.contains(&format!("{h2}[label=\"h2\"")));
+    assert!(dot.contains(&format!("{qed}[label=\"ax-mp\"")));
+    assert!(!dot.contains("label=\"wph\""));
+    assert!(dot.contains(&format!("{qed}->{h1}")));
+    assert!(dot.contains(&format!("{qed}->{h2}")));
+    assert_eq!(dot.matches("->").count(), 2);
+    assert_eq!(dot.matches("fontcolor=red").count(), 2);
+
+    // With the syntax steps and the expressions
+    let (dot, db, arr) = export_dot(true, true);
+    assert_eq!(dot.matches("[label=").count(), arr.trees.len());
+    assert!(dot.contains("label=\"h2\\n|-(ph->ps)\""));
+    assert!(dot.contains("label=\"wph\\nwffph\""));
+    assert!(dot.contains("label=\"ax-mp\\n|-ps\""));
+    let qed = (arr.trees.iter())
+        .position(|tree| db.statement_by_address(tree.address).label() == b"ax-mp")
+        .unwrap();
+    assert_eq!(dot.matches(&format!(";{qed}->")).count(), 4);
+}
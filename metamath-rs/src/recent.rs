@@ -0,0 +1,117 @@
+//! Generation of the list of most recent contributions.
+//!
+//! This reproduces the contents of the `mmrecent.html` page of
+//! [`metamath.exe`](https://github.com/metamath/metamath-exe), which lists the most
+//! recently contributed or revised theorems, based on the `(Contributed by ...)`
+//! and `(Revised by ...)` parentheticals of their comments.
+//! See [`crate::html`] for the HTML rendering of this list.
+
+use crate::comment_parser::{Date, Parenthetical};
+use crate::database::time;
+use crate::statement::TokenPtr;
+use crate::util::write_json_string;
+use crate::{Database, StatementRef};
+use std::io::{self, Write};
+
+/// The default number of entries in the list of most recent contributions.
+pub const RECENT_COUNT: usize = 100;
+
+/// An entry of the list of most recent contributions.
+#[derive(Debug, Clone, Copy)]
+pub struct RecentEntry<'a> {
+    /// The contributed or revised statement.
+    pub stmt: StatementRef<'a>,
+    /// The most recent of the contribution and revision dates.
+    pub date: Date,
+    /// The author of the `(Contributed by ...)` parenthetical, if any.
+    pub contributor: Option<TokenPtr<'a>>,
+    /// The author and date of the most recent `(Revised by ...)` parenthetical, if any.
+    pub revised: Option<(TokenPtr<'a>, Date)>,
+}
+
+impl<'a> RecentEntry<'a> {
+    /// Builds the entry for the given assertion, if its comment has any
+    /// contribution or revision dates.
+    fn new(stmt: StatementRef<'a>) -> Option<Self> {
+        let comment = stmt.associated_comment()?;
+        let buf = &**comment.segment().segment.buffer;
+        let mut contributed = None;
+        let mut revised: Option<(TokenPtr<'a>, Date)> = None;
+        for (_, parenthetical) in comment.parentheticals() {
+            match parenthetical {
+                Parenthetical::ContributedBy { author, date } => {
+                    if let Ok(date) = Date::try_from(date.as_ref(buf)) {
+                        contributed = Some((author.as_ref(buf), date));
+                    }
+                }
+                Parenthetical::RevisedBy { author, date } => {
+                    if let Ok(date) = Date::try_from(date.as_ref(buf)) {
+                        if revised.is_none_or(|(_, last)| date >= last) {
+                            revised = Some((author.as_ref(buf), date));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let date = contributed
+            .map(|(_, date)| date)
+            .max(revised.map(|(_, date)| date))?;
+        Some(RecentEntry {
+            stmt,
+            date,
+            contributor: contributed.map(|(author, _)| author),
+            revised,
+        })
+    }
+}
+
+impl Database {
+    /// Returns the `count` most recently contributed or revised assertions,
+    /// most recent first. Statements with the same date are listed in
+    /// reverse database order.
+    #[must_use]
+    pub fn recent_statements(&self, count: usize) -> Vec<RecentEntry<'_>> {
+        let mut entries: Vec<_> = self
+            .statements()
+            .filter(|stmt| stmt.is_assertion())
+            .filter_map(RecentEntry::new)
+            .collect();
+        // The sort is stable, so reversing first keeps later statements first.
+        entries.reverse();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.date));
+        entries.truncate(count);
+        entries
+    }
+
+    /// Writes the list of the `count` most recent contributions to the given writer,
+    /// as a JSON array.
+    pub fn write_recent_json(&self, count: usize, out: &mut impl Write) -> Result<(), io::Error> {
+        time(&self.options.clone(), "recent", || {
+            writeln!(out, "[")?;
+            for (i, entry) in self.recent_statements(count).iter().enumerate() {
+                let sep = if i == 0 { "" } else { ",\n" };
+                write!(out, "{sep}  {{\"label\": ")?;
+                write_json_string(out, entry.stmt.label())?;
+                write!(out, ", \"date\": \"{}\"", entry.date)?;
+                if let Some(contributor) = entry.contributor {
+                    write!(out, ", \"contributor\": ")?;
+                    write_json_string(out, contributor)?;
+                }
+                if let Some((author, date)) = entry.revised {
+                    write!(out, ", \"revised_by\": ")?;
+                    write_json_string(out, author)?;
+                    write!(out, ", \"revised\": \"{date}\"")?;
+                }
+                if let Some(comment) = entry.stmt.associated_comment() {
+                    let buf = &comment.segment().segment.buffer;
+                    write!(out, ", \"description\": ")?;
+                    write_json_string(out, comment.comment_contents().as_ref(buf).trim_ascii())?;
+                }
+                write!(out, "}}")?;
+            }
+            writeln!(out, "\n]")?;
+            Ok(())
+        })
+    }
+}
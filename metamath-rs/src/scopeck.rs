@@ -29,6 +29,7 @@
 //! segment, tracking the active `$e` and `$f` statements at each point.
 
 use crate::bit_set::Bitset;
+use crate::database::log;
 use crate::diag::Diagnostic;
 use crate::nameck::{Atom, NameReader, NameUsage, Nameset};
 use crate::segment::{Comparer, Segment, SegmentOrder, SegmentRef};
@@ -994,7 +995,10 @@ pub(crate) fn scope_check(result: &mut ScopeResult, segments: &SegmentSet, names
                     }
                 }
                 if segments2.options.trace_recalc {
-                    println!("scopeck({:?})", parser::guess_buffer_name(&sref.buffer));
+                    log(
+                        &segments2.options,
+                        format_args!("scopeck({:?})", parser::guess_buffer_name(&sref.buffer)),
+                    );
                 }
                 Some(Arc::new(scope_check_single(&segments2, &names, sref)))
             }));
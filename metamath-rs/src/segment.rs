@@ -4,6 +4,7 @@ use std::{
     cmp::Ordering,
     ops::{Bound, Deref, RangeBounds},
     sync::Arc,
+    time::Duration,
 };
 
 use crate::{
@@ -241,6 +242,8 @@ pub struct Segment {
     pub t_commands: Vec<(StatementIndex, Command)>,
     /// Parser commands provided in $j additional information comments.
     pub j_commands: Vec<(StatementIndex, Command)>,
+    /// The time it took to parse this segment.
+    pub parse_time: Duration,
 }
 
 /// A pointer to a segment which knows its identity.
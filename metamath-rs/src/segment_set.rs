@@ -49,7 +49,7 @@
 //! would make changing the beginning and end at the same time faster, and is
 //! attractive future work.
 
-use crate::database::{DbOptions, Executor, Promise};
+use crate::database::{log, DbOptions, Executor, Promise};
 use crate::diag::Diagnostic;
 use crate::segment::{Comparer, Segment, SegmentOrder, SegmentRef};
 use crate::statement::{SegmentId, StatementAddress};
@@ -114,6 +114,12 @@ impl SourceInfo {
             text,
         }
     }
+
+    /// The full text of the source file.
+    #[must_use]
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
 }
 
 /// The result of parsing one or more segments from a single slice of a source
@@ -319,11 +325,14 @@ impl SegmentSet {
                     let sres = SliceSR(Some(cachekey), eseg.clone(), srcinfo);
                     promises.push(Promise::new(sres));
                 } else {
-                    let trace = state.options.trace_recalc;
+                    let options = *state.options;
                     // parse it on a worker thread
                     promises.push(state.exec.exec(partbuf.len(), move || {
-                        if trace {
-                            println!("parse({:?})", parser::guess_buffer_name(&partbuf));
+                        if options.trace_recalc {
+                            log(
+                                &options,
+                                format_args!("parse({:?})", parser::guess_buffer_name(&partbuf)),
+                            );
                         }
                         SliceSR(Some(cachekey), parser::parse_segments(&partbuf), srcinfo)
                     }));
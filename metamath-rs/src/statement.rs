@@ -647,6 +647,29 @@ impl<'a> StatementRef<'a> {
         &self.segment.segment.span_pool[self.statement.proof_start..self.statement.proof_end]
     }
 
+    /// Returns the number of steps of this statement's proof, as counted by
+    /// metamath-exe: the number of tokens of a normal proof, or the number of
+    /// step references of a compressed proof.
+    #[must_use]
+    pub fn proof_step_count(&self) -> usize {
+        let buf = &self.segment.segment.buffer;
+        let spans = self.proof_spans();
+        if spans.first().map(|sp| sp.as_ref(buf)) == Some(b"(") {
+            let Some(end) = spans.iter().position(|sp| sp.as_ref(buf) == b")") else {
+                return 0;
+            };
+            (spans[end + 1..].iter())
+                .map(|sp| {
+                    (sp.as_ref(buf).iter())
+                        .filter(|ch| (b'A'..=b'T').contains(ch))
+                        .count()
+                })
+                .sum()
+        } else {
+            spans.len()
+        }
+    }
+
     /// Returns an iterator over the statements referenced in the proof.
     #[must_use]
     pub fn use_iter(&self) -> UseIter<'a> {
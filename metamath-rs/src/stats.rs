@@ -0,0 +1,285 @@
+//! Statistics about the database, written as a plain text report.
+//!
+//! The report has the number of statements of each type, the number of
+//! constants and variables of each typecode, the number of theorems in each
+//! part, chapter and section of the outline and in each mathbox, the
+//! distributions of the sizes of the proofs, the deepest proofs, the most
+//! referenced theorems, and the time it took to parse and verify each segment.
+
+use crate::database::time;
+use crate::outline::OutlineNodeRef;
+use crate::parser::HeadingLevel;
+use crate::proof::{ProofStyle, ProofTreeArray, ProofTreePrinter};
+use crate::segment::Comparer;
+use crate::statement::{StatementAddress, StatementRef, SymbolType};
+use crate::table::MathboxTracker;
+use crate::util::{HashMap, HashSet};
+use crate::{as_str, Database, StatementType};
+use std::io;
+use std::time::Duration;
+
+/// The number of proofs listed as the deepest ones, and of theorems listed as the most referenced ones.
+const TOP_COUNT: usize = 10;
+
+/// The names of the statement types, in the order they are reported.
+const STATEMENT_TYPES: [&str; 14] = [
+    "$c",
+    "$v",
+    "$f",
+    "$e",
+    "$d",
+    "$a",
+    "$p",
+    "${",
+    "$}",
+    "$[ $]",
+    "comments",
+    "headings",
+    "$t comments",
+    "$j comments",
+];
+
+/// Returns the index of the statement type in [`STATEMENT_TYPES`].
+const fn statement_type_index(stype: StatementType) -> Option<usize> {
+    Some(match stype {
+        StatementType::Constant => 0,
+        StatementType::Variable => 1,
+        StatementType::Floating => 2,
+        StatementType::Essential => 3,
+        StatementType::Disjoint => 4,
+        StatementType::Axiom => 5,
+        StatementType::Provable => 6,
+        StatementType::OpenGroup => 7,
+        StatementType::CloseGroup => 8,
+        StatementType::FileInclude => 9,
+        StatementType::Comment => 10,
+        StatementType::HeadingComment(_) => 11,
+        StatementType::TypesettingComment => 12,
+        StatementType::AdditionalInfoComment => 13,
+        StatementType::Eof | StatementType::Invalid => return None,
+    })
+}
+
+/// Formats a duration in milliseconds.
+fn millis(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+/// The sizes of a proof.
+struct ProofSize {
+    /// The number of steps of the normal proof, where shared subproofs are repeated.
+    normal: u64,
+    /// The number of step letters of the compressed proof, after its list of labels.
+    compressed: usize,
+    /// The length of the longest chain of logical steps.
+    depth: usize,
+}
+
+impl ProofSize {
+    fn new(db: &Database, stmt: StatementRef<'_>, arr: &ProofTreeArray) -> Self {
+        let mut normal = vec![0u64; arr.trees.len()];
+        for (ix, tree) in arr.trees.iter().enumerate() {
+            normal[ix] = (tree.children.iter())
+                .fold(1, |size: u64, &child| size.saturating_add(normal[child]));
+        }
+        // Logical steps are numbered from 1, so the depth of step `ix` is `depths[ix]`
+        let mut depths = vec![0];
+        for hyps in arr.with_logical_steps(db, |_, _, _, hyps| hyps) {
+            depths.push(1 + hyps.iter().map(|&hyp| depths[hyp]).max().unwrap_or(0));
+        }
+        let proof =
+            ProofTreePrinter::new(db, stmt.label(), ProofStyle::Compressed, arr).to_string();
+        let letters = proof
+            .rsplit_once(')')
+            .map_or(&*proof, |(_, letters)| letters);
+        Self {
+            normal: normal[arr.qed],
+            compressed: letters.chars().filter(|c| !c.is_whitespace()).count(),
+            depth: depths.into_iter().max().unwrap_or(0),
+        }
+    }
+}
+
+/// Writes the chapters of the outline down to the sections (`=-=-`), with their number of theorems.
+fn write_chapter_stats(
+    out: &mut impl io::Write,
+    node: OutlineNodeRef<'_>,
+    theorems: &HashMap<usize, usize>,
+) -> io::Result<()> {
+    for chapter in node.children_iter() {
+        if !matches!(chapter, OutlineNodeRef::Chapter { .. })
+            || chapter.get_level() > HeadingLevel::SubSection
+        {
+            continue;
+        }
+        let count = theorems
+            .get(&chapter.get_ref())
+            .copied()
+            .unwrap_or_default();
+        let indent = "  ".repeat(chapter.get_chapter_numbers().len() - 1);
+        let number = chapter.get_chapter_number();
+        writeln!(out, "{count:>10}  {indent}{number} {}", chapter.get_name())?;
+        write_chapter_stats(out, chapter, theorems)?;
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Writes a report of statistics about the database, described in [`crate::stats`].
+    /// The verification times are only reported if [`Database::verify_pass`] was run.
+    ///
+    /// Requires: [`Database::name_pass`], [`Database::scope_pass`], [`Database::outline_pass`]
+    pub fn write_stats(&self, out: &mut impl io::Write) -> io::Result<()> {
+        time(&self.options.clone(), "stats", || {
+            let nset = self.name_result();
+            let is_variable = |symbol| {
+                nset.lookup_symbol(symbol)
+                    .is_some_and(|symbol| symbol.stype == SymbolType::Variable)
+            };
+            let mut statements = [0; STATEMENT_TYPES.len()];
+            let mut typecodes = vec![];
+            let mut symbols = HashSet::default();
+            let mut chapter_theorems = HashMap::<usize, usize>::default();
+            let mut mathbox = MathboxTracker::default();
+            let mut mathbox_theorems = Vec::<(String, usize)>::new();
+            let mut uses = HashMap::<StatementAddress, usize>::default();
+            let mut proofs = vec![];
+            for stmt in self.statements() {
+                mathbox.update(stmt);
+                if let Some(ix) = statement_type_index(stmt.statement_type()) {
+                    statements[ix] += 1;
+                }
+                match stmt.statement_type() {
+                    StatementType::Floating => {
+                        let typecode = stmt.math_at(0).slice;
+                        if !typecodes.contains(&typecode) {
+                            typecodes.push(typecode);
+                        }
+                        symbols.insert((typecode, stmt.math_at(1).slice));
+                    }
+                    StatementType::Axiom if typecodes.contains(&stmt.math_at(0).slice) => {
+                        let typecode = stmt.math_at(0).slice;
+                        for token in stmt.math_iter().skip(1) {
+                            if !is_variable(token.slice) {
+                                symbols.insert((typecode, token.slice));
+                            }
+                        }
+                    }
+                    StatementType::Provable => {
+                        for chapter in self.get_outline_node(stmt).breadcrumb() {
+                            if chapter.get_level() <= HeadingLevel::SubSection {
+                                *chapter_theorems.entry(chapter.get_ref()).or_default() += 1;
+                            }
+                        }
+                        if !mathbox.owner.is_empty() {
+                            match mathbox_theorems.last_mut() {
+                                Some((owner, count)) if *owner == mathbox.owner => *count += 1,
+                                _ => mathbox_theorems.push((mathbox.owner.clone(), 1)),
+                            }
+                        }
+                        let mut referenced = HashSet::default();
+                        for (_, label) in stmt.use_iter() {
+                            if let Some(lookup) = nset.lookup_label(label) {
+                                referenced.insert(lookup.address);
+                            }
+                        }
+                        for address in referenced {
+                            *uses.entry(address).or_default() += 1;
+                        }
+                        if let Ok(arr) = ProofTreeArray::from_stmt(self, stmt, false) {
+                            proofs.push((stmt.address(), ProofSize::new(self, stmt, &arr)));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            writeln!(out, "Statements:")?;
+            for (count, name) in statements.iter().zip(STATEMENT_TYPES) {
+                writeln!(out, "{count:>10}  {name}")?;
+            }
+
+            writeln!(out, "\nConstants and variables per typecode:")?;
+            for typecode in typecodes {
+                let (mut constants, mut variables) = (0, 0);
+                for &(_, symbol) in symbols.iter().filter(|(tc, _)| *tc == typecode) {
+                    if is_variable(symbol) {
+                        variables += 1;
+                    } else {
+                        constants += 1;
+                    }
+                }
+                writeln!(out, "{constants:>10}{variables:>10}  {}", as_str(typecode))?;
+            }
+
+            writeln!(out, "\nTheorems per chapter:")?;
+            write_chapter_stats(out, OutlineNodeRef::root_node(self), &chapter_theorems)?;
+
+            writeln!(out, "\nTheorems per mathbox:")?;
+            for (owner, count) in mathbox_theorems {
+                writeln!(out, "{count:>10}  {owner}")?;
+            }
+
+            writeln!(out, "\nProof sizes (normal steps, compressed letters):")?;
+            let mut distribution = vec![];
+            for (_, size) in &proofs {
+                let buckets = [size.normal.ilog10(), size.compressed.max(1).ilog10()];
+                for (i, bucket) in buckets.into_iter().enumerate() {
+                    let bucket = bucket as usize;
+                    if distribution.len() <= bucket {
+                        distribution.resize(bucket + 1, [0; 2]);
+                    }
+                    distribution[bucket][i] += 1;
+                }
+            }
+            for (bucket, [normal, compressed]) in distribution.into_iter().enumerate() {
+                let low = 10u64.pow(bucket as u32);
+                let high = low.saturating_mul(10) - 1;
+                writeln!(out, "{normal:>10}{compressed:>10}  {low}-{high}")?;
+            }
+
+            writeln!(out, "\nDeepest proofs:")?;
+            proofs.sort_by_key(|(_, size)| std::cmp::Reverse(size.depth));
+            for (address, size) in proofs.iter().take(TOP_COUNT) {
+                let label = self.statement_by_address(*address).label();
+                writeln!(out, "{:>10}  {}", size.depth, as_str(label))?;
+            }
+
+            writeln!(out, "\nMost referenced theorems:")?;
+            let mut referenced = (uses.into_iter())
+                .filter(|(address, _)| {
+                    self.statement_by_address(*address).statement_type() == StatementType::Provable
+                })
+                .collect::<Vec<_>>();
+            referenced.sort_by(|(address1, count1), (address2, count2)| {
+                count2
+                    .cmp(count1)
+                    .then_with(|| self.parse_result().order.cmp(address1, address2))
+            });
+            for (address, count) in referenced.into_iter().take(TOP_COUNT) {
+                let label = self.statement_by_address(address).label();
+                writeln!(out, "{count:>10}  {}", as_str(label))?;
+            }
+
+            writeln!(out, "\nSegments (statements, parse time, verify time):")?;
+            let sset = self.parse_result();
+            for segment in sset.segments(..) {
+                let name = &sset.source_info(segment.id).name;
+                let verify_time = (self.try_verify_result())
+                    .and_then(|verify| verify.verify_time(segment.id))
+                    .map_or_else(|| "-".to_owned(), millis);
+                write!(
+                    out,
+                    "{:>10}{:>10}{verify_time:>10}  {name}",
+                    segment.statements.len(),
+                    millis(segment.parse_time),
+                )?;
+                if let Some(heading) = segment.outline.first() {
+                    write!(out, ": {}", as_str(&heading.name))?;
+                }
+                writeln!(out)?;
+            }
+            Ok(())
+        })
+    }
+}
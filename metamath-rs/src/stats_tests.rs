@@ -0,0 +1,110 @@
+use crate::grammar_tests::mkdb;
+
+const STATS_DB: &[u8] = b"$c wff |- ( ) -> -. $.
+$v ph ps $.
+wph $f wff ph $.
+wps $f wff ps $.
+wn $a wff -. ph $.
+wi $a wff ( ph -> ps ) $.
+$(
+####
+  Logic
+####
+$)
+$(
+#*#*#*#*
+  Axioms
+#*#*#*#*
+$)
+${
+  min $e |- ph $.
+  maj $e |- ( ph -> ps ) $.
+  ax-mp $a |- ps $.
+$}
+ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+$(
+#*#*#*#*
+  Theorems
+#*#*#*#*
+$)
+$(
+=-=-=-=-
+  Implication
+=-=-=-=-
+$)
+th1 $p |- ( ph -> ( ph -> ph ) ) $= wph wph ax-1 $.
+${
+  th2.1 $e |- ph $.
+  th2 $p |- ( ps -> ( ps -> ph ) ) $=
+    wps wph wi wps wps wph wi wi wph wps wph wi th2.1 wph wps ax-1 ax-mp
+    wps wph wi wps ax-1 ax-mp $.
+$}
+$( Mathboxes. $)
+mathbox $p |- ( ph -> ( ph -> ph ) ) $= ( ax-1 ) AAB $.
+$(
+#*#*#*#*
+  Mathbox for Some Author
+#*#*#*#*
+$)
+th3 $p |- ( -. ph -> ( -. ph -> -. ph ) ) $= wph wn th1 $.
+";
+
+#[test]
+fn test_stats() {
+    let mut db = mkdb(STATS_DB);
+    db.scope_pass();
+    db.outline_pass();
+    let mut out = vec![];
+    db.write_stats(&mut out).unwrap();
+    let stats = String::from_utf8(out).unwrap();
+    let (report, segments) = stats.split_once("\nSegments").unwrap();
+    assert_eq!(
+        report,
+        "Statements:
+         1  $c
+         1  $v
+         2  $f
+         3  $e
+         0  $d
+         4  $a
+         4  $p
+         2  ${
+         2  $}
+         0  $[ $]
+         1  comments
+         5  headings
+         0  $t comments
+         0  $j comments
+
+Constants and variables per typecode:
+         4         2  wff
+
+Theorems per chapter:
+         4  1 Logic
+         0    1.1 Axioms
+         3    1.2 Theorems
+         3      1.2.1 Implication
+         1    1.3 Mathbox for Some Author
+
+Theorems per mathbox:
+         1  Some Author
+
+Proof sizes (normal steps, compressed letters):
+         3         3  1-9
+         1         1  10-99
+
+Deepest proofs:
+         3  th2
+         1  th1
+         1  mathbox
+         1  th3
+
+Most referenced theorems:
+         1  th1
+"
+    );
+    // The timings vary, and the proofs were not verified
+    let segment = segments.lines().nth(1).unwrap();
+    assert!(segment.starts_with("        26"));
+    assert!(segment.ends_with("ms         -  test.mm: Logic"));
+}
@@ -0,0 +1,240 @@
+//! Export of a table of metadata about the assertions, for use in spreadsheets
+//! and dashboards.
+//!
+//! The table has one row per assertion, with its label, kind, number, the
+//! numbered path of its chapter in the outline, the owner of the mathbox it is
+//! in, the authors and dates of its parentheticals, its discouragement flags,
+//! the number of steps of its proof, the number of theorems referencing it,
+//! and the axioms it depends on, which are the `$a` statements with an `ax-`
+//! label.
+
+use crate::bit_set::Bitset;
+use crate::comment_parser::{Date, Discouragements, Parenthetical};
+use crate::database::time;
+use crate::html::statement_kind;
+use crate::parser::HeadingLevel;
+use crate::statement::StatementAddress;
+use crate::util::HashMap;
+use crate::{as_str, Database, StatementRef, StatementType};
+use std::borrow::Cow;
+use std::io;
+
+/// The format of the exported table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Comma-separated values, quoted as in RFC 4180.
+    Csv,
+    /// Tab-separated values; tabs and line breaks in the values are replaced by spaces.
+    Tsv,
+}
+
+impl TableFormat {
+    /// Formats a single value of the table.
+    fn field(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Self::Csv if value.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", value.replace('"', "\"\"")).into()
+            }
+            Self::Tsv if value.contains(['\t', '\n', '\r']) => {
+                value.replace(['\t', '\n', '\r'], " ").into()
+            }
+            _ => value.into(),
+        }
+    }
+
+    /// Writes a row of the table.
+    fn write_row(self, out: &mut impl io::Write, values: &[&str]) -> io::Result<()> {
+        let separator = match self {
+            Self::Csv => ",",
+            Self::Tsv => "\t",
+        };
+        let fields = values
+            .iter()
+            .map(|value| self.field(value))
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", fields.join(separator))
+    }
+}
+
+/// The columns of the table.
+const COLUMNS: [&str; 16] = [
+    "label",
+    "type",
+    "number",
+    "section",
+    "mathbox",
+    "contributor",
+    "contributed",
+    "reviser",
+    "revised",
+    "shortener",
+    "shortened",
+    "new_usage_discouraged",
+    "modification_discouraged",
+    "steps",
+    "uses",
+    "axioms",
+];
+
+/// An author and date from a parenthetical.
+type Credit<'a> = Option<(&'a str, Date)>;
+
+/// The credits of an assertion: the first `(Contributed by ...)`, and the most
+/// recent `(Revised by ...)` and `(Proof shortened by ...)` parentheticals.
+fn credits(stmt: StatementRef<'_>) -> [Credit<'_>; 3] {
+    let mut credits = [None; 3];
+    let Some(comment) = stmt.associated_comment() else {
+        return credits;
+    };
+    let buf = &**comment.segment().segment.buffer;
+    for (_, parenthetical) in comment.parentheticals() {
+        let (ix, author, date) = match parenthetical {
+            Parenthetical::ContributedBy { author, date } => (0, author, date),
+            Parenthetical::RevisedBy { author, date } => (1, author, date),
+            Parenthetical::ProofShortenedBy { author, date } => (2, author, date),
+            _ => continue,
+        };
+        let Ok(date) = Date::try_from(date.as_ref(buf)) else {
+            continue;
+        };
+        let latest = ix > 0 && credits[ix].is_some_and(|(_, last)| date >= last);
+        if credits[ix].is_none() || latest {
+            credits[ix] = Some((as_str(author.as_ref(buf)), date));
+        }
+    }
+    credits
+}
+
+/// Tracks the owner of the mathbox containing the statements, while iterating
+/// over the statements of the database in order.
+#[derive(Debug, Default)]
+pub(crate) struct MathboxTracker {
+    in_mathboxes: bool,
+    /// The owner of the current mathbox, or an empty string outside of mathboxes.
+    pub(crate) owner: String,
+}
+
+impl MathboxTracker {
+    /// Updates the current mathbox with the next statement.
+    pub(crate) fn update(&mut self, stmt: StatementRef<'_>) {
+        if stmt.label() == b"mathbox" {
+            self.in_mathboxes = true;
+        }
+        if self.in_mathboxes
+            && stmt.statement_type() == StatementType::HeadingComment(HeadingLevel::Section)
+        {
+            let buf = &stmt.segment().segment.buffer;
+            self.owner = (stmt.as_heading_comment())
+                .and_then(|header| header.parse_mathbox_header(buf))
+                .map_or_else(String::new, |span| as_str(span.as_ref(buf)).to_owned());
+        }
+    }
+}
+
+impl Database {
+    /// Writes a table with one row per assertion, with the columns described
+    /// in [`crate::table`], preceded by a header row.
+    ///
+    /// Requires: [`Database::name_pass`], [`Database::outline_pass`]
+    pub fn write_statement_table(
+        &self,
+        format: TableFormat,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        time(&self.options.clone(), "statement_table", || {
+            let nset = self.name_result();
+            let mut axioms = vec![];
+            let mut axiom_use = HashMap::<StatementAddress, Bitset>::default();
+            let mut uses = HashMap::<StatementAddress, usize>::default();
+            for stmt in self.statements() {
+                let mut usage = Bitset::new();
+                match stmt.statement_type() {
+                    StatementType::Axiom if stmt.label().starts_with(b"ax-") => {
+                        usage.set_bit(axioms.len());
+                        axioms.push(stmt.label());
+                    }
+                    StatementType::Provable => {
+                        let mut referenced = vec![];
+                        for (_, label) in stmt.use_iter() {
+                            let Some(lookup) = nset.lookup_label(label) else {
+                                continue;
+                            };
+                            if let Some(used_axioms) = axiom_use.get(&lookup.address) {
+                                usage |= used_axioms;
+                            }
+                            if !referenced.contains(&lookup.address) {
+                                referenced.push(lookup.address);
+                            }
+                        }
+                        for address in referenced {
+                            *uses.entry(address).or_default() += 1;
+                        }
+                    }
+                    _ => continue,
+                }
+                axiom_use.insert(stmt.address(), usage);
+            }
+
+            format.write_row(out, &COLUMNS)?;
+            let mut number = 0;
+            let mut mathbox = MathboxTracker::default();
+            for stmt in self.statements() {
+                mathbox.update(stmt);
+                if !stmt.is_assertion() {
+                    continue;
+                }
+                number += 1;
+                let section = (self.get_outline_node(stmt).breadcrumb().iter())
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" > ");
+                let [contributed, revised, shortened] = credits(stmt);
+                let author =
+                    |credit: Credit<'_>| credit.map_or("", |(author, _)| author).to_owned();
+                let date = |credit: Credit<'_>| {
+                    credit.map_or_else(String::new, |(_, date)| date.to_string())
+                };
+                let Discouragements {
+                    modification_discouraged,
+                    usage_discouraged,
+                } = stmt.discouragements();
+                let flag = |flag: bool| if flag { "1" } else { "0" };
+                let steps = if stmt.statement_type() == StatementType::Provable {
+                    stmt.proof_step_count().to_string()
+                } else {
+                    String::new()
+                };
+                let axioms_used = (axiom_use.get(&stmt.address()).into_iter())
+                    .flat_map(|usage| usage.iter().map(|ix| as_str(axioms[ix])))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format.write_row(
+                    out,
+                    &[
+                        as_str(stmt.label()),
+                        statement_kind(stmt),
+                        &number.to_string(),
+                        &section,
+                        &mathbox.owner,
+                        &author(contributed),
+                        &date(contributed),
+                        &author(revised),
+                        &date(revised),
+                        &author(shortened),
+                        &date(shortened),
+                        flag(usage_discouraged),
+                        flag(modification_discouraged),
+                        &steps,
+                        &uses
+                            .get(&stmt.address())
+                            .copied()
+                            .unwrap_or_default()
+                            .to_string(),
+                        &axioms_used,
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+    }
+}
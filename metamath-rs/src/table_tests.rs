@@ -0,0 +1,62 @@
+use crate::grammar_tests::mkdb;
+use crate::table::TableFormat;
+
+const TABLE_DB: &[u8] = b"$c wff |- ( ) -> $.
+$v ph ps $.
+wph $f wff ph $.
+wps $f wff ps $.
+wi $a wff ( ph -> ps ) $.
+$(
+####
+  Logic, propositional
+####
+$)
+$( Axiom 1. (Contributed by A. Author, 1-Jan-2020.) $)
+ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+$( Identity. (Contributed by A. Author, 2-Jan-2020.)
+   (Revised by B. Author, 3-Feb-2021.) (Revised by C. Author, 4-Mar-2022.)
+   (Proof shortened by D. Author, 5-Apr-2023.)
+   (New usage is discouraged.) $)
+th1 $p |- ( ph -> ( ph -> ph ) ) $= wph wph ax-1 $.
+$( Mathboxes. $)
+mathbox $p |- ( ph -> ( ph -> ph ) ) $= ( ax-1 ) AAB $.
+$(
+#*#*#*#*
+  Mathbox for \"Some\" Author
+#*#*#*#*
+$)
+th2 $p |- ( ph -> ( ps -> ph ) ) $= wph wps ax-1 $.
+";
+
+fn table(format: TableFormat) -> String {
+    let mut db = mkdb(TABLE_DB);
+    db.name_pass();
+    db.outline_pass();
+    let mut out = vec![];
+    db.write_statement_table(format, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_table_csv() {
+    assert_eq!(
+        table(TableFormat::Csv),
+        "label,type,number,section,mathbox,contributor,contributed,reviser,revised,shortener,shortened,new_usage_discouraged,modification_discouraged,steps,uses,axioms
+wi,Syntax Definition,1,,,,,,,,,0,0,,0,
+ax-1,Axiom,2,\"1 Logic, propositional\",,A. Author,1-Jan-2020,,,,,0,0,,3,ax-1
+th1,Theorem,3,\"1 Logic, propositional\",,A. Author,2-Jan-2020,C. Author,4-Mar-2022,D. Author,5-Apr-2023,1,0,3,0,ax-1
+mathbox,Theorem,4,\"1 Logic, propositional\",,,,,,,,0,0,3,0,ax-1
+th2,Theorem,5,\"1 Logic, propositional > 1.1 Mathbox for \"\"Some\"\" Author\",\"\"\"Some\"\" Author\",,,,,,,0,0,3,0,ax-1
+"
+    );
+}
+
+#[test]
+fn test_table_tsv() {
+    let table = table(TableFormat::Tsv);
+    let row = table.lines().nth(5).unwrap();
+    assert_eq!(
+        row,
+        "th2\tTheorem\t5\t1 Logic, propositional > 1.1 Mathbox for \"Some\" Author\t\"Some\" Author\t\t\t\t\t\t\t0\t0\t3\t0\tax-1"
+    );
+}
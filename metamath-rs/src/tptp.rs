@@ -0,0 +1,423 @@
+//! Export of first-order theorems to TPTP.
+//!
+//! [TPTP](https://www.tptp.org) is the input format of most automated theorem
+//! provers. Each theorem whose statement is first-order is exported as a FOF
+//! problem: its hypotheses become `hypothesis` formulas, its conclusion the
+//! `conjecture`, and the instances of the assertions applied in its proof, with
+//! the substitutions used there, become `axiom` premises.
+//!
+//! Formulas are translated from their syntax trees, using a [`ConnectiveTable`]
+//! mapping syntax axioms to TPTP connectives. The default table covers the
+//! predicate calculus and set theory fragment of set.mm. A formula using a syntax
+//! axiom which is not in the table cannot be translated: the theorem is then not
+//! exported, and the premise is left out.
+//!
+//! The variables of the typecodes which are not produced by any syntax axiom,
+//! like `setvar`, become TPTP variables. The other variables, like `ph` or `A`,
+//! become predicates or functions of the former ones they have no `$d` condition
+//! with. Each formula is universally closed, and an assertion with hypotheses
+//! becomes an implication from its closed hypotheses to its closed conclusion.
+
+use crate::export::ExportError;
+use crate::formula::{Formula, TypeCode};
+use crate::proof::ProofTreeArray;
+use crate::scopeck::{Frame, Hyp};
+use crate::statement::StatementAddress;
+use crate::util::{HashMap, HashSet};
+use crate::{as_str, Database, StatementRef, StatementType};
+use std::io;
+
+/// The translation of a syntax axiom to TPTP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Connective {
+    /// Negation `~`, with a single argument.
+    Not,
+    /// Conjunction `&` of all the arguments.
+    And,
+    /// Disjunction `|` of all the arguments.
+    Or,
+    /// Implication `=>`, with two arguments.
+    Implies,
+    /// Equivalence `<=>`, with two arguments.
+    Iff,
+    /// Universal quantification `!`, of a variable over a formula.
+    ForAll,
+    /// Existential quantification `?`, of a variable over a formula.
+    Exists,
+    /// Equality `=` of two terms.
+    Equal,
+    /// The single argument itself, for coercions like `cv` in set.mm.
+    Identity,
+    /// A predicate with the given name, applied to the arguments.
+    Predicate(String),
+    /// A function with the given name, applied to the arguments.
+    Function(String),
+}
+
+/// The translations of the syntax axioms to TPTP.
+///
+/// The default table covers the predicate calculus and set theory fragment of
+/// set.mm: `wn`, `wi`, `wb`, `wa`, `wo`, `w3a`, `w3o`, `wtru`, `wfal`, `wal`,
+/// `wex`, `wceq`, `wcel` and `cv`.
+#[derive(Debug, Clone)]
+pub struct ConnectiveTable {
+    connectives: HashMap<Box<[u8]>, Connective>,
+}
+
+impl Default for ConnectiveTable {
+    fn default() -> Self {
+        let mut table = Self {
+            connectives: HashMap::default(),
+        };
+        for (label, connective) in [
+            ("wn", Connective::Not),
+            ("wi", Connective::Implies),
+            ("wb", Connective::Iff),
+            ("wa", Connective::And),
+            ("wo", Connective::Or),
+            ("w3a", Connective::And),
+            ("w3o", Connective::Or),
+            ("wtru", Connective::Predicate("$true".to_owned())),
+            ("wfal", Connective::Predicate("$false".to_owned())),
+            ("wal", Connective::ForAll),
+            ("wex", Connective::Exists),
+            ("wceq", Connective::Equal),
+            ("wcel", Connective::Predicate("in".to_owned())),
+            ("cv", Connective::Identity),
+        ] {
+            table.set(label, connective);
+        }
+        table
+    }
+}
+
+impl ConnectiveTable {
+    /// Sets the translation of the syntax axiom with the given label.
+    pub fn set(&mut self, label: &str, connective: Connective) {
+        self.connectives.insert(label.as_bytes().into(), connective);
+    }
+
+    /// Adds the translations listed in the given text, one per line, as a label
+    /// followed by `not`, `and`, `or`, `implies`, `iff`, `forall`, `exists`,
+    /// `equal`, `identity`, `predicate NAME` or `function NAME`.
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// Returns the number of the first invalid line in case of error.
+    pub fn load(&mut self, text: &str) -> Result<(), usize> {
+        for (number, line) in text.lines().enumerate() {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let connective = match words[..] {
+                [] => continue,
+                [first, ..] if first.starts_with('#') => continue,
+                [_, "not"] => Connective::Not,
+                [_, "and"] => Connective::And,
+                [_, "or"] => Connective::Or,
+                [_, "implies"] => Connective::Implies,
+                [_, "iff"] => Connective::Iff,
+                [_, "forall"] => Connective::ForAll,
+                [_, "exists"] => Connective::Exists,
+                [_, "equal"] => Connective::Equal,
+                [_, "identity"] => Connective::Identity,
+                [_, "predicate", name] => Connective::Predicate(name.to_owned()),
+                [_, "function", name] => Connective::Function(name.to_owned()),
+                _ => return Err(number + 1),
+            };
+            self.set(words[0], connective);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the given name as a TPTP name, quoting it unless it is a lower word.
+fn quote(name: &str) -> String {
+    let lower_word = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if lower_word {
+        name.to_owned()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Returns the TPTP variable for a floating hypothesis label.
+fn variable(label: &[u8]) -> String {
+    let name = as_str(label)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() => {
+            format!("{}{}", first.to_ascii_uppercase(), chars.as_str())
+        }
+        _ => format!("V{name}"),
+    }
+}
+
+/// Wraps a formula in a universal quantification over the given variables.
+fn close(vars: &[String], formula: String) -> String {
+    if vars.is_empty() {
+        formula
+    } else {
+        format!("(! [{}] : {formula})", vars.join(", "))
+    }
+}
+
+struct TptpWriter<'a> {
+    db: &'a Database,
+    table: &'a ConnectiveTable,
+    /// The typecodes of the TPTP variables.
+    variable_typecodes: &'a HashSet<TypeCode>,
+    /// The TPTP variables the predicates and functions translating the
+    /// other variables of the theorem depend on.
+    deps: HashMap<StatementAddress, Vec<String>>,
+    /// The dependencies of the variables which are not in the frame of the
+    /// theorem: all its TPTP variables.
+    dummy_deps: Vec<String>,
+}
+
+impl<'a> TptpWriter<'a> {
+    fn new(db: &'a Database, table: &'a ConnectiveTable, frame: &Frame) -> Self {
+        let variable_typecodes = db.variable_typecodes();
+        let label = |address| db.statement_by_address(address).label();
+        let vars = (frame.hypotheses.iter())
+            .filter_map(|hyp| match *hyp {
+                Hyp::Floating(address, var, typecode) if variable_typecodes.contains(&typecode) => {
+                    Some((var, variable(label(address))))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut deps = HashMap::default();
+        for hyp in &*frame.hypotheses {
+            if let Hyp::Floating(address, var, typecode) = *hyp {
+                if variable_typecodes.contains(&typecode) {
+                    continue;
+                }
+                let disjoint = |other: usize| {
+                    (frame.mandatory_dv.iter())
+                        .any(|&pair| pair == (var, other) || pair == (other, var))
+                };
+                let var_deps = (vars.iter())
+                    .filter(|&&(other, _)| !disjoint(other))
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                deps.insert(address, var_deps);
+            }
+        }
+        Self {
+            db,
+            table,
+            variable_typecodes,
+            deps,
+            dummy_deps: vars.into_iter().map(|(_, name)| name).collect(),
+        }
+    }
+
+    /// Returns the indices of the floating hypotheses of a syntax axiom, in the
+    /// order of appearance of their variables in its statement.
+    fn arguments(&self, address: StatementAddress) -> Option<Vec<usize>> {
+        let stmt = self.db.statement_by_address(address);
+        let frame = self.db.scope_result().get(stmt.label())?;
+        let position = |hyp: &Hyp| {
+            let var = self.db.statement_by_address(hyp.address()).math_at(1).slice;
+            stmt.math_iter().position(|token| token.slice == var)
+        };
+        let mut args = (0..frame.hypotheses.len()).collect::<Vec<_>>();
+        args.sort_by_key(|&ix| position(&frame.hypotheses[ix]));
+        Some(args)
+    }
+
+    /// Translates a syntax tree, adding its free variables to `free`.
+    fn translate(
+        &self,
+        arr: &ProofTreeArray,
+        ix: usize,
+        bound: &mut Vec<String>,
+        free: &mut Vec<String>,
+    ) -> Option<String> {
+        let tree = &arr.trees[ix];
+        let stmt = self.db.statement_by_address(tree.address);
+        let mut add_free = |name: &String| {
+            if !bound.contains(name) && !free.contains(name) {
+                free.push(name.clone());
+            }
+        };
+        if stmt.statement_type() == StatementType::Floating {
+            let typecode = self.db.name_result().get_atom(stmt.math_at(0).slice);
+            if self.variable_typecodes.contains(&typecode) {
+                let name = variable(stmt.label());
+                add_free(&name);
+                return Some(name);
+            }
+            let deps = self.deps.get(&tree.address).unwrap_or(&self.dummy_deps);
+            deps.iter().for_each(&mut add_free);
+            let name = quote(as_str(stmt.label()));
+            return Some(if deps.is_empty() {
+                name
+            } else {
+                format!("{name}({})", deps.join(", "))
+            });
+        }
+        let connective = self.table.connectives.get(stmt.label())?;
+        let children = (self.arguments(tree.address)?.into_iter())
+            .map(|arg| tree.children.get(arg).copied())
+            .collect::<Option<Vec<_>>>()?;
+        if let (Connective::ForAll | Connective::Exists, &[var, body]) = (connective, &children[..])
+        {
+            let var = self.translate(arr, var, &mut vec![], &mut vec![])?;
+            if !var.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return None;
+            }
+            bound.push(var.clone());
+            let body = self.translate(arr, body, bound, free);
+            bound.pop();
+            let quantifier = if *connective == Connective::ForAll {
+                '!'
+            } else {
+                '?'
+            };
+            return Some(format!("({quantifier} [{var}] : {})", body?));
+        }
+        let args = (children.iter())
+            .map(|&child| self.translate(arr, child, bound, free))
+            .collect::<Option<Vec<_>>>()?;
+        Some(match (connective, &args[..]) {
+            (Connective::Not, [arg]) => format!("~ {arg}"),
+            (Connective::And | Connective::Or | Connective::Identity, [arg]) => arg.clone(),
+            (Connective::And, [_, _, ..]) => format!("({})", args.join(" & ")),
+            (Connective::Or, [_, _, ..]) => format!("({})", args.join(" | ")),
+            (Connective::Implies, [left, right]) => format!("({left} => {right})"),
+            (Connective::Iff, [left, right]) => format!("({left} <=> {right})"),
+            (Connective::Equal, [left, right]) => format!("({left} = {right})"),
+            (Connective::Predicate(name) | Connective::Function(name), []) => name.clone(),
+            (Connective::Predicate(name) | Connective::Function(name), _) => {
+                format!("{name}({})", args.join(", "))
+            }
+            _ => return None,
+        })
+    }
+
+    /// Translates a formula, universally closed.
+    fn translate_formula(&self, formula: &Formula) -> Option<String> {
+        let mut arr = ProofTreeArray::new(false);
+        let root = formula
+            .as_ref(self.db)
+            .build_syntax_proof(&mut vec![], &mut arr);
+        let mut free = vec![];
+        let formula = self.translate(&arr, root, &mut vec![], &mut free)?;
+        Some(close(&free, formula))
+    }
+}
+
+impl Database {
+    /// Writes a TPTP problem for the given theorem, as described in [`crate::tptp`],
+    /// using the given translations of the syntax axioms.
+    ///
+    /// Returns `false` without writing anything if the statement is not a theorem
+    /// of the provable typecode, or if its hypotheses or conclusion cannot be
+    /// translated.
+    ///
+    /// Requires: [`Database::scope_pass`], [`Database::stmt_parse_pass`]
+    pub fn write_tptp_problem(
+        &self,
+        stmt: StatementRef<'_>,
+        table: &ConnectiveTable,
+        out: &mut impl io::Write,
+    ) -> Result<bool, ExportError> {
+        let grammar = self.grammar_result();
+        let nset = self.name_result();
+        let provable = grammar.provable_typecode();
+        let typecode = |stmt: StatementRef<'_>| nset.get_atom(stmt.math_at(0).slice);
+        if stmt.statement_type() != StatementType::Provable || typecode(stmt) != provable {
+            return Ok(false);
+        }
+        let Some(frame) = self.scope_result().get(stmt.label()) else {
+            return Ok(false);
+        };
+        let writer = TptpWriter::new(self, table, frame);
+        let formulas = self.stmt_parse_result();
+        let translate = |stmt: StatementRef<'_>| {
+            (formulas.get_formula(&stmt)).and_then(|formula| writer.translate_formula(formula))
+        };
+        let mut hyps = vec![];
+        for hyp in &*frame.hypotheses {
+            if let Hyp::Essential(address, _) = *hyp {
+                let hyp = self.statement_by_address(address);
+                let Some(formula) = translate(hyp) else {
+                    return Ok(false);
+                };
+                hyps.push((hyp.label(), formula));
+            }
+        }
+        let Some(conjecture) = translate(stmt) else {
+            return Ok(false);
+        };
+
+        let arr = ProofTreeArray::from_stmt(self, stmt, true)?;
+        let exprs = arr.exprs().expect("proof trees are built with expressions");
+        let step = |ix: usize| {
+            let step = self.statement_by_address(arr.trees[ix].address);
+            let expr = format!(
+                "{}{}",
+                as_str(step.math_at(0).slice),
+                String::from_utf8_lossy(&exprs[ix])
+            );
+            let formula = grammar.parse_string(&expr, nset).ok()?;
+            writer.translate_formula(&formula)
+        };
+        let mut premises = Vec::<(&[u8], String)>::new();
+        let mut skipped = 0;
+        for (ix, tree) in arr.trees.iter().enumerate() {
+            let applied = self.statement_by_address(tree.address);
+            if !applied.is_assertion() || typecode(applied) != provable {
+                continue;
+            }
+            let hyps = (tree.children.iter())
+                .filter(|&&child| {
+                    typecode(self.statement_by_address(arr.trees[child].address)) == provable
+                })
+                .map(|&child| step(child))
+                .collect::<Option<Vec<_>>>();
+            let premise = hyps
+                .zip(step(ix))
+                .map(|(hyps, conclusion)| match &hyps[..] {
+                    [] => conclusion,
+                    [hyp] => format!("({hyp} => {conclusion})"),
+                    _ => format!("(({}) => {conclusion})", hyps.join(" & ")),
+                });
+            match premise {
+                Some(premise) if !premises.iter().any(|(_, known)| *known == premise) => {
+                    premises.push((applied.label(), premise));
+                }
+                Some(_) => {}
+                None => skipped += 1,
+            }
+        }
+
+        let label = as_str(stmt.label());
+        writeln!(out, "% Theorem {label}")?;
+        if skipped > 0 {
+            writeln!(
+                out,
+                "% The premises from {skipped} proof step(s) could not be translated."
+            )?;
+        }
+        for (hyp, formula) in hyps {
+            writeln!(out, "fof({}, hypothesis, {formula}).", quote(as_str(hyp)))?;
+        }
+        let mut counts = HashMap::<&[u8], usize>::default();
+        for (applied, premise) in premises {
+            let count = counts.entry(applied).or_default();
+            *count += 1;
+            let name = if *count == 1 {
+                as_str(applied).to_owned()
+            } else {
+                format!("{}_{count}", as_str(applied))
+            };
+            writeln!(out, "fof({}, axiom, {premise}).", quote(&name))?;
+        }
+        writeln!(out, "fof({}, conjecture, {conjecture}).", quote(label))?;
+        Ok(true)
+    }
+}
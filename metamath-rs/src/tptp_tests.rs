@@ -0,0 +1,103 @@
+use crate::grammar_tests::mkdb;
+use crate::tptp::ConnectiveTable;
+
+const TPTP_DB: &[u8] = b"
+  $( $j syntax 'setvar'; syntax 'class'; syntax 'wff'; syntax '|-' as 'wff'; type_conversions; $)
+  $c ( ) -> -. A. = { | } wff setvar class |- $.
+  $v ph ps x A B $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  vx $f setvar x $.
+  cA $f class A $.
+  cB $f class B $.
+  wn $a wff -. ph $.
+  wi $a wff ( ph -> ps ) $.
+  wal $a wff A. x ph $.
+  cv $a class x $.
+  wceq $a wff A = B $.
+  cab $a class { x | ph } $.
+  ${
+    min $e |- ph $.
+    maj $e |- ( ph -> ps ) $.
+    ax-mp $a |- ps $.
+  $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ${
+    ax-gen.1 $e |- ph $.
+    ax-gen $a |- A. x ph $.
+  $}
+  ${
+    $d x ph $.
+    ax-5 $a |- ( ph -> A. x ph ) $.
+  $}
+  eqid $a |- A = A $.
+  th1 $p |- A. x ( ps -> ( ph -> ps ) ) $= wps wph wps wi wi vx wps wph ax-1 ax-gen $.
+  ${
+    $d x ph $.
+    th2.1 $e |- ps $.
+    th2 $p |- ( ph -> A. x ph ) $= wph vx ax-5 $.
+  $}
+  th3 $p |- x = x $= vx cv eqid $.
+  th4 $p |- { x | ph } = { x | ph } $= wph vx cab eqid $.
+";
+
+fn export(label: &[u8], table: &ConnectiveTable) -> Option<String> {
+    let mut db = mkdb(TPTP_DB);
+    db.scope_pass();
+    db.stmt_parse_pass();
+    let mut out = vec![];
+    let stmt = db.statement(label).unwrap();
+    db.write_tptp_problem(stmt, table, &mut out)
+        .unwrap()
+        .then(|| String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_tptp() {
+    let table = ConnectiveTable::default();
+    assert_eq!(
+        export(b"th1", &table).unwrap(),
+        "% Theorem th1
+fof('ax-1', axiom, (! [Vx] : (wps(Vx) => (wph(Vx) => wps(Vx))))).
+fof('ax-gen', axiom, ((! [Vx] : (wps(Vx) => (wph(Vx) => wps(Vx)))) => \
+(! [Vx] : (wps(Vx) => (wph(Vx) => wps(Vx)))))).
+fof(th1, conjecture, (! [Vx] : (wps(Vx) => (wph(Vx) => wps(Vx))))).
+"
+    );
+    assert_eq!(
+        export(b"th2", &table).unwrap(),
+        "% Theorem th2
+fof('th2.1', hypothesis, (! [Vx] : wps(Vx))).
+fof('ax-5', axiom, (wph => (! [Vx] : wph))).
+fof(th2, conjecture, (wph => (! [Vx] : wph))).
+"
+    );
+    assert_eq!(
+        export(b"th3", &table).unwrap(),
+        "% Theorem th3
+fof(eqid, axiom, (! [Vx] : (Vx = Vx))).
+fof(th3, conjecture, (! [Vx] : (Vx = Vx))).
+"
+    );
+    // Class abstractions are not first-order
+    assert_eq!(export(b"th4", &table), None);
+    // Syntax theorems and axioms are not problems
+    assert_eq!(export(b"ax-1", &table), None);
+}
+
+#[test]
+fn test_tptp_table() {
+    let mut table = ConnectiveTable::default();
+    assert_eq!(
+        table.load("# Equality as a predicate\n\nwceq predicate eq\n"),
+        Ok(())
+    );
+    assert_eq!(
+        export(b"th3", &table).unwrap(),
+        "% Theorem th3
+fof(eqid, axiom, (! [Vx] : eq(Vx, Vx))).
+fof(th3, conjecture, (! [Vx] : eq(Vx, Vx))).
+"
+    );
+    assert_eq!(table.load("wn not\nwceq predicate\n"), Err(2));
+}
@@ -0,0 +1,310 @@
+//! Unicode plain-text rendering of math strings, for terminals.
+//!
+//! The Unicode text of each token is derived from its `althtmldef` definition
+//! in the `$t` comment (see [`crate::typesetting`]), by stripping the HTML tags
+//! and decoding the character entities, so that `|- ( ph -> ps )` is rendered
+//! as `⊢ (𝜑 → 𝜓)`.
+//!
+//! Optionally, variables are colored using ANSI escape codes, according to the
+//! colors given for their `CLASS` by the `htmlvarcolor` typesetting command,
+//! or by their own `STYLE` attribute.
+
+use crate::statement::{Token, TokenPtr};
+use crate::typesetting::TypesettingData;
+use crate::util::HashMap;
+use crate::{as_str, Database};
+use std::fmt::Write;
+
+/// Returns the character for a named HTML entity, for the entities commonly
+/// found in `althtmldef` definitions.
+fn named_entity(name: &str) -> Option<char> {
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "not" => '¬',
+        "and" => '∧',
+        "or" => '∨',
+        "rarr" => '→',
+        "larr" => '←',
+        "harr" => '↔',
+        "rArr" => '⇒',
+        "lArr" => '⇐',
+        "hArr" => '⇔',
+        "forall" => '∀',
+        "exist" => '∃',
+        "isin" => '∈',
+        "notin" => '∉',
+        "ni" => '∋',
+        "empty" => '∅',
+        "sub" => '⊂',
+        "sup" => '⊃',
+        "sube" => '⊆',
+        "supe" => '⊇',
+        "cap" => '∩',
+        "cup" => '∪',
+        "le" => '≤',
+        "ge" => '≥',
+        "ne" => '≠',
+        "equiv" => '≡',
+        "asymp" => '≈',
+        "sim" => '∼',
+        "times" => '×',
+        "divide" => '÷',
+        "middot" => '·',
+        "sdot" => '⋅',
+        "minus" => '−',
+        "plusmn" => '±',
+        "infin" => '∞',
+        "prime" => '′',
+        "lang" => '⟨',
+        "rang" => '⟩',
+        "lceil" => '⌈',
+        "rceil" => '⌉',
+        "lfloor" => '⌊',
+        "rfloor" => '⌋',
+        "uarr" => '↑',
+        "darr" => '↓',
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+/// Decodes the HTML entity at the start of `text`, which starts with a `&`.
+/// Returns the decoded character and the length of the entity.
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+    let c = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+    } else if let Some(dec) = name.strip_prefix('#') {
+        char::from_u32(dec.parse().ok()?)?
+    } else {
+        named_entity(name)?
+    };
+    Some((c, end + 1))
+}
+
+/// Returns the value of the given attribute in the given HTML tag, if any.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find(&format!("{name}="))? + name.len() + 1;
+    let value = &tag[start..];
+    if let Some(quoted) = value.strip_prefix(['"', '\'']) {
+        quoted.find(['"', '\'']).map(|end| &quoted[..end])
+    } else {
+        Some(value.split([' ', '>']).next().unwrap_or(value))
+    }
+}
+
+/// Returns the value of the `color` property in the `STYLE` attribute of the given tag.
+fn style_color(tag: &str) -> Option<&str> {
+    let style = attribute(tag, "style")?;
+    style.split(';').find_map(|prop| {
+        let (key, value) = prop.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("color")
+            .then(|| value.trim())
+    })
+}
+
+/// Returns the ANSI foreground color code closest to the given CSS color.
+fn ansi_color(css: &str) -> Option<u8> {
+    if let Some(hex) = css.strip_prefix('#') {
+        let digits = hex
+            .chars()
+            .map(|c| c.to_digit(16))
+            .collect::<Option<Vec<_>>>()?;
+        let ([r, g, b] | [r, _, g, _, b, _]) = *digits else {
+            return None;
+        };
+        let bit = |v: u32, n: u8| if v >= 8 { n } else { 0 };
+        return Some(30 + (bit(r, 1) | bit(g, 2) | bit(b, 4)));
+    }
+    Some(match css.to_ascii_lowercase().as_str() {
+        "black" => 30,
+        "red" | "maroon" => 31,
+        "green" | "lime" | "olive" => 32,
+        "yellow" | "orange" => 33,
+        "blue" | "navy" => 34,
+        "magenta" | "fuchsia" | "purple" => 35,
+        "cyan" | "aqua" | "teal" => 36,
+        "white" | "gray" | "grey" | "silver" => 37,
+        _ => return None,
+    })
+}
+
+/// The result of converting a piece of HTML to plain text.
+struct PlainText {
+    text: String,
+    /// The classes and colors of the `SPAN` elements, with their text contents.
+    spans: Vec<(Option<String>, Option<String>, String)>,
+}
+
+/// Converts a piece of HTML to plain text, stripping tags and decoding entities.
+fn strip_html(html: &str) -> PlainText {
+    let mut out = PlainText {
+        text: String::new(),
+        spans: vec![],
+    };
+    let mut open_span: Option<(Option<String>, Option<String>, usize)> = None;
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+                let tag = &rest[..end];
+                let name = tag[1..].split([' ', '>']).next().unwrap_or_default();
+                if name.eq_ignore_ascii_case("span") {
+                    open_span = Some((
+                        attribute(tag, "class").map(str::to_owned),
+                        style_color(tag).map(str::to_owned),
+                        out.text.len(),
+                    ));
+                } else if name.eq_ignore_ascii_case("/span") {
+                    if let Some((class, color, start)) = open_span.take() {
+                        let text = out.text[start..].trim().to_owned();
+                        out.spans.push((class, color, text));
+                    }
+                }
+                rest = &rest[end..];
+            }
+            '&' => {
+                if let Some((c, len)) = decode_entity(rest) {
+                    out.text.push(c);
+                    rest = &rest[len..];
+                } else {
+                    out.text.push('&');
+                    rest = &rest[1..];
+                }
+            }
+            _ => {
+                out.text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+/// Renders math strings as Unicode text, using the `althtmldef` definitions.
+#[derive(Debug, Default, Clone)]
+pub struct UnicodeRenderer {
+    /// The text of each token, and its ANSI color code if any.
+    defs: HashMap<Token, (String, Option<u8>)>,
+    /// Whether to use ANSI escape codes to color variables.
+    color: bool,
+}
+
+impl UnicodeRenderer {
+    /// Builds a renderer from the given typesetting data,
+    /// using ANSI colors for variables if `color` is set.
+    #[must_use]
+    pub fn new(typesetting: &TypesettingData, color: bool) -> Self {
+        let mut class_colors = HashMap::default();
+        for (_, var_color) in &typesetting.html_var_color {
+            for (class, color, text) in strip_html(as_str(var_color)).spans {
+                if let Some(color) = color.as_deref().and_then(ansi_color) {
+                    class_colors.insert(class.unwrap_or(text), color);
+                }
+            }
+        }
+        let defs = typesetting
+            .alt_html_defs
+            .iter()
+            .map(|(token, (_, _, def))| {
+                let plain = strip_html(&String::from_utf8_lossy(def));
+                let color = plain.spans.iter().find_map(|(class, color, _)| {
+                    color
+                        .as_deref()
+                        .and_then(ansi_color)
+                        .or_else(|| class_colors.get(class.as_ref()?).copied())
+                });
+                (token.clone(), (plain.text, color))
+            })
+            .collect();
+        Self { defs, color }
+    }
+
+    /// Returns the plain text derived from the `althtmldef` definition
+    /// of the given token, if any.
+    pub(crate) fn text(&self, token: TokenPtr<'_>) -> Option<&str> {
+        self.defs.get(token).map(|(text, _)| text.as_str())
+    }
+
+    /// Writes the Unicode rendering of a single token to `out`.
+    /// Tokens without an `althtmldef` are written as is, followed by a space.
+    pub fn write_token(&self, out: &mut String, token: TokenPtr<'_>) {
+        match self.defs.get(token) {
+            Some((text, Some(color))) if self.color => {
+                // Leading and trailing spaces are kept out of the colored text
+                let trimmed = text.trim();
+                let start = text.find(trimmed).unwrap_or_default();
+                out.push_str(&text[..start]);
+                _ = write!(out, "\x1b[{color}m{trimmed}\x1b[0m");
+                out.push_str(&text[start + trimmed.len()..]);
+            }
+            Some((text, _)) => out.push_str(text),
+            None => {
+                out.push_str(as_str(token));
+                out.push(' ');
+            }
+        }
+    }
+
+    /// Renders the given math string as Unicode text.
+    #[must_use]
+    pub fn render<'a>(&self, tokens: impl IntoIterator<Item = TokenPtr<'a>>) -> String {
+        let mut out = String::new();
+        for token in tokens {
+            self.write_token(&mut out, token);
+        }
+        out.trim_end().to_owned()
+    }
+}
+
+impl Database {
+    /// Builds a renderer of math strings as Unicode text, using the `althtmldef`
+    /// definitions of this database. If `color` is set, variables are colored
+    /// using ANSI escape codes.
+    ///
+    /// Requires: [`Database::typesetting_pass`]
+    #[must_use]
+    pub fn unicode_renderer(&self, color: bool) -> UnicodeRenderer {
+        UnicodeRenderer::new(self.typesetting_result(), color)
+    }
+}
@@ -0,0 +1,30 @@
+use crate::grammar_tests::mkdb;
+
+const UNICODE_DB: &[u8] = b"
+$( $t
+althtmldef \"|-\" as \"&#8866; \";
+althtmldef \"ph\" as \"<SPAN CLASS=wff>&#x1D711;</SPAN>\";
+althtmldef \"ps\" as \"<SPAN CLASS=wff STYLE='color:#C3C'>&psi;</SPAN>\";
+althtmldef \"->\" as \" &rarr; \";
+althtmldef \"(\" as \"(\";
+htmlvarcolor '<SPAN CLASS=wff STYLE=\"color:blue\">wff</SPAN>';
+$)
+$c ( ) -> |- $.  $v ph ps $.
+";
+
+#[test]
+fn test_unicode_render() {
+    let mut db = mkdb(UNICODE_DB);
+    db.typesetting_pass();
+    let tokens = [&b"|-"[..], b"(", b"ph", b"->", b"ps", b")"];
+    let renderer = db.unicode_renderer(false);
+    assert_eq!(
+        renderer.render(tokens),
+        "\u{22a2} (\u{1d711} \u{2192} \u{3c8})"
+    );
+    let renderer = db.unicode_renderer(true);
+    assert_eq!(
+        renderer.render(tokens),
+        "\u{22a2} (\x1b[34m\u{1d711}\x1b[0m \u{2192} \x1b[35m\u{3c8}\x1b[0m)"
+    );
+}
@@ -141,3 +141,21 @@ pub(crate) fn find_chapter_header(mut buffer: &[u8]) -> Option<usize> {
         }
     }
 }
+
+/// Writes a byte string as a JSON string literal, with quotes and escapes.
+/// Invalid UTF-8 sequences are replaced with U+FFFD.
+pub(crate) fn write_json_string(out: &mut impl std::io::Write, s: &[u8]) -> std::io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in String::from_utf8_lossy(s).chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{c}")?,
+        }
+    }
+    out.write_all(b"\"")
+}
@@ -94,3 +94,10 @@ fn test_find_chapter() {
         None
     );
 }
+
+#[test]
+fn test_write_json_string() {
+    let mut out = vec![];
+    util::write_json_string(&mut out, b"a \"b\"\\c\n\x01").unwrap();
+    assert_eq!(out, br#""a \"b\"\\c\n\u0001""#);
+}
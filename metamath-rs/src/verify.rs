@@ -25,6 +25,8 @@
 //! than it is now.
 
 use crate::bit_set::Bitset;
+use crate::cache::{segment_hashes, VerifyCache};
+use crate::database::log;
 use crate::diag::Diagnostic;
 use crate::nameck::{Atom, Nameset};
 use crate::scopeck::{
@@ -38,9 +40,11 @@ use crate::{parser, Database, StatementRef, StatementType};
 use std::mem;
 use std::ops::Range;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // Proofs are very fragile and there are very few situations where errors are
-// recoverable, so we bail out using Result on any error.
+// recoverable, so we bail out using Result on any error.  The exception is the
+// error recovery mode, see `VerifyState::recover`.
 macro_rules! try_assert {
     ( $cond:expr , $($arg:tt)+ ) => {
         if !$cond {
@@ -54,7 +58,7 @@ macro_rules! try_assert {
 /// results/hypotheses, which are copied directly onto the stack, or previously
 /// proved assertions which require substitution before use.
 enum PreparedStep<'a, D> {
-    Hyp(Bitset, Atom, Range<usize>, D),
+    Hyp(StackSlot, D),
     Assert(&'a Frame),
 }
 use self::PreparedStep::*;
@@ -72,6 +76,10 @@ pub struct StackSlot {
     vars: Bitset,
     code: Atom,
     expr: Range<usize>,
+    /// In error recovery mode, whether this entry depends on an unknown step,
+    /// in which case it is not the actual result of its subtree, and
+    /// mismatches involving it are not reported.
+    suspect: bool,
 }
 
 /// A constructor trait for plugging in to the verifier, to collect extra data during the
@@ -139,6 +147,54 @@ struct VerifyState<'a, P: ProofBuilder> {
     var2bit: HashMap<Atom, usize>,
     /// Disjoint variable conditions in the current extended frame
     dv_map: &'a [Bitset],
+    /// The number of the current proof step, counted from 1
+    step: usize,
+    /// The span of the proof token of the current step
+    step_span: Span,
+    /// In error recovery mode, the errors found so far in the current proof
+    recovered: Option<Vec<Diagnostic>>,
+}
+
+impl<P: ProofBuilder> VerifyState<'_, P> {
+    /// In error recovery mode, records the given error for the current step and
+    /// lets the verification continue; otherwise, returns the error.
+    fn recover(&mut self, diag: Diagnostic) -> Result<()> {
+        match &mut self.recovered {
+            Some(recovered) => {
+                recovered.push(Diagnostic::ProofStep(
+                    self.step,
+                    self.step_span,
+                    Box::new(diag),
+                ));
+                Ok(())
+            }
+            None => Err(diag),
+        }
+    }
+
+    /// In error recovery mode, attaches the current step number to an error
+    /// which ends the verification of the proof.
+    fn step_error(&self, diag: Diagnostic) -> Diagnostic {
+        if self.recovered.is_some() {
+            Diagnostic::ProofStep(self.step, self.step_span, Box::new(diag))
+        } else {
+            diag
+        }
+    }
+
+    /// Returns a stack entry standing for an unknown step `?`, which matches
+    /// any hypothesis in error recovery mode.
+    fn unknown_step(&mut self, addr: StatementAddress) -> (P::Item, StackSlot) {
+        let top = self.stack_buffer.len();
+        let data = (self.builder).build(addr, Default::default(), &self.stack_buffer, top..top);
+        let slot = StackSlot {
+            vars: Bitset::new(),
+            code: Atom::default(),
+            expr: top..top,
+            suspect: true,
+        };
+        (data, slot)
+    }
 }
 
 type Result<T> = std::result::Result<T, Diagnostic>;
@@ -196,10 +252,14 @@ fn prepare_hypothesis<P: ProofBuilder>(state: &mut VerifyState<'_, P>, hyp: &sco
 
     let n_tos = state.stack_buffer.len();
 
-    state.prepared.push(Hyp(
+    let slot = StackSlot {
         vars,
-        hyp.typecode(),
-        tos..n_tos,
+        code: hyp.typecode(),
+        expr: tos..n_tos,
+        suspect: false,
+    };
+    state.prepared.push(Hyp(
+        slot,
         state.builder.build(
             hyp.address(),
             Default::default(),
@@ -331,10 +391,14 @@ fn prepare_step<'a, P: ProofBuilder>(
         let old_top = state.stack_buffer.len();
         fast_extend(&mut state.stack_buffer, &frame.stub_expr);
         let new_top = state.stack_buffer.len();
-        state.prepared.push(Hyp(
+        let slot = StackSlot {
             vars,
-            frame.target.typecode,
-            old_top..new_top,
+            code: frame.target.typecode,
+            expr: old_top..new_top,
+            suspect: false,
+        };
+        state.prepared.push(Hyp(
+            slot,
             state.builder.build(
                 valid.start,
                 Default::default(),
@@ -429,6 +493,9 @@ fn do_substitute_vars(expr: &[ExprFragment], vars: &[(Range<usize>, Bitset)]) ->
 /// Since a variable must be `$f`-declared before it can appear in an `$e` (or
 /// else we'll ignore the `$e`), and that logical file order is reflected in
 /// the stack order of the hypotheses, we can do this in one pass.
+///
+/// In error recovery mode, `suspect` tells whether one of the previous
+/// hypotheses of the step is suspect, and the updated value is returned.
 #[inline(always)]
 fn process_hyp<P: ProofBuilder>(
     state: &mut VerifyState<'_, P>,
@@ -436,29 +503,44 @@ fn process_hyp<P: ProofBuilder>(
     frame: &Frame,
     ix: usize,
     hyp: &scopeck::Hyp,
-) -> Result<()> {
+    suspect: bool,
+) -> Result<bool> {
     let (ref data, ref slot): (P::Item, StackSlot) = state.stack[ix];
     state.builder.push(datavec, data.clone());
-    match *hyp {
+    let suspect = suspect || slot.suspect;
+    let error = match *hyp {
         Floating(_addr, var_index, typecode) => {
-            try_assert!(slot.code == typecode, Diagnostic::StepFloatWrongType);
-            state.subst_info[var_index] = (slot.expr.clone(), slot.vars.clone());
+            if slot.code == typecode {
+                state.subst_info[var_index] = (slot.expr.clone(), slot.vars.clone());
+                None
+            } else {
+                // The variable is left unsubstituted, and the step is suspect
+                state.subst_info[var_index] = (0..0, Bitset::new());
+                Some(Diagnostic::StepFloatWrongType)
+            }
         }
         Essential(_addr, ref expr) => {
-            try_assert!(slot.code == expr.typecode, Diagnostic::StepEssenWrongType);
-            try_assert!(
-                do_substitute_eq(
-                    &state.stack_buffer[slot.expr.clone()],
-                    frame,
-                    expr,
-                    &state.subst_info,
-                    &state.stack_buffer
-                ),
-                Diagnostic::StepEssenWrong
-            );
+            if slot.code != expr.typecode {
+                Some(Diagnostic::StepEssenWrongType)
+            } else if !do_substitute_eq(
+                &state.stack_buffer[slot.expr.clone()],
+                frame,
+                expr,
+                &state.subst_info,
+                &state.stack_buffer,
+            ) {
+                Some(Diagnostic::StepEssenWrong)
+            } else {
+                None
+            }
         }
+    };
+    match error {
+        // Mismatches in a suspect step are likely caused by an unknown step
+        Some(diag) if !suspect => state.recover(diag).map(|()| true),
+        Some(_) => Ok(true),
+        None => Ok(suspect),
     }
-    Ok(())
 }
 
 /// This is the main "VM" function, and responsible for ~30% of CPU time during
@@ -469,22 +551,16 @@ fn execute_step<P: ProofBuilder>(
     index: usize,
     explicit: Option<&mut Vec<Option<TokenPtr<'_>>>>,
 ) -> Result<()> {
+    state.step += 1;
     let fref = match *state
         .prepared
         .get(index)
         .ok_or(Diagnostic::StepOutOfRange)?
     {
-        Hyp(ref vars, code, ref expr, ref data) => {
+        Hyp(ref slot, ref data) => {
             // hypotheses/saved steps are the easy case.  unfortunately, this is
             // also a very unpredictable branch
-            state.stack.push((
-                data.clone(),
-                StackSlot {
-                    vars: vars.clone(),
-                    code,
-                    expr: expr.clone(),
-                },
-            ));
+            state.stack.push((data.clone(), slot.clone()));
             return Ok(());
         }
         Assert(fref) => fref,
@@ -503,6 +579,7 @@ fn execute_step<P: ProofBuilder>(
     }
 
     let mut datavec = Default::default();
+    let mut suspect = false;
 
     // This branch is optimized out of compressed proof processing
     if let Some(explicit_stack) = explicit {
@@ -524,7 +601,7 @@ fn execute_step<P: ProofBuilder>(
         }
         if in_order {
             for (ix, hyp) in fref.hypotheses.iter().enumerate() {
-                process_hyp(state, &mut datavec, fref, sbase + ix, hyp)?;
+                suspect = process_hyp(state, &mut datavec, fref, sbase + ix, hyp, suspect)?;
             }
         } else {
             // Otherwise, we need to reorder hypotheses
@@ -554,12 +631,13 @@ fn execute_step<P: ProofBuilder>(
             }
 
             for (ix, slot) in assn_hyps.iter().enumerate() {
-                process_hyp(
+                suspect = process_hyp(
                     state,
                     &mut datavec,
                     fref,
                     sbase + ix,
                     &fref.hypotheses[slot.unwrap()],
+                    suspect,
                 )?;
             }
         }
@@ -567,7 +645,7 @@ fn execute_step<P: ProofBuilder>(
         explicit_stack.truncate(sbase);
     } else {
         for (ix, hyp) in fref.hypotheses.iter().enumerate() {
-            process_hyp(state, &mut datavec, fref, sbase + ix, hyp)?;
+            suspect = process_hyp(state, &mut datavec, fref, sbase + ix, hyp, suspect)?;
         }
     }
 
@@ -597,21 +675,22 @@ fn execute_step<P: ProofBuilder>(
             code: fref.target.typecode,
             vars: do_substitute_vars(&fref.target.tail, &state.subst_info),
             expr: old_top..new_top,
+            suspect,
         },
     ));
 
     // check $d constraints on the used assertion now that the dust has settled.
     // Remember that we might have variable indexes allocated during the proof
     // that are out of range for dv_map
-    for &(ix1, ix2) in &*fref.mandatory_dv {
-        for var1 in &state.subst_info[ix1].1 {
-            for var2 in &state.subst_info[ix2].1 {
-                try_assert!(
-                    var1 < state.dv_map.len() && state.dv_map[var1].has_bit(var2),
-                    Diagnostic::ProofDvViolation
-                );
-            }
-        }
+    let dv_violated = fref.mandatory_dv.iter().any(|&(ix1, ix2)| {
+        (&state.subst_info[ix1].1).into_iter().any(|var1| {
+            (&state.subst_info[ix2].1)
+                .into_iter()
+                .any(|var2| var1 >= state.dv_map.len() || !state.dv_map[var1].has_bit(var2))
+        })
+    });
+    if dv_violated && !suspect {
+        state.recover(Diagnostic::ProofDvViolation)?;
     }
 
     Ok(())
@@ -621,6 +700,9 @@ fn finalize_step<P: ProofBuilder>(state: &mut VerifyState<'_, P>) -> Result<P::I
     // if we get here, it's a valid proof, but was it the _right_ valid proof?
     try_assert!(state.stack.len() <= 1, Diagnostic::ProofExcessEnd);
     let (data, tos) = state.stack.last().ok_or(Diagnostic::ProofNoSteps)?;
+    if tos.suspect {
+        return Ok(data.clone());
+    }
 
     try_assert!(
         tos.code == state.cur_frame.target.typecode,
@@ -643,12 +725,7 @@ fn save_step<P: ProofBuilder>(state: &mut VerifyState<'_, P>) {
         .stack
         .last()
         .expect("can_save should prevent getting here");
-    state.prepared.push(Hyp(
-        top.vars.clone(),
-        top.code,
-        top.expr.clone(),
-        data.clone(),
-    ));
+    state.prepared.push(Hyp(top.clone(), data.clone()));
 }
 
 // proofs are not self-synchronizing, so it's not likely to get >1 usable error
@@ -662,6 +739,11 @@ fn verify_proof<'a, P: ProofBuilder>(
     state.prepared.clear();
     state.var2bit.clear();
     state.dv_map = &state.cur_frame.optional_dv;
+    state.step = 0;
+    state.step_span = Span::NULL;
+    if let Some(recovered) = &mut state.recovered {
+        recovered.clear();
+    }
     // temp_buffer is cleared before use; subst_info should be overwritten
     // before use if scopeck is working correctly
 
@@ -700,12 +782,18 @@ fn verify_proof<'a, P: ProofBuilder>(
         // presumably due to branch overhead
         let mut k = 0usize;
         let mut can_save = false;
+        let mut step_start = 0;
         while i < stmt.proof_len() {
+            let span = stmt.proof_span(i);
             let chunk = stmt.proof_slice_at(i);
-            for &ch in chunk {
+            for (pos, &ch) in (span.start..).zip(chunk) {
+                if k == 0 {
+                    step_start = pos;
+                }
+                state.step_span = Span::new2(step_start, pos + 1);
                 if (b'A'..=b'T').contains(&ch) {
                     k = k * 20 + (ch - b'A') as usize;
-                    execute_step(state, k, None)?;
+                    execute_step(state, k, None).map_err(|diag| state.step_error(diag))?;
                     k = 0;
                     can_save = true;
                 } else if (b'U'..=b'Y').contains(&ch) {
@@ -721,7 +809,11 @@ fn verify_proof<'a, P: ProofBuilder>(
                     can_save = false;
                 } else if ch == b'?' {
                     try_assert!(k == 0, Diagnostic::ProofMalformedVarint);
-                    return Err(Diagnostic::ProofIncomplete);
+                    state.step += 1;
+                    state.recover(Diagnostic::ProofIncomplete)?;
+                    let unknown = state.unknown_step(stmt.address());
+                    state.stack.push(unknown);
+                    can_save = true;
                 }
             }
             i += 1;
@@ -737,16 +829,31 @@ fn verify_proof<'a, P: ProofBuilder>(
         for i in 0..stmt.proof_len() {
             let span = stmt.proof_span(i);
             let chunk = stmt.proof_slice_at(i);
-            try_assert!(chunk != b"?", Diagnostic::ProofIncomplete);
-            let step = prepare_step(state, chunk, Some(span))?;
+            state.step_span = span;
+            if chunk == b"?" {
+                state.step += 1;
+                state.recover(Diagnostic::ProofIncomplete)?;
+                let (data, slot) = state.unknown_step(stmt.address());
+                state.prepared.push(Hyp(slot.clone(), data.clone()));
+                state.stack.push((data, slot));
+                if let Some(explicit_stack) = &mut explicit_stack {
+                    explicit_stack.push(None);
+                }
+                count += 1;
+                continue;
+            }
+            let step =
+                prepare_step(state, chunk, Some(span)).map_err(|diag| state.step_error(diag))?;
             if let Some(label) = step.label {
                 try_assert!(step.fwdref.is_none(), Diagnostic::ChainBackref(span));
                 let &ix = backrefs
                     .get(label)
                     .ok_or_else(|| Diagnostic::StepMissing(label.into()))?;
-                execute_step(state, ix, explicit_stack.as_mut())?;
+                execute_step(state, ix, explicit_stack.as_mut())
+                    .map_err(|diag| state.step_error(diag))?;
             } else {
-                execute_step(state, count, explicit_stack.as_mut())?;
+                execute_step(state, count, explicit_stack.as_mut())
+                    .map_err(|diag| state.step_error(diag))?;
                 if let Some(fwdref) = step.fwdref {
                     state.prepared.pop();
                     save_step(state);
@@ -775,8 +882,13 @@ fn verify_proof<'a, P: ProofBuilder>(
 #[derive(Debug)]
 struct VerifySegment {
     source: Arc<Segment>,
-    scope_usage: ScopeUsage,
-    diagnostics: HashMap<StatementAddress, Diagnostic>,
+    /// The frames read during verification, or `None` if the result was
+    /// obtained from the persistent cache.
+    scope_usage: Option<ScopeUsage>,
+    diagnostics: Vec<(StatementAddress, Diagnostic)>,
+    /// The time it took to verify this segment, or `None` if the result was
+    /// obtained from the persistent cache.
+    verify_time: Option<Duration>,
 }
 
 /// Analysis pass result for the verifier.
@@ -791,12 +903,25 @@ impl VerifyResult {
     pub fn diagnostics(&self) -> Vec<(StatementAddress, Diagnostic)> {
         let mut out = Vec::new();
         for vsr in self.segments.values() {
-            for (&sa, diag) in &vsr.diagnostics {
+            for &(sa, ref diag) in &vsr.diagnostics {
                 out.push((sa, diag.clone()));
             }
         }
         out
     }
+
+    /// Returns the time it took to verify the given segment, or `None` if it
+    /// was not verified, or its result was obtained from the persistent cache.
+    pub(crate) fn verify_time(&self, id: SegmentId) -> Option<Duration> {
+        self.segments.get(&id).and_then(|vsr| vsr.verify_time)
+    }
+
+    /// Returns whether the given segment was verified without errors.
+    pub(crate) fn is_clean(&self, id: SegmentId) -> bool {
+        self.segments
+            .get(&id)
+            .is_some_and(|vsr| vsr.diagnostics.is_empty())
+    }
 }
 
 /// Driver which verifies each statement in a segment.
@@ -806,7 +931,8 @@ fn verify_segment(
     scopes: &ScopeResult,
     sid: SegmentId,
 ) -> VerifySegment {
-    let mut diagnostics = HashMap::default();
+    let now = Instant::now();
+    let mut diagnostics = Vec::new();
     let dummy_frame = Frame::default();
     let sref = sset.segment(sid);
     let mut state = VerifyState {
@@ -823,6 +949,9 @@ fn verify_segment(
         subst_info: Vec::new(),
         var2bit: HashMap::default(),
         dv_map: &dummy_frame.optional_dv,
+        step: 0,
+        step_span: Span::NULL,
+        recovered: sset.options.verify_recovery.then(Vec::new),
     };
     // use the _same_ VerifyState so that memory can be reused
     for stmt in sref {
@@ -832,8 +961,12 @@ fn verify_segment(
             // may wish to record a secondary error?
             if let Some(frame) = state.scoper.get(stmt.label()) {
                 state.cur_frame = frame;
-                if let Err(diag) = verify_proof(&mut state, stmt) {
-                    diagnostics.insert(stmt.address(), diag);
+                let result = verify_proof(&mut state, stmt);
+                if let Some(recovered) = &mut state.recovered {
+                    diagnostics.extend(recovered.drain(..).map(|diag| (stmt.address(), diag)));
+                }
+                if let Err(diag) = result {
+                    diagnostics.push((stmt.address(), diag));
                 }
             }
         }
@@ -841,18 +974,23 @@ fn verify_segment(
     VerifySegment {
         source: (*sref).clone(),
         diagnostics,
-        scope_usage: state.scoper.into_usage(),
+        scope_usage: Some(state.scoper.into_usage()),
+        verify_time: Some(now.elapsed()),
     }
 }
 
 /// Calculates or updates the verification result for a database.
+///
+/// Segments whose hash is in the given persistent cache are not verified again.
 pub(crate) fn verify(
     result: &mut VerifyResult,
     segments: &Arc<SegmentSet>,
     nset: &Arc<Nameset>,
     scope: &Arc<ScopeResult>,
+    cache: Option<&VerifyCache>,
 ) {
     let old = mem::take(&mut result.segments);
+    let hashes = (cache.map(|_| segment_hashes(segments, nset, scope))).unwrap_or_default();
     let mut ssrq = Vec::new();
     for sref in segments.segments(..) {
         let segments2 = segments.clone();
@@ -860,15 +998,31 @@ pub(crate) fn verify(
         let scope = scope.clone();
         let id = sref.id;
         let old_res_o = old.get(&id).cloned();
+        let cached =
+            (hashes.get(&id)).is_some_and(|hash| cache.is_some_and(|c| c.clean.contains(hash)));
         ssrq.push(segments.exec.exec(sref.bytes(), move || {
             let sref = segments2.segment(id);
             if let Some(old_res) = old_res_o {
-                if old_res.scope_usage.valid(&nset, &scope) && Arc::ptr_eq(&old_res.source, &sref) {
+                if (old_res.scope_usage.as_ref()).is_some_and(|usage| usage.valid(&nset, &scope))
+                    && Arc::ptr_eq(&old_res.source, &sref)
+                {
                     return (id, old_res);
                 }
             }
+            if cached {
+                let cached_res = VerifySegment {
+                    source: (*sref).clone(),
+                    scope_usage: None,
+                    diagnostics: Vec::new(),
+                    verify_time: None,
+                };
+                return (id, Arc::new(cached_res));
+            }
             if segments2.options.trace_recalc {
-                println!("verify({:?})", parser::guess_buffer_name(&sref.buffer));
+                log(
+                    &segments2.options,
+                    format_args!("verify({:?})", parser::guess_buffer_name(&sref.buffer)),
+                );
             }
             (id, Arc::new(verify_segment(&segments2, &nset, &scope, id)))
         }))
@@ -904,6 +1058,9 @@ impl Database {
             subst_info: Vec::new(),
             var2bit: HashMap::default(),
             dv_map: &dummy_frame.optional_dv,
+            step: 0,
+            step_span: Span::NULL,
+            recovered: None,
         };
 
         assert!(stmt.statement_type() == StatementType::Provable);
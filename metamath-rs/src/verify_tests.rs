@@ -0,0 +1,83 @@
+use crate::database::{Database, DbOptions};
+use crate::diag::Diagnostic;
+use crate::statement::Span;
+
+const ERRORS_DB: &[u8] = b"$c |- wff ( ) -> $.
+  $v ph ps ch $.
+  wph $f wff ph $.
+  wps $f wff ps $.
+  wch $f wff ch $.
+  wi $a wff ( ph -> ps ) $.
+  ${ min $e |- ph $. maj $e |- ( ph -> ps ) $. ax-mp $a |- ps $. $}
+  ax-1 $a |- ( ph -> ( ps -> ph ) ) $.
+  ${
+    h1 $e |- ph $.
+    h2 $e |- ( ph -> ch ) $.
+    th1 $p |- ( ps -> ch ) $=
+      wch wps wch wi wph wch h1 h1 ax-mp wps wch ax-1 ax-mp $.
+    th2 $p |- ( ps -> ch ) $= ( wi ax-mp ax-1 ) CBCFACEDGBCHG $.
+    th3 $p |- ( ps -> ch ) $= wch wps wch wi ? wch wps ax-1 ax-mp $.
+  $}
+";
+
+fn verify_errors(verify_recovery: bool) -> Vec<(String, Diagnostic)> {
+    let options = DbOptions {
+        incremental: true,
+        verify_recovery,
+        ..DbOptions::default()
+    };
+    let mut db = Database::new(options);
+    db.parse(
+        "test.mm".to_owned(),
+        vec![("test.mm".to_owned(), ERRORS_DB.to_owned())],
+    );
+    let mut errors = (db.verify_pass().diagnostics().into_iter())
+        .map(|(sa, diag)| {
+            let label = db.statement_by_address(sa).label();
+            (String::from_utf8_lossy(label).into_owned(), diag)
+        })
+        .collect::<Vec<_>>();
+    errors.sort_by(|a, b| a.0.cmp(&b.0));
+    errors
+}
+
+/// Returns an error at the given proof step, whose token is the first
+/// occurrence of `token` within the first occurrence of `context`.
+fn step(step: usize, context: &str, token: &str, diag: Diagnostic) -> Diagnostic {
+    let text = std::str::from_utf8(ERRORS_DB).unwrap();
+    let start = text.find(context).unwrap() + context.find(token).unwrap();
+    Diagnostic::ProofStep(step, Span::new(start, start + token.len()), Box::new(diag))
+}
+
+#[test]
+fn test_verify_first_error() {
+    assert_eq!(
+        verify_errors(false),
+        [
+            ("th1".to_owned(), Diagnostic::StepEssenWrong),
+            ("th2".to_owned(), Diagnostic::StepEssenWrong),
+            ("th3".to_owned(), Diagnostic::ProofIncomplete),
+        ]
+    );
+}
+
+#[test]
+fn test_verify_recovery() {
+    assert_eq!(
+        verify_errors(true),
+        [
+            (
+                "th1".to_owned(),
+                step(9, "h1 h1 ax-mp", "ax-mp", Diagnostic::StepEssenWrong)
+            ),
+            (
+                "th2".to_owned(),
+                step(9, "CBCFACEDG", "G", Diagnostic::StepEssenWrong)
+            ),
+            (
+                "th3".to_owned(),
+                step(5, "wi ? wch", "?", Diagnostic::ProofIncomplete)
+            ),
+        ]
+    );
+}